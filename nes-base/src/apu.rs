@@ -37,11 +37,12 @@ pub trait Apu {
     fn read_reg_status(&self) -> u8;
 
     fn clock(&mut self);
+    /// 当前的混音输出，范围 [0.0, 1.0]
+    fn output(&self) -> f32;
 
-    // 检查是否有 IRQ 中断请求
+    /// IRQ 输出线是否有效，电平触发
+    /// 帧 IRQ 在读取 $4015 或写入 $4017 时清除，DMC IRQ 在写入 $4010 或 $4015 时清除
     fn check_irq_interrupt(&self) -> bool;
-    // 清除 IRQ 中断请求
-    fn clear_irq_interrupt(&mut self);
 }
 
 pub struct ApuAdapterForCpuBus(pub Rc<RefCell<dyn Apu>>);
@@ -50,9 +51,9 @@ impl Reader for ApuAdapterForCpuBus {
     fn read(&self, addr: u16) -> u8 {
         match addr {
             0x4015 => self.0.borrow().read_reg_status(),
-            x => {
-                panic!("APU read from unsupported address: {:#04X}", x);
-            }
+            // 只写寄存器，实际读到的是开放总线，这里简化为 0
+            // CPU 变址写入时的空读会访问到这些地址
+            _ => 0,
         }
     }
 }
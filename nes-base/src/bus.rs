@@ -2,6 +2,11 @@ use std::{cell::RefCell, rc::Rc};
 
 pub trait Reader {
     fn read(&self, addr: u16) -> u8;
+    /// 不产生副作用的读取，供反汇编与调试器使用
+    /// 读取可能改变设备状态 (I/O 寄存器、Mapper 寄存器等) 时返回 None
+    fn peek(&self, _addr: u16) -> Option<u8> {
+        None
+    }
     fn read_u16(&self, addr: u16) -> u16 {
         let low = self.read(addr) as u16;
         let high = self.read(addr + 1) as u16;
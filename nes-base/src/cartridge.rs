@@ -5,17 +5,41 @@ use crate::{BusAdapter, Reader, Writer};
 pub trait Cartridge {
     fn cpu_read(&self, addr: u16) -> u8;
     fn cpu_write(&mut self, addr: u16, value: u8);
-    fn ppu_read(&self, addr: u16) -> u8;
+    /// 不产生副作用地读取 CPU 地址空间，只返回 PRG-ROM/PRG-RAM 中的数据，其他地址返回 None
+    fn peek(&self, addr: u16) -> Option<u8>;
+    /// PPU 读取可能改变卡带状态，例如 MMC2/MMC4 在读取特定图案时切换 CHR bank
+    fn ppu_read(&mut self, addr: u16) -> u8;
     fn ppu_write(&mut self, addr: u16, value: u8);
     fn mirroring(&self) -> Mirroring;
+
+    /// 每个 CPU 周期调用一次，驱动 Mapper 内部的计数器和扩展音频
+    fn clock(&mut self);
+    /// IRQ 输出线是否有效，电平触发，由卡带在寄存器读写时自行应答
+    fn check_irq_interrupt(&self) -> bool;
+    /// 扩展音频的输出，与 APU 输出处于同一量级，没有扩展音频时为 0
+    /// 由 BoardImpl 与 APU 的输出混音后送入音频输出通路
+    fn audio_output(&self) -> f32;
+
+    /// 名称表读取，返回 None 时按镜像方式读取 PPU 内部的 VRAM
+    /// MMC5 等 Mapper 可以用卡带上的 RAM 或填充数据代替名称表，并借此监听 PPU 的渲染进度
+    fn nametable_read(&mut self, addr: u16) -> Option<u8>;
+    /// 名称表写入，返回 false 时按镜像方式写入 PPU 内部的 VRAM
+    fn nametable_write(&mut self, addr: u16, value: u8) -> bool;
+    /// 监听 CPU 对 PPU 寄存器 [0x2000, 0x2007] 的写入
+    fn ppu_register_write(&mut self, addr: u16, value: u8);
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Mirroring {
     Horizontal,
     Vertical,
-    SingleScreen,
+    /// 单屏，所有名称表都映射到 VRAM 的前 1KB
+    SingleScreenLower,
+    /// 单屏，所有名称表都映射到 VRAM 的后 1KB
+    SingleScreenUpper,
     FourScreen,
+    /// 四个名称表分别映射到 VRAM 的第几个 1KB 页，用于 MMC5 这类可以任意组合的 Mapper
+    Mapped([u8; 4]),
 }
 
 pub struct CartridgeAdapterForCPUBus(pub Rc<RefCell<dyn Cartridge>>);
@@ -24,6 +48,10 @@ impl Reader for CartridgeAdapterForCPUBus {
     fn read(&self, addr: u16) -> u8 {
         self.0.borrow().cpu_read(addr)
     }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.0.borrow().peek(addr)
+    }
 }
 
 impl Writer for CartridgeAdapterForCPUBus {
@@ -19,6 +19,10 @@ pub struct CpuState {
     pub reg_sp: u8,
     pub reg_pc: u16,
     pub reg_status: CpuStatusFlags,
+    /// 执行 JAM 指令后停机，只有复位才能恢复
+    pub jammed: bool,
+    /// 已响应的 NMI 次数，包括劫持 BRK/IRQ 的 NMI，回绕计数
+    pub nmi_count: u32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -66,7 +70,12 @@ pub trait Cpu {
     fn reset(&mut self);
     fn attach_bus(&mut self, bus: Rc<RefCell<dyn BusAdapter>>);
     fn dump_state(&self) -> CpuState;
+    /// 写入 A/X/Y/SP/PC/P 寄存器，周期计数保持不变，供调试器修改寄存器
+    fn set_registers(&mut self, state: &CpuState);
     fn increase_cycles(&mut self, cycles: u32);
+    /// NMI 为边沿触发；IRQ 等同于 `set_irq_line(true)`，不会在响应后自动释放
     fn trigger_interrupt(&mut self, interrupt: Interrupt);
+    /// 设置电平触发的 IRQ 线，有效期间只要 I 标志清零就会响应
+    fn set_irq_line(&mut self, asserted: bool);
     fn clock(&mut self);
 }
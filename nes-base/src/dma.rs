@@ -8,8 +8,9 @@ pub struct DmaForCpuBus {
 }
 
 impl Reader for DmaForCpuBus {
-    fn read(&self, addr: u16) -> u8 {
-        panic!("DMA read from unsupported address: {:#04X}", addr)
+    fn read(&self, _: u16) -> u8 {
+        // $4014 只写，读取只可能来自 CPU 的空读
+        0
     }
 }
 
@@ -12,6 +12,10 @@ impl Reader for RamAdapterForCpuBus {
     fn read(&self, addr: u16) -> u8 {
         self.0.borrow().read(addr % 0x800)
     }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.read(addr))
+    }
 }
 
 impl Writer for RamAdapterForCpuBus {
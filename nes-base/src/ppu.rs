@@ -25,6 +25,8 @@ pub trait Ppu {
     fn reset(&mut self);
     fn clock(&mut self);
     fn attach_bus(&mut self, bus: Rc<RefCell<dyn BusAdapter>>);
+    /// 当前渲染位置 (扫描线, 点)
+    fn position(&self) -> (u16, u16);
 
     // 检查 NMI 中断是否被触发
     fn check_nmi_interrupt(&self) -> bool;
@@ -32,15 +34,20 @@ pub trait Ppu {
     fn clear_nmi_interrupt(&mut self);
 }
 
-pub struct PpuBusAdapterForCpuBus(pub Rc<RefCell<dyn Ppu>>);
+/// PPU 寄存器适配器，寄存器写入同时通知卡带，部分 Mapper (如 MMC5) 需要知道 PPU 的配置
+pub struct PpuBusAdapterForCpuBus {
+    pub ppu: Rc<RefCell<dyn Ppu>>,
+    pub cartridge: Rc<RefCell<dyn Cartridge>>,
+}
 
 impl Reader for PpuBusAdapterForCpuBus {
     fn read(&self, addr: u16) -> u8 {
         match (addr - 0x2000) % 8 {
-            2 => self.0.borrow().read_reg_status(),
-            4 => self.0.borrow().read_reg_oam_data(),
-            7 => self.0.borrow().read_reg_data(),
-            _ => panic!("PPU read from unsupported address: {:#04X}", addr),
+            2 => self.ppu.borrow().read_reg_status(),
+            4 => self.ppu.borrow().read_reg_oam_data(),
+            7 => self.ppu.borrow().read_reg_data(),
+            // 只写寄存器，实际读到的是 PPU 内部的开放总线锁存，这里简化为 0
+            _ => 0,
         }
     }
 }
@@ -48,15 +55,16 @@ impl Reader for PpuBusAdapterForCpuBus {
 impl Writer for PpuBusAdapterForCpuBus {
     fn write(&mut self, addr: u16, data: u8) {
         match (addr - 0x2000) % 8 {
-            0 => self.0.borrow_mut().write_reg_control(data),
-            1 => self.0.borrow_mut().write_reg_mask(data),
-            3 => self.0.borrow_mut().write_reg_oam_addr(data),
-            4 => self.0.borrow_mut().write_reg_oam_data(data),
-            5 => self.0.borrow_mut().write_reg_scroll(data),
-            6 => self.0.borrow_mut().write_reg_address(data),
-            7 => self.0.borrow_mut().write_reg_data(data),
+            0 => self.ppu.borrow_mut().write_reg_control(data),
+            1 => self.ppu.borrow_mut().write_reg_mask(data),
+            3 => self.ppu.borrow_mut().write_reg_oam_addr(data),
+            4 => self.ppu.borrow_mut().write_reg_oam_data(data),
+            5 => self.ppu.borrow_mut().write_reg_scroll(data),
+            6 => self.ppu.borrow_mut().write_reg_address(data),
+            7 => self.ppu.borrow_mut().write_reg_data(data),
             _ => panic!("PPU write to unsupported address: {:#04X}", addr),
         }
+        self.cartridge.borrow_mut().ppu_register_write(addr, data);
     }
 }
 
@@ -73,7 +81,7 @@ pub struct PatternTablesAdapterForPpuBus(pub Rc<RefCell<dyn Cartridge>>);
 
 impl Reader for PatternTablesAdapterForPpuBus {
     fn read(&self, addr: u16) -> u8 {
-        self.0.borrow().ppu_read(addr)
+        self.0.borrow_mut().ppu_read(addr)
     }
 }
 
@@ -96,9 +104,10 @@ impl BusAdapter for PatternTablesAdapterForPpuBus {
 /// 游戏画面256x240分辨率被分割成了32x30个图案块
 /// 每个图案块使用一个8x8点阵图案
 /// 名称表用于确定画面中的每个图案块是什么，使用哪个8x8点阵
+/// 镜像方式每次访问时从卡带获取，因为部分 Mapper 可以在运行时切换镜像方式
 pub struct NameTablesAdapterForPpuBus {
     pub vram: Rc<RefCell<dyn Ram>>,
-    pub mirroring: Mirroring,
+    pub cartridge: Rc<RefCell<dyn Cartridge>>,
 }
 
 impl NameTablesAdapterForPpuBus {
@@ -107,7 +116,7 @@ impl NameTablesAdapterForPpuBus {
         let base_addr = base_addr % 0x1000; // 将镜像地址进行映射到真正的数据区域
         let nametable_index = base_addr / 0x400; // 计算名称表索引 0,1,2,3
         let nametable_offset = base_addr % 0x400; // 计算名称表内的偏移地址
-        match self.mirroring {
+        match self.cartridge.borrow().mirroring() {
             Mirroring::Horizontal => {
                 // NT0 = NT2
                 // NT1 = NT3
@@ -120,20 +129,31 @@ impl NameTablesAdapterForPpuBus {
                 let mapping_index = if nametable_index < 2 { 0 } else { 1 };
                 mapping_index * 0x400 + nametable_offset
             }
-            Mirroring::SingleScreen => {
+            Mirroring::SingleScreenLower => {
                 // 所有名称表都映射到 NT0
                 nametable_offset
             }
+            Mirroring::SingleScreenUpper => {
+                // 所有名称表都映射到 NT1
+                0x400 + nametable_offset
+            }
             Mirroring::FourScreen => {
                 // 四屏模式下，所有名称表都独立
                 base_addr
             }
+            Mirroring::Mapped(pages) => {
+                // 由 Mapper 指定每个名称表对应的页
+                pages[nametable_index as usize] as u16 * 0x400 + nametable_offset
+            }
         }
     }
 }
 
 impl Reader for NameTablesAdapterForPpuBus {
     fn read(&self, addr: u16) -> u8 {
+        if let Some(value) = self.cartridge.borrow_mut().nametable_read(addr) {
+            return value;
+        }
         let mirrored_addr = self.mirror_address(addr);
         self.vram.borrow().read(mirrored_addr)
     }
@@ -141,6 +161,9 @@ impl Reader for NameTablesAdapterForPpuBus {
 
 impl Writer for NameTablesAdapterForPpuBus {
     fn write(&mut self, addr: u16, data: u8) {
+        if self.cartridge.borrow_mut().nametable_write(addr, data) {
+            return;
+        }
         let mirrored_addr = self.mirror_address(addr);
         self.vram.borrow_mut().write(mirrored_addr, data);
     }
@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+/// NTSC 主机的 CPU 时钟频率
+const CPU_CLOCK_RATE: u32 = 1_789_773;
+
+/// 默认输出采样率
+const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// 主板的音频输出通路
+///
+/// 每个 CPU 周期送入一次 APU 与卡带扩展音频的混音，按采样率取平均降采样后缓存，由前端取走播放。
+/// 缓冲区最多保留一秒的采样，前端来不及取走时丢弃最旧的采样
+pub struct AudioOutput {
+    sample_rate: u32,
+    /// 按采样率累加，超过 CPU 时钟频率时输出一个采样
+    phase: u32,
+    sum: f32,
+    count: u32,
+    samples: VecDeque<f32>,
+}
+
+impl AudioOutput {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            phase: 0,
+            sum: 0.0,
+            count: 0,
+            samples: VecDeque::with_capacity(sample_rate as usize),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 送入一个 CPU 周期的混音输出
+    pub fn push(&mut self, output: f32) {
+        self.sum += output;
+        self.count += 1;
+        self.phase += self.sample_rate;
+        if self.phase < CPU_CLOCK_RATE {
+            return;
+        }
+        self.phase -= CPU_CLOCK_RATE;
+        if self.samples.len() >= self.sample_rate as usize {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(self.sum / self.count as f32);
+        self.sum = 0.0;
+        self.count = 0;
+    }
+
+    /// 取走目前缓存的所有采样
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+}
+
+impl Default for AudioOutput {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
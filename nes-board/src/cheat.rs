@@ -0,0 +1,272 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use nes_base::{BusAdapter, Cartridge, Ram, Reader, Writer};
+
+/// Game Genie 使用的 16 个字母，按编码值排列
+const GAME_GENIE_LETTERS: &[u8; 16] = b"APZLGITYEOXUKSVN";
+
+/// 解析金手指代码时可能出现的错误
+#[derive(Debug)]
+pub enum CheatError {
+    /// 读取金手指文件失败
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// 无法识别的代码
+    InvalidCode(String),
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheatError::Io { path, source } => {
+                write!(f, "failed to read cheat file {}: {}", path, source)
+            }
+            CheatError::InvalidCode(code) => write!(f, "invalid cheat code: {}", code),
+        }
+    }
+}
+
+impl std::error::Error for CheatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheatError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// 金手指的作用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatKind {
+    /// 修改 CPU 从卡带读到的值，compare 存在时只有原值相等才替换
+    Rom {
+        address: u16,
+        value: u8,
+        compare: Option<u8>,
+    },
+    /// 每帧把 [0x0000, 0x07FF] 中的值锁定
+    Ram { address: u16, value: u8 },
+}
+
+impl CheatKind {
+    /// 支持的格式：
+    /// - 6 或 8 个字母的 Game Genie 代码，例如 SXIOPO
+    /// - Pro Action Replay 风格的 6 位十六进制 RAM 代码 AAAAVV
+    /// - AAAA:VV 与 AAAA?CC:VV，按地址区分 RAM 与卡带
+    pub fn parse(code: &str) -> Result<Self, CheatError> {
+        let code = code.trim().to_ascii_uppercase();
+        let invalid = || CheatError::InvalidCode(code.clone());
+        if let Some(kind) = Self::decode_game_genie(&code) {
+            return Ok(kind);
+        }
+
+        let hex = |s: &str| u16::from_str_radix(s, 16).map_err(|_| invalid());
+        let (address, compare, value) = if let Some((address, value)) = code.split_once(':') {
+            match address.split_once('?') {
+                Some((address, compare)) => (hex(address)?, Some(hex(compare)?), hex(value)?),
+                None => (hex(address)?, None, hex(value)?),
+            }
+        } else if code.len() == 6 {
+            (hex(&code[..4])?, None, hex(&code[4..])?)
+        } else {
+            return Err(invalid());
+        };
+        let value = u8::try_from(value).map_err(|_| invalid())?;
+        let compare = compare
+            .map(|compare| u8::try_from(compare).map_err(|_| invalid()))
+            .transpose()?;
+
+        match address {
+            0x0000..=0x07FF if compare.is_none() => Ok(CheatKind::Ram { address, value }),
+            0x8000..=0xFFFF => Ok(CheatKind::Rom {
+                address,
+                value,
+                compare,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// 每个字母对应 4 位，按位重新排列得到地址、值与比较值
+    fn decode_game_genie(code: &str) -> Option<Self> {
+        if code.len() != 6 && code.len() != 8 {
+            return None;
+        }
+        let n: Vec<u16> = code
+            .bytes()
+            .map(|c| {
+                GAME_GENIE_LETTERS
+                    .iter()
+                    .position(|&l| l == c)
+                    .map(|i| i as u16)
+            })
+            .collect::<Option<_>>()?;
+
+        let address = 0x8000
+            | ((n[3] & 7) << 12)
+            | ((n[4] & 8) << 8)
+            | ((n[5] & 7) << 8)
+            | ((n[1] & 8) << 4)
+            | ((n[2] & 7) << 4)
+            | (n[3] & 8)
+            | (n[4] & 7);
+        let value = ((n[0] & 8) << 4) | ((n[1] & 7) << 4) | (n[0] & 7);
+        if n.len() == 6 {
+            Some(CheatKind::Rom {
+                address,
+                value: (value | (n[5] & 8)) as u8,
+                compare: None,
+            })
+        } else {
+            let compare = ((n[6] & 8) << 4) | ((n[7] & 7) << 4) | (n[5] & 8) | (n[6] & 7);
+            Some(CheatKind::Rom {
+                address,
+                value: (value | (n[7] & 8)) as u8,
+                compare: Some(compare as u8),
+            })
+        }
+    }
+}
+
+/// 一条金手指
+#[derive(Debug, Clone)]
+pub struct Cheat {
+    pub code: String,
+    pub description: String,
+    pub kind: CheatKind,
+    pub enabled: bool,
+}
+
+/// 金手指列表，由主板在 CPU 读取卡带与每帧结束时应用
+#[derive(Default)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一条金手指，默认启用，返回其序号
+    pub fn add(&mut self, code: &str, description: &str) -> Result<usize, CheatError> {
+        let kind = CheatKind::parse(code)?;
+        self.cheats.push(Cheat {
+            code: code.trim().to_ascii_uppercase(),
+            description: description.trim().to_string(),
+            kind,
+            enabled: true,
+        });
+        Ok(self.cheats.len() - 1)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Cheat> {
+        (index < self.cheats.len()).then(|| self.cheats.remove(index))
+    }
+
+    /// 启用或禁用一条金手指，序号不存在时返回 false
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        match self.cheats.get_mut(index) {
+            Some(cheat) => {
+                cheat.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn list(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+    }
+
+    /// 从文本加载金手指，每行一条：代码，之后可以跟描述，# 开头的行为注释
+    /// 返回添加的数量，遇到无法识别的代码时停止
+    pub fn load_str(&mut self, text: &str) -> Result<usize, CheatError> {
+        let mut count = 0;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (code, description) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            self.add(code, description)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn load_file(&mut self, path: &str) -> Result<usize, CheatError> {
+        let text = std::fs::read_to_string(path).map_err(|source| CheatError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        self.load_str(&text)
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &CheatKind> {
+        self.cheats
+            .iter()
+            .filter(|cheat| cheat.enabled)
+            .map(|cheat| &cheat.kind)
+    }
+
+    /// 对 CPU 从卡带读到的值应用 ROM 金手指
+    pub fn patch_read(&self, addr: u16, value: u8) -> u8 {
+        self.enabled()
+            .find_map(|kind| match *kind {
+                CheatKind::Rom {
+                    address,
+                    value: patched,
+                    compare,
+                } if address == addr && compare.is_none_or(|compare| compare == value) => {
+                    Some(patched)
+                }
+                _ => None,
+            })
+            .unwrap_or(value)
+    }
+
+    /// 把 RAM 金手指的值写入内存，每帧调用一次
+    pub fn apply_ram(&self, ram: &mut dyn Ram) {
+        for kind in self.enabled() {
+            if let CheatKind::Ram { address, value } = *kind {
+                ram.write(address, value);
+            }
+        }
+    }
+}
+
+/// 代替 CartridgeAdapterForCPUBus 连接到 CPU 总线，读取卡带时应用 ROM 金手指
+pub struct CheatCartridgeAdapterForCpuBus {
+    pub cartridge: Rc<RefCell<dyn Cartridge>>,
+    pub cheats: Rc<RefCell<Cheats>>,
+}
+
+impl Reader for CheatCartridgeAdapterForCpuBus {
+    fn read(&self, addr: u16) -> u8 {
+        let value = self.cartridge.borrow().cpu_read(addr);
+        self.cheats.borrow().patch_read(addr, value)
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        let value = self.cartridge.borrow().peek(addr)?;
+        Some(self.cheats.borrow().patch_read(addr, value))
+    }
+}
+
+impl Writer for CheatCartridgeAdapterForCpuBus {
+    fn write(&mut self, addr: u16, data: u8) {
+        self.cartridge.borrow_mut().cpu_write(addr, data);
+    }
+}
+
+impl BusAdapter for CheatCartridgeAdapterForCpuBus {
+    fn address_accept(&self, addr: u16) -> bool {
+        addr >= 0x4020
+    }
+}
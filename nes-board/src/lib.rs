@@ -1,12 +1,21 @@
 use std::{cell::RefCell, rc::Rc};
 
 use nes_base::{
-    ApuAdapterForCpuBus, Bus, BusAdapter, Cartridge, CartridgeAdapterForCPUBus, Cpu, DmaForCpuBus,
-    Interrupt, Joypad, JoypadAdapterForCpuBus, MirrorBusAdapterForPpuBus,
-    NameTablesAdapterForPpuBus, PalettesTablesAdapterForPpuBus, PatternTablesAdapterForPpuBus, Ppu,
-    PpuBusAdapterForCpuBus, Ram, RamAdapterForCpuBus,
+    ApuAdapterForCpuBus, Bus, BusAdapter, Cartridge, Cpu, DmaForCpuBus, Interrupt, Joypad,
+    JoypadAdapterForCpuBus, MirrorBusAdapterForPpuBus, NameTablesAdapterForPpuBus,
+    PalettesTablesAdapterForPpuBus, PatternTablesAdapterForPpuBus, Ppu, PpuBusAdapterForCpuBus,
+    Ram, RamAdapterForCpuBus,
 };
 
+mod audio;
+mod cheat;
+
+pub use audio::AudioOutput;
+pub use cheat::{Cheat, CheatCartridgeAdapterForCpuBus, CheatError, CheatKind, Cheats};
+
+/// PPU 进入 VBlank 的扫描线，此时一帧画面渲染结束
+const VBLANK_SCANLINE: u16 = 241;
+
 pub struct BoardImpl {
     pub cpu_bus: Rc<RefCell<dyn Bus>>,                 // CPU bus
     pub ppu_bus: Rc<RefCell<dyn Bus>>,                 // PPU bus
@@ -19,6 +28,8 @@ pub struct BoardImpl {
     pub cartridge: Rc<RefCell<dyn Cartridge>>,         // 游戏卡带
     pub joypad1: Option<Rc<RefCell<dyn Joypad>>>,      // 手柄1P
     pub joypad2: Option<Rc<RefCell<dyn Joypad>>>,      // 手柄2P
+    pub cheats: Rc<RefCell<Cheats>>,                   // 金手指
+    pub audio: Rc<RefCell<AudioOutput>>,               // 音频输出
 }
 
 impl BoardImpl {
@@ -40,7 +51,7 @@ impl BoardImpl {
             ))),
             Rc::new(RefCell::new(NameTablesAdapterForPpuBus {
                 vram: self.ppu_name_tables_ram.clone(),
-                mirroring: self.cartridge.borrow().mirroring(),
+                cartridge: self.cartridge.clone(),
             })),
             Rc::new(RefCell::new(PalettesTablesAdapterForPpuBus {
                 vram: self.ppu_palettes_tables_ram.clone(),
@@ -56,10 +67,14 @@ impl BoardImpl {
         // 连接各个设备到CPU总线上
         let cpu_bus_devices: [Rc<RefCell<dyn BusAdapter>>; 6] = [
             Rc::new(RefCell::new(RamAdapterForCpuBus(self.ram.clone()))),
-            Rc::new(RefCell::new(PpuBusAdapterForCpuBus(self.ppu.clone()))),
-            Rc::new(RefCell::new(CartridgeAdapterForCPUBus(
-                self.cartridge.clone(),
-            ))),
+            Rc::new(RefCell::new(PpuBusAdapterForCpuBus {
+                ppu: self.ppu.clone(),
+                cartridge: self.cartridge.clone(),
+            })),
+            Rc::new(RefCell::new(CheatCartridgeAdapterForCpuBus {
+                cartridge: self.cartridge.clone(),
+                cheats: self.cheats.clone(),
+            })),
             Rc::new(RefCell::new(JoypadAdapterForCpuBus {
                 joypad1: self.joypad1.clone(),
                 joypad2: self.joypad2.clone(),
@@ -84,6 +99,7 @@ impl BoardImpl {
     pub fn clock(&mut self) {
         self.cpu.borrow_mut().clock();
 
+        let scanline = self.ppu.borrow().position().0;
         for _ in 0..3 {
             // PPU 每个 CPU 时钟周期执行 3 次
             self.ppu.borrow_mut().clock();
@@ -93,12 +109,30 @@ impl BoardImpl {
                 self.ppu.borrow_mut().clear_nmi_interrupt();
             }
         }
+        // 一帧画面结束时重新锁定 RAM 金手指
+        let now = self.ppu.borrow().position().0;
+        if now != scanline && now == VBLANK_SCANLINE {
+            self.apply_ram_cheats();
+        }
 
         self.apu.borrow_mut().clock();
-        if self.apu.borrow().check_irq_interrupt() {
-            // 如果 APU 检测到 IRQ 中断，则触发 CPU 的 IRQ 中断
-            self.cpu.borrow_mut().trigger_interrupt(Interrupt::Irq);
-            self.apu.borrow_mut().clear_irq_interrupt();
-        }
+        self.cartridge.borrow_mut().clock();
+        let output = self.audio_output();
+        self.audio.borrow_mut().push(output);
+
+        // IRQ 线为线与，APU 与卡带任一方拉低都有效，由各自在寄存器读写时应答
+        let irq = self.apu.borrow().check_irq_interrupt()
+            || self.cartridge.borrow().check_irq_interrupt();
+        self.cpu.borrow_mut().set_irq_line(irq);
+    }
+
+    /// 锁定 RAM 金手指的值，PPU 每帧进入 VBlank 时由 clock 调用
+    pub fn apply_ram_cheats(&mut self) {
+        self.cheats.borrow().apply_ram(&mut *self.ram.borrow_mut());
+    }
+
+    /// 当前的音频输出，APU 与卡带扩展音频的混音
+    pub fn audio_output(&self) -> f32 {
+        self.apu.borrow().output() + self.cartridge.borrow().audio_output()
     }
 }
@@ -35,6 +35,13 @@ impl Reader for BusImpl {
         }
         panic!("Address out of range: 0x{:04X}", address);
     }
+
+    fn peek(&self, address: u16) -> Option<u8> {
+        self.devices
+            .iter()
+            .find(|device| device.borrow().address_accept(address))
+            .and_then(|device| device.borrow().peek(address))
+    }
 }
 
 impl Writer for BusImpl {
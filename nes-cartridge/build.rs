@@ -0,0 +1,102 @@
+//! 从 NES 2.0 XML 数据库生成内置的游戏数据库
+//!
+//! 默认读取 data/nes20db.xml，可以用环境变量 NES20DB_XML 指定完整的 nes20db.xml。
+//! 生成的 game_database.rs 按 CRC32 排序，由 src/database.rs 通过 include! 引入。
+//!
+//! see: https://forums.nesdev.org/viewtopic.php?t=19940
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+struct Game {
+    title: String,
+    region: &'static str,
+    crc32: u32,
+    sha1: String,
+    mapper_id: u8,
+    mirroring: &'static str,
+    has_battery_backed: bool,
+}
+
+/// 取出元素 `<name .../>` 的属性值
+fn attribute<'a>(game: &'a str, element: &str, name: &str) -> Option<&'a str> {
+    let start = game.find(&format!("<{element} "))?;
+    let tag = &game[start..start + game[start..].find('>')?];
+    let value_start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let value_end = value_start + tag[value_start..].find('"')?;
+    Some(&tag[value_start..value_end])
+}
+
+/// 标题取自注释中的文件名，去掉目录、扩展名以及 "(USA)" 之类的标签
+fn title(game: &str) -> Option<String> {
+    let start = game.find("<!--")? + 4;
+    let comment = game[start..start + game[start..].find("-->")?].trim();
+    let name = comment.rsplit(['\\', '/']).next()?;
+    let name = name.strip_suffix(".nes").unwrap_or(name);
+    let name = name.find(" (").map_or(name, |end| &name[..end]);
+    Some(name.trim().to_string())
+}
+
+fn parse_game(game: &str) -> Option<Game> {
+    let crc32 = u32::from_str_radix(attribute(game, "rom", "crc32")?, 16).ok()?;
+    let sha1 = attribute(game, "rom", "sha1")?.to_ascii_uppercase();
+    // Mapper 编号超过 255 的记录无法用 iNES 1.0 的字段表示
+    let mapper_id = attribute(game, "pcb", "mapper")?.parse().ok()?;
+    let mirroring = match attribute(game, "pcb", "mirroring") {
+        Some("V") => "Vertical",
+        Some("4") => "FourScreen",
+        _ => "Horizontal",
+    };
+    let region = match attribute(game, "console", "region") {
+        Some("1") => "Pal",
+        Some("2") => "Multiple",
+        Some("3") => "Dendy",
+        _ => "Ntsc",
+    };
+    Some(Game {
+        title: title(game).unwrap_or_default(),
+        region,
+        crc32,
+        sha1,
+        mapper_id,
+        mirroring,
+        has_battery_backed: attribute(game, "pcb", "battery") == Some("1"),
+    })
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=NES20DB_XML");
+    let path = env::var_os("NES20DB_XML")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("data/nes20db.xml"));
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let xml = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+    let mut games: Vec<Game> = xml
+        .split("<game>")
+        .skip(1)
+        .filter_map(|game| parse_game(&game[..game.find("</game>")?]))
+        .collect();
+    games.sort_by(|a, b| (a.crc32, &a.sha1).cmp(&(b.crc32, &b.sha1)));
+
+    let mut out = String::from("static GAME_DATABASE: &[GameInfo] = &[\n");
+    for game in &games {
+        writeln!(
+            out,
+            "    GameInfo {{ title: {:?}, region: Region::{}, crc32: 0x{:08X}, sha1: {:?}, \
+             mapper_id: {}, mirroring: Mirroring::{}, has_battery_backed: {} }},",
+            game.title,
+            game.region,
+            game.crc32,
+            game.sha1,
+            game.mapper_id,
+            game.mirroring,
+            game.has_battery_backed
+        )
+        .unwrap();
+    }
+    out.push_str("];\n");
+
+    let out_path = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("game_database.rs");
+    fs::write(out_path, out).unwrap();
+}
@@ -0,0 +1,40 @@
+use nes_base::Mirroring;
+
+use crate::RomHash;
+
+/// 游戏的运行制式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Ntsc,
+    Pal,
+    Dendy,
+    /// 同时兼容多种制式
+    Multiple,
+}
+
+/// 游戏数据库中的一条记录，字段对应 NES 2.0 XML 数据库中的 <game> 节点
+#[derive(Debug)]
+pub struct GameInfo {
+    pub title: &'static str,
+    pub region: Region,
+    /// PRG-ROM + CHR-ROM 的 CRC32
+    pub crc32: u32,
+    /// PRG-ROM + CHR-ROM 的 SHA-1，十六进制大写
+    pub sha1: &'static str,
+    pub mapper_id: u8,
+    pub mirroring: Mirroring,
+    pub has_battery_backed: bool,
+}
+
+// 内置的游戏数据库，由 build.rs 从 NES 2.0 XML 数据库生成，按 CRC32 排序
+include!(concat!(env!("OUT_DIR"), "/game_database.rs"));
+
+/// 按校验值查找游戏，CRC32 相同时再比较 SHA-1 以排除碰撞
+pub fn lookup(hash: &RomHash) -> Option<&'static GameInfo> {
+    let sha1 = hash.sha1_hex();
+    let start = GAME_DATABASE.partition_point(|game| game.crc32 < hash.crc32);
+    GAME_DATABASE[start..]
+        .iter()
+        .take_while(|game| game.crc32 == hash.crc32)
+        .find(|game| game.sha1 == sha1)
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+/// 加载 ROM 时可能出现的错误
+#[derive(Debug)]
+pub enum RomError {
+    /// 读取 ROM 文件失败
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// 文件头标识不是 "NES\x1A"
+    InvalidMagic([u8; 4]),
+    /// 文件长度不足以容纳文件头声明的数据
+    Truncated { expected: usize, actual: usize },
+    /// 没有 PRG-ROM bank
+    NoPrgRom,
+    /// 不支持的 Mapper，附带板名与支持的 Mapper 列表以便诊断
+    UnsupportedMapper {
+        mapper_id: u8,
+        submapper: u8,
+        board: Option<String>,
+        supported: Vec<String>,
+    },
+    /// 不支持的 UNIF 板名
+    UnsupportedBoard(String),
+    /// UNIF 文件缺少必需的块
+    MissingChunk(&'static str),
+    /// FDS 磁盘镜像的大小不是整数面
+    InvalidDiskImage(usize),
+    /// FDS BIOS 的大小不是 8KB
+    InvalidBiosSize(usize),
+    /// 应用补丁失败
+    Patch(PatchError),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::Io { path, source } => {
+                write!(f, "failed to read ROM file {}: {}", path, source)
+            }
+            RomError::InvalidMagic(magic) => {
+                write!(f, "invalid NES file magic number: {:02X?}", magic)
+            }
+            RomError::Truncated { expected, actual } => write!(
+                f,
+                "ROM file is truncated: expected at least {} bytes, got {}",
+                expected, actual
+            ),
+            RomError::NoPrgRom => write!(f, "NES file must have at least one PRG-ROM bank"),
+            RomError::UnsupportedMapper {
+                mapper_id,
+                submapper,
+                board,
+                supported,
+            } => {
+                write!(f, "unsupported mapper {}.{}", mapper_id, submapper)?;
+                if let Some(board) = board {
+                    write!(f, " ({})", board)?;
+                }
+                write!(f, "; supported mappers: {}", supported.join(", "))
+            }
+            RomError::UnsupportedBoard(board) => write!(f, "unsupported UNIF board: {}", board),
+            RomError::MissingChunk(id) => write!(f, "UNIF file has no {} chunk", id),
+            RomError::InvalidDiskImage(len) => {
+                write!(
+                    f,
+                    "FDS disk image size {} is not a whole number of sides",
+                    len
+                )
+            }
+            RomError::InvalidBiosSize(len) => {
+                write!(f, "FDS BIOS must be 8192 bytes, got {}", len)
+            }
+            RomError::Patch(e) => write!(f, "failed to apply patch: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RomError::Io { source, .. } => Some(source),
+            RomError::Patch(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// 恢复卡带存档状态时可能出现的错误
+#[derive(Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// 数据提前结束
+    UnexpectedEnd,
+    /// 读取完成后还有多余的数据
+    TrailingData(usize),
+    /// 存档来自使用其他 Mapper 的卡带
+    MapperMismatch { expected: u8, actual: u8 },
+    /// 字段的值不合法
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::UnexpectedEnd => write!(f, "state data ended unexpectedly"),
+            StateError::TrailingData(len) => {
+                write!(f, "state data has {} unexpected trailing bytes", len)
+            }
+            StateError::MapperMismatch { expected, actual } => write!(
+                f,
+                "state was saved by mapper {}, but the cartridge uses mapper {}",
+                actual, expected
+            ),
+            StateError::InvalidValue(name) => write!(f, "invalid value for {} in state data", name),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// 应用 IPS/BPS/UPS 补丁时可能出现的错误
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// 无法识别的补丁格式
+    UnknownFormat,
+    /// 补丁数据提前结束
+    UnexpectedEnd,
+    /// 补丁内容不合法，例如偏移量越界
+    Malformed(&'static str),
+    /// 补丁要求的源文件大小与 ROM 不一致
+    SourceSizeMismatch { expected: usize, actual: usize },
+    /// CRC32 校验失败，which 为 "source"、"target" 或 "patch"
+    ChecksumMismatch {
+        which: &'static str,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::UnknownFormat => write!(f, "unknown patch format"),
+            PatchError::UnexpectedEnd => write!(f, "patch data ended unexpectedly"),
+            PatchError::Malformed(reason) => write!(f, "malformed patch: {}", reason),
+            PatchError::SourceSizeMismatch { expected, actual } => write!(
+                f,
+                "patch expects a {} byte source file, got {} bytes",
+                expected, actual
+            ),
+            PatchError::ChecksumMismatch {
+                which,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} CRC32 mismatch: expected {:08X}, got {:08X}",
+                which, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl From<PatchError> for RomError {
+    fn from(e: PatchError) -> Self {
+        RomError::Patch(e)
+    }
+}
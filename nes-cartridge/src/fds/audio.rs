@@ -0,0 +1,227 @@
+/// 主音量 2/2、2/3、2/4、2/5，以 1152 为分母，使最大输出为 63
+const MASTER_VOLUME: [u32; 4] = [36, 24, 17, 14];
+/// 调制表的值对调制计数器的影响，4 表示清零
+const MOD_ADJUST: [i8; 8] = [0, 1, 2, 4, 0, -4, -2, -1];
+
+/// 音量包络与调制包络，$4080/$4084
+#[derive(Default)]
+struct Envelope {
+    speed: u8,
+    gain: u8,
+    increase: bool,
+    disabled: bool,
+    timer: u32,
+}
+
+impl Envelope {
+    fn write(&mut self, value: u8, master_speed: u8) {
+        self.speed = value & 0x3F;
+        self.increase = value & 0x40 != 0;
+        self.disabled = value & 0x80 != 0;
+        if self.disabled {
+            self.gain = self.speed;
+        }
+        self.reset_timer(master_speed);
+    }
+
+    fn reset_timer(&mut self, master_speed: u8) {
+        self.timer = 8 * (self.speed as u32 + 1) * master_speed as u32;
+    }
+
+    fn clock(&mut self, master_speed: u8) {
+        if self.disabled || master_speed == 0 {
+            return;
+        }
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer == 0 {
+            self.reset_timer(master_speed);
+            if self.increase && self.gain < 32 {
+                self.gain += 1;
+            } else if !self.increase && self.gain > 0 {
+                self.gain -= 1;
+            }
+        }
+    }
+}
+
+/// FDS 扩展音频：一个 64 步、6 位精度的波表声道，带有频率调制单元
+pub(crate) struct FdsAudio {
+    wave_table: [u8; 64],
+    /// $4089 bit7，置位时可以写入波表，声道保持当前输出
+    wave_write: bool,
+    wave_halt: bool,
+    envelopes_disabled: bool,
+    wave_frequency: u16,
+    wave_accumulator: u32,
+    wave_position: u8,
+    volume: Envelope,
+    modulation: Envelope,
+    mod_frequency: u16,
+    mod_halt: bool,
+    mod_table: [u8; 64],
+    mod_position: u8,
+    mod_accumulator: u32,
+    /// 7 位有符号的调制计数器
+    mod_counter: i8,
+    master_volume: u8,
+    master_envelope_speed: u8,
+    output: u8,
+}
+
+impl FdsAudio {
+    pub fn new() -> Self {
+        Self {
+            wave_table: [0; 64],
+            wave_write: false,
+            wave_halt: true,
+            envelopes_disabled: false,
+            wave_frequency: 0,
+            wave_accumulator: 0,
+            wave_position: 0,
+            volume: Envelope::default(),
+            modulation: Envelope::default(),
+            mod_frequency: 0,
+            mod_halt: true,
+            mod_table: [0; 64],
+            mod_position: 0,
+            mod_accumulator: 0,
+            mod_counter: 0,
+            master_volume: 0,
+            master_envelope_speed: 0xE8,
+            output: 0,
+        }
+    }
+
+    /// $4040-$407F 读取波表，$4090/$4092 读取包络增益
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x4040..=0x407F => self.wave_table[addr as usize - 0x4040] | 0x40,
+            0x4090 => self.volume.gain | 0x40,
+            0x4092 => self.modulation.gain | 0x40,
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4040..=0x407F if self.wave_write => {
+                self.wave_table[addr as usize - 0x4040] = value & 0x3F;
+            }
+            0x4080 => self.volume.write(value, self.master_envelope_speed),
+            0x4082 => self.wave_frequency = (self.wave_frequency & 0x0F00) | value as u16,
+            0x4083 => {
+                self.wave_frequency = (self.wave_frequency & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.wave_halt = value & 0x80 != 0;
+                self.envelopes_disabled = value & 0x40 != 0;
+                if self.wave_halt {
+                    self.wave_accumulator = 0;
+                    self.wave_position = 0;
+                }
+                if self.envelopes_disabled {
+                    self.volume.reset_timer(self.master_envelope_speed);
+                    self.modulation.reset_timer(self.master_envelope_speed);
+                }
+            }
+            0x4084 => self.modulation.write(value, self.master_envelope_speed),
+            0x4085 => {
+                // 7 位补码符号扩展
+                self.mod_counter = ((value << 1) as i8) >> 1;
+            }
+            0x4086 => self.mod_frequency = (self.mod_frequency & 0x0F00) | value as u16,
+            0x4087 => {
+                self.mod_frequency = (self.mod_frequency & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.mod_halt = value & 0x80 != 0;
+                if self.mod_halt {
+                    self.mod_accumulator = 0;
+                }
+            }
+            // 调制单元停止时才能写入，每次写入占用两个位置
+            0x4088 if self.mod_halt => {
+                let position = self.mod_position as usize;
+                self.mod_table[position] = value & 0x07;
+                self.mod_table[(position + 1) & 0x3F] = value & 0x07;
+                self.mod_position = (self.mod_position + 2) & 0x3F;
+            }
+            0x4089 => {
+                self.wave_write = value & 0x80 != 0;
+                self.master_volume = value & 0x03;
+            }
+            0x408A => self.master_envelope_speed = value,
+            _ => {}
+        }
+    }
+
+    /// 调制单元对波表频率的偏移量
+    fn pitch_offset(&self) -> i32 {
+        let counter = self.mod_counter as i32;
+        let mut temp = counter * self.modulation.gain as i32;
+        let remainder = temp & 0x0F;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            temp += if counter < 0 { -1 } else { 2 };
+        }
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+        let temp = self.wave_frequency as i32 * temp;
+        let remainder = temp & 0x3F;
+        (temp >> 6) + (remainder >= 32) as i32
+    }
+
+    fn clock_modulator(&mut self) {
+        if self.mod_halt || self.mod_frequency == 0 {
+            return;
+        }
+        self.mod_accumulator += self.mod_frequency as u32;
+        if self.mod_accumulator >= 0x10000 {
+            self.mod_accumulator -= 0x10000;
+            let value = self.mod_table[self.mod_position as usize];
+            if value == 4 {
+                self.mod_counter = 0;
+            } else {
+                let counter = self.mod_counter as i16 + MOD_ADJUST[value as usize] as i16;
+                self.mod_counter = match counter {
+                    64.. => counter - 128,
+                    ..-64 => counter + 128,
+                    _ => counter,
+                } as i8;
+            }
+            self.mod_position = (self.mod_position + 1) & 0x3F;
+        }
+    }
+
+    /// 每个 CPU 周期调用一次
+    pub fn clock(&mut self) {
+        if !self.wave_halt && !self.envelopes_disabled {
+            self.volume.clock(self.master_envelope_speed);
+            self.modulation.clock(self.master_envelope_speed);
+        }
+        self.clock_modulator();
+
+        // 写入波表时保持上一次的输出
+        if !self.wave_write {
+            let level =
+                self.volume.gain.min(32) as u32 * MASTER_VOLUME[self.master_volume as usize];
+            self.output =
+                (self.wave_table[self.wave_position as usize] as u32 * level / 1152) as u8;
+        }
+        if self.wave_halt || self.wave_write {
+            return;
+        }
+        let pitch = self.wave_frequency as i32 + self.pitch_offset();
+        if pitch > 0 {
+            self.wave_accumulator += pitch as u32;
+            if self.wave_accumulator > 0xFFFF {
+                self.wave_accumulator &= 0xFFFF;
+                self.wave_position = (self.wave_position + 1) & 0x3F;
+            }
+        }
+    }
+
+    /// 当前输出，0 到 63
+    pub fn output(&self) -> u8 {
+        self.output
+    }
+}
@@ -0,0 +1,319 @@
+use std::cell::Cell;
+
+use crate::RomError;
+
+/// .fds 文件中每一面的大小，不含 CRC 与间隙
+const SIDE_SIZE: usize = 65500;
+/// QD 镜像中每一面的大小，每个块后带有 2 字节 CRC
+const QD_SIDE_SIZE: usize = 0x10000;
+/// .fds 文件的可选文件头
+const HEADER_SIZE: usize = 16;
+const HEADER_MAGIC: &[u8] = b"FDS\x1A";
+
+/// 磁盘开头的间隙，约 28300 位
+const LEADING_GAP: usize = 28300 / 8;
+/// 块之间的间隙，约 976 位
+const BLOCK_GAP: usize = 976 / 8;
+/// 转换后的每一面至少这么长，保证磁头走完一面的时间接近真实磁盘
+const RAW_SIDE_SIZE: usize = 0x10000 + LEADING_GAP;
+/// 间隙结束标记，之后紧跟块数据
+const GAP_END: u8 = 0x80;
+
+/// 磁盘约 96.4kbit/s，CPU 时钟 1.79MHz，每传输一个字节约 149 个 CPU 周期
+const BYTE_CYCLES: u16 = 149;
+/// 磁头回到起点后，开始读取前需要等待的周期数
+const REWIND_CYCLES: u16 = 50000;
+
+/// FDS 的 CRC 为 CRC-16/KERMIT，计算范围包括间隙结束标记 $80
+fn crc_update(crc: u16, byte: u8) -> u16 {
+    (0..8).fold(crc ^ byte as u16, |crc, _| {
+        if crc & 1 != 0 {
+            (crc >> 1) ^ 0x8408
+        } else {
+            crc >> 1
+        }
+    })
+}
+
+/// 一张 FDS 磁盘，每一面都被转换成磁头实际读到的字节流：
+/// 开头的间隙、每个块前的 $80 标记、块数据、CRC 以及块之间的间隙
+pub struct FdsDisk {
+    sides: Vec<Vec<u8>>,
+}
+
+impl FdsDisk {
+    /// 从文件路径加载 .fds 或 QD 镜像
+    pub fn from_file(path: &str) -> Result<Self, RomError> {
+        let bytes = std::fs::read(path).map_err(|source| RomError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Self::new(&bytes)
+    }
+
+    /// 解析 .fds（可带 fwNES 文件头）或 QD 镜像，按文件大小区分
+    pub fn new(bytes: &[u8]) -> Result<Self, RomError> {
+        let data = if bytes.starts_with(HEADER_MAGIC) {
+            bytes.get(HEADER_SIZE..).unwrap_or_default()
+        } else {
+            bytes
+        };
+        let (side_size, crc_len) = if !data.is_empty() && data.len() % SIDE_SIZE == 0 {
+            (SIDE_SIZE, 0)
+        } else if !data.is_empty() && data.len() % QD_SIDE_SIZE == 0 {
+            (QD_SIDE_SIZE, 2)
+        } else {
+            return Err(RomError::InvalidDiskImage(data.len()));
+        };
+        Ok(Self {
+            sides: data
+                .chunks_exact(side_size)
+                .map(|side| Self::convert_side(side, crc_len))
+                .collect(),
+        })
+    }
+
+    /// 按块类型拆分一面的数据：1 磁盘信息，2 文件数量，3 文件头，4 文件数据
+    fn convert_side(side: &[u8], crc_len: usize) -> Vec<u8> {
+        let mut raw = vec![0; LEADING_GAP];
+        let mut position = 0;
+        let mut file_size = 0;
+        while let Some(&block_type) = side.get(position) {
+            let len = match block_type {
+                1 => 56,
+                2 => 2,
+                3 => 16,
+                4 => 1 + file_size,
+                _ => break,
+            };
+            let Some(block) = side.get(position..position + len) else {
+                break;
+            };
+            if block_type == 3 {
+                file_size = u16::from_le_bytes([block[13], block[14]]) as usize;
+            }
+            let crc = block
+                .iter()
+                .fold(crc_update(0, GAP_END), |crc, &b| crc_update(crc, b));
+            raw.push(GAP_END);
+            raw.extend_from_slice(block);
+            raw.extend_from_slice(&crc.to_le_bytes());
+            raw.extend(std::iter::repeat_n(0, BLOCK_GAP));
+            position += len + crc_len;
+        }
+        if raw.len() < RAW_SIDE_SIZE {
+            raw.resize(RAW_SIDE_SIZE, 0);
+        }
+        raw
+    }
+
+    /// 磁盘面数，双面磁盘的 A/B 面各算一面
+    pub fn side_count(&self) -> usize {
+        self.sides.len()
+    }
+}
+
+/// 磁盘驱动器，由 $4024-$4026 控制，通过 $4030-$4032 读取状态与数据
+pub(crate) struct DiskDrive {
+    disk: FdsDisk,
+    /// 当前插入的面，None 表示没有插入磁盘
+    side: Option<usize>,
+    /// 磁头在当前面上的位置
+    position: usize,
+    /// 距离下一次传输字节还需要的周期数
+    delay: u16,
+    motor_on: bool,
+    /// 为 true 时磁头保持在起点
+    transfer_reset: bool,
+    read_mode: bool,
+    crc_control: bool,
+    /// 开始读写，为 false 时等待间隙
+    transfer_start: bool,
+    irq_on_transfer: bool,
+    /// 磁头到达末尾，等待回到起点
+    end_of_head: bool,
+    /// 是否已经读到间隙结束标记
+    gap_ended: bool,
+    /// 写入时使用的 CRC
+    crc: u16,
+    read_data: Cell<u8>,
+    write_data: u8,
+    /// 一个字节传输完成，读取 $4030/$4031 或写入 $4024 时清除
+    transferred: Cell<bool>,
+    /// 传输 IRQ，读写数据或控制寄存器时应答
+    irq_pending: Cell<bool>,
+}
+
+impl DiskDrive {
+    pub fn new(disk: FdsDisk) -> Self {
+        let side = (disk.side_count() > 0).then_some(0);
+        Self {
+            disk,
+            side,
+            position: 0,
+            delay: 0,
+            motor_on: false,
+            transfer_reset: false,
+            read_mode: true,
+            crc_control: false,
+            transfer_start: false,
+            irq_on_transfer: false,
+            end_of_head: true,
+            gap_ended: false,
+            crc: 0,
+            read_data: Cell::new(0),
+            write_data: 0,
+            transferred: Cell::new(false),
+            irq_pending: Cell::new(false),
+        }
+    }
+
+    pub fn side_count(&self) -> usize {
+        self.disk.side_count()
+    }
+
+    pub fn side(&self) -> Option<usize> {
+        self.side
+    }
+
+    /// 插入磁盘的某一面，换面前 BIOS 需要看到磁盘被取出
+    pub fn insert(&mut self, side: usize) {
+        if side < self.disk.side_count() {
+            self.side = Some(side);
+            self.end_of_head = true;
+        }
+    }
+
+    pub fn eject(&mut self) {
+        self.side = None;
+    }
+
+    /// $4024 写入数据
+    pub fn write_data(&mut self, value: u8) {
+        self.write_data = value;
+        self.transferred.set(false);
+        self.irq_pending.set(false);
+    }
+
+    /// $4025 控制寄存器
+    pub fn write_control(&mut self, value: u8) {
+        self.motor_on = value & 0x01 != 0;
+        self.transfer_reset = value & 0x02 != 0;
+        self.read_mode = value & 0x04 != 0;
+        self.crc_control = value & 0x10 != 0;
+        self.transfer_start = value & 0x40 != 0;
+        self.irq_on_transfer = value & 0x80 != 0;
+        self.irq_pending.set(false);
+        if !self.transfer_start {
+            self.gap_ended = false;
+        }
+    }
+
+    /// $4030 状态中与磁盘有关的位，读取后清除传输标志
+    pub fn read_status(&self) -> u8 {
+        let mut status = 0;
+        if self.transferred.get() {
+            status |= 0x02;
+        }
+        if self.end_of_head {
+            status |= 0x40;
+        }
+        self.transferred.set(false);
+        self.irq_pending.set(false);
+        status
+    }
+
+    /// $4031 读取数据
+    pub fn read_data(&self) -> u8 {
+        self.transferred.set(false);
+        self.irq_pending.set(false);
+        self.read_data.get()
+    }
+
+    /// $4032 驱动器状态：bit0 未插入磁盘，bit1 未就绪，bit2 写保护
+    pub fn read_drive_status(&self) -> u8 {
+        match self.side {
+            None => 0x07,
+            Some(_) if !self.motor_on || self.end_of_head => 0x02,
+            Some(_) => 0x00,
+        }
+    }
+
+    /// 传输 IRQ 是否有效
+    pub fn irq_pending(&self) -> bool {
+        self.irq_pending.get()
+    }
+
+    /// 每个 CPU 周期调用一次，磁头按固定速度经过磁盘
+    pub fn clock(&mut self) {
+        let Some(side) = self.side else {
+            return;
+        };
+        if !self.motor_on {
+            self.end_of_head = true;
+            return;
+        }
+        if self.transfer_reset && self.end_of_head {
+            return;
+        }
+        if self.end_of_head {
+            // 回到起点
+            self.end_of_head = false;
+            self.position = 0;
+            self.gap_ended = false;
+            self.delay = REWIND_CYCLES;
+            return;
+        }
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        let raw = &mut self.disk.sides[side];
+        let mut transfer = false;
+        if self.read_mode {
+            let byte = raw[self.position];
+            if !self.transfer_start {
+                self.gap_ended = false;
+            } else if !self.gap_ended {
+                if byte == GAP_END {
+                    self.gap_ended = true;
+                }
+            } else {
+                self.read_data.set(byte);
+                transfer = true;
+            }
+        } else if self.transfer_start {
+            // 写入模式，CRC 控制位置位时写出累计的 CRC
+            let byte = if self.crc_control {
+                let [low, high] = self.crc.to_le_bytes();
+                self.crc = high as u16;
+                low
+            } else {
+                self.write_data
+            };
+            if byte == GAP_END && !self.gap_ended {
+                self.gap_ended = true;
+                self.crc = crc_update(0, GAP_END);
+            } else if self.gap_ended && !self.crc_control {
+                self.crc = crc_update(self.crc, byte);
+            }
+            raw[self.position] = byte;
+            transfer = true;
+        } else {
+            raw[self.position] = 0;
+        }
+
+        if transfer {
+            self.transferred.set(true);
+            self.irq_pending.set(self.irq_on_transfer);
+        }
+
+        self.position += 1;
+        if self.position >= raw.len() {
+            self.end_of_head = true;
+        } else {
+            self.delay = BYTE_CYCLES;
+        }
+    }
+}
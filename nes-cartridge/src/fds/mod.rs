@@ -0,0 +1,216 @@
+use std::cell::Cell;
+
+use nes_base::{Cartridge, Mirroring};
+
+use crate::RomError;
+
+mod audio;
+mod disk;
+
+pub use disk::FdsDisk;
+
+use audio::FdsAudio;
+use disk::DiskDrive;
+
+const BIOS_SIZE: usize = 0x2000; // 8KB
+const PRG_RAM_SIZE: usize = 0x8000; // 32KB
+const CHR_RAM_SIZE: usize = 0x2000; // 8KB
+/// 扩展音频最大输出 63，与其他扩展音频使用相同的量级
+const AUDIO_SCALE: f32 = 0.25 / 63.0;
+
+/// Famicom Disk System 的 RAM 适配器
+/// CPU: [0x6000, 0xDFFF] 32KB PRG-RAM，[0xE000, 0xFFFF] 8KB BIOS
+/// PPU: 8KB CHR-RAM，镜像方式由 $4025 bit3 控制
+/// 寄存器 [0x4020, 0x4026] 写入，[0x4030, 0x4033] 读取，[0x4040, 0x4092] 为扩展音频
+pub struct FdsCartridge {
+    bios: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr_ram: Vec<u8>,
+    drive: DiskDrive,
+    audio: FdsAudio,
+    mirroring: Mirroring,
+    /// $4023 bit0，关闭后磁盘寄存器与计时器 IRQ 都无效
+    disk_registers_enabled: bool,
+    /// $4023 bit1
+    sound_registers_enabled: bool,
+    irq_reload: u16,
+    irq_counter: u16,
+    irq_repeat: bool,
+    irq_enabled: bool,
+    /// 计时器 IRQ 标志，读取 $4030 时清除
+    timer_irq: Cell<bool>,
+}
+
+impl FdsCartridge {
+    /// 使用用户提供的 BIOS（disksys.rom）与磁盘镜像创建卡带，默认插入第一面
+    pub fn new(bios: Vec<u8>, disk: FdsDisk) -> Result<Self, RomError> {
+        if bios.len() != BIOS_SIZE {
+            return Err(RomError::InvalidBiosSize(bios.len()));
+        }
+        Ok(Self {
+            bios,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            chr_ram: vec![0; CHR_RAM_SIZE],
+            drive: DiskDrive::new(disk),
+            audio: FdsAudio::new(),
+            mirroring: Mirroring::Horizontal,
+            disk_registers_enabled: true,
+            sound_registers_enabled: true,
+            irq_reload: 0,
+            irq_counter: 0,
+            irq_repeat: false,
+            irq_enabled: false,
+            timer_irq: Cell::new(false),
+        })
+    }
+
+    /// 从文件加载 BIOS 与磁盘镜像
+    pub fn from_files(bios_path: &str, disk_path: &str) -> Result<Self, RomError> {
+        let bios = std::fs::read(bios_path).map_err(|source| RomError::Io {
+            path: bios_path.to_string(),
+            source,
+        })?;
+        Self::new(bios, FdsDisk::from_file(disk_path)?)
+    }
+
+    pub fn side_count(&self) -> usize {
+        self.drive.side_count()
+    }
+
+    /// 当前插入的磁盘面，None 表示没有插入磁盘
+    pub fn current_side(&self) -> Option<usize> {
+        self.drive.side()
+    }
+
+    /// 插入磁盘的某一面，超出面数时忽略
+    /// 换面时应先取出磁盘并等待 BIOS 检测到，再插入另一面
+    pub fn insert_disk(&mut self, side: usize) {
+        self.drive.insert(side);
+    }
+
+    pub fn eject_disk(&mut self) {
+        self.drive.eject();
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        if addr == 0x4023 {
+            self.disk_registers_enabled = value & 0x01 != 0;
+            self.sound_registers_enabled = value & 0x02 != 0;
+            if !self.disk_registers_enabled {
+                self.irq_enabled = false;
+                self.timer_irq.set(false);
+            }
+            return;
+        }
+        if !self.disk_registers_enabled {
+            return;
+        }
+        match addr {
+            0x4020 => self.irq_reload = (self.irq_reload & 0xFF00) | value as u16,
+            0x4021 => self.irq_reload = (self.irq_reload & 0x00FF) | ((value as u16) << 8),
+            0x4022 => {
+                self.irq_repeat = value & 0x01 != 0;
+                self.irq_enabled = value & 0x02 != 0;
+                if self.irq_enabled {
+                    self.irq_counter = self.irq_reload;
+                } else {
+                    self.timer_irq.set(false);
+                }
+            }
+            0x4024 => self.drive.write_data(value),
+            0x4025 => {
+                self.mirroring = if value & 0x08 != 0 {
+                    Mirroring::Horizontal
+                } else {
+                    Mirroring::Vertical
+                };
+                self.drive.write_control(value);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Cartridge for FdsCartridge {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x4030 if self.disk_registers_enabled => {
+                let status = self.timer_irq.get() as u8 | self.drive.read_status();
+                self.timer_irq.set(false);
+                status
+            }
+            0x4031 if self.disk_registers_enabled => self.drive.read_data(),
+            0x4032 if self.disk_registers_enabled => self.drive.read_drive_status() | 0x40,
+            // bit7 为电池电量正常
+            0x4033 if self.disk_registers_enabled => 0x80,
+            0x4040..=0x4092 => self.audio.read(addr),
+            0x6000..=0xDFFF => self.prg_ram[addr as usize - 0x6000],
+            0xE000..=0xFFFF => self.bios[addr as usize - 0xE000],
+            _ => 0,
+        }
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000.. => Some(self.cpu_read(addr)),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4020..=0x4026 => self.write_register(addr, value),
+            0x4040..=0x408A if self.sound_registers_enabled => self.audio.write(addr, value),
+            0x6000..=0xDFFF => self.prg_ram[addr as usize - 0x6000] = value,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr_ram[addr as usize & 0x1FFF]
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        self.chr_ram[addr as usize & 0x1FFF] = value;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn clock(&mut self) {
+        if self.irq_enabled && self.disk_registers_enabled {
+            if self.irq_counter == 0 {
+                self.timer_irq.set(true);
+                if self.irq_repeat {
+                    self.irq_counter = self.irq_reload;
+                } else {
+                    self.irq_enabled = false;
+                }
+            } else {
+                self.irq_counter -= 1;
+            }
+        }
+
+        self.drive.clock();
+        self.audio.clock();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.timer_irq.get() || self.drive.irq_pending()
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output() as f32 * AUDIO_SCALE
+    }
+
+    fn nametable_read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    fn nametable_write(&mut self, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    fn ppu_register_write(&mut self, _addr: u16, _value: u8) {}
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+
+/// ROM 数据的校验值，按 NES 2.0 数据库的约定只计算 PRG-ROM 与 CHR-ROM，不含文件头与 Trainer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomHash {
+    pub crc32: u32,
+    pub sha1: [u8; 20],
+}
+
+impl RomHash {
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            crc32: crc32(data),
+            sha1: sha1(data),
+        }
+    }
+
+    /// SHA-1 的十六进制大写形式
+    pub fn sha1_hex(&self) -> String {
+        self.sha1
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect()
+    }
+}
+
+impl fmt::Display for RomHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CRC32 {:08X}, SHA-1 {}", self.crc32, self.sha1_hex())
+    }
+}
+
+/// CRC-32 (IEEE 802.3)，与 zip/No-Intro 使用的相同
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// SHA-1 摘要
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    // 填充到 64 字节的整数倍，末尾是以位为单位的消息长度
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..40 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, value) in digest.chunks_exact_mut(4).zip(h) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
@@ -1,35 +1,97 @@
-use nes_base::{Cartridge, Mirroring, Ram};
+use nes_base::{Cartridge, Mirroring, Ram, Reader, Writer};
 use nes_ram::RamImpl;
 use std::{cell::RefCell, rc::Rc};
 
+mod database;
+mod error;
+mod fds;
+mod hash;
 mod mapper;
 mod nes_file;
+mod patch;
+mod save;
+mod state;
+mod unif;
 
+pub use database::{GameInfo, Region};
+pub use error::{PatchError, RomError, StateError};
+pub use fds::{FdsCartridge, FdsDisk};
+pub use hash::RomHash;
 pub use nes_file::NESFile;
+pub use patch::{PatchFormat, apply_patch, soft_patch_path};
+pub use save::sav_path;
+pub use state::{BankBus, BankMap, BankMapping, BankMemory, StateReader, StateWriter};
+pub use unif::board_mapper_id;
 
-use crate::mapper::Mapper;
+pub use mapper::{Mapper, MapperConstructor, MapperContext, MapperEntry, MapperRegistry};
+
+const SRAM_SIZE: usize = 0x2000; // 8KB
+const TRAINER_ADDRESS: u16 = 0x7000;
 
 pub struct CartridgeImpl {
+    mapper_id: u8,
     mapper: Box<dyn Mapper>,
     mirroring: Mirroring,
+    /// PRG-ROM，与 mapper 共享
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    /// PRG-RAM，与 mapper 共享，只有 Mapper 真正写入时才会被标记为已修改
+    sram: Option<Rc<RefCell<RamImpl>>>,
+    /// PRG-RAM 的大小
+    sram_size: usize,
+    /// PRG-RAM 是否由电池供电
+    battery_backed: bool,
 }
 
 impl CartridgeImpl {
-    pub fn new(nes: NESFile) -> Self {
+    /// 使用内置的 Mapper 创建卡带
+    pub fn new(nes: NESFile) -> Result<Self, RomError> {
+        Self::with_registry(nes, &MapperRegistry::builtin())
+    }
+
+    /// 从注册表中查找 Mapper 创建卡带，可以使用其他 crate 注册的 Mapper
+    pub fn with_registry(nes: NESFile, registry: &MapperRegistry) -> Result<Self, RomError> {
         let mapper_id = nes.header().mapper_id;
+        let submapper = nes.header().submapper;
+        let entry = registry
+            .find(mapper_id, submapper)
+            .ok_or_else(|| registry.unsupported(mapper_id, submapper, nes.board()))?;
         let prg_banks = nes.header().prg_banks;
         let has_battery_backed = nes.header().has_battery_backed;
         let chr_rom = Rc::new(RefCell::new(nes.chr_rom()));
         let prg_rom = Rc::new(RefCell::new(nes.prg_rom()));
-        let sram: Option<Rc<RefCell<dyn Ram>>> = if has_battery_backed {
-            Some(Rc::new(RefCell::new(RamImpl::new(0x2000))))
+        let trainer = nes.trainer_rom();
+        let builtin_sram_size = entry.prg_ram_size;
+        let sram_size = builtin_sram_size.unwrap_or(SRAM_SIZE);
+        let sram = if has_battery_backed || trainer.is_some() || builtin_sram_size.is_some() {
+            Some(Rc::new(RefCell::new(RamImpl::new(sram_size))))
         } else {
             None
         };
-        CartridgeImpl {
-            mapper: mapper::get_mapper_by_id(mapper_id, prg_banks, chr_rom, prg_rom, sram),
-            mirroring: nes.header().mirroring,
+        // Trainer 需要在复位前被放置到 [0x7000, 0x7200)
+        if let (Some(trainer), Some(sram)) = (trainer, &sram) {
+            for (i, &value) in trainer.iter().enumerate() {
+                sram.borrow_mut()
+                    .write(TRAINER_ADDRESS - 0x6000 + i as u16, value);
+            }
+            sram.borrow_mut().clear_dirty();
         }
+        let mapper = (entry.constructor)(MapperContext {
+            mapper_id,
+            submapper,
+            prg_banks,
+            chr_rom,
+            prg_rom: prg_rom.clone(),
+            sram: sram.clone().map(|sram| sram as Rc<RefCell<dyn Ram>>),
+        });
+        Ok(CartridgeImpl {
+            mapper_id,
+            mapper,
+            mirroring: nes.header().mirroring,
+            prg_rom,
+            sram,
+            sram_size,
+            battery_backed: has_battery_backed,
+        })
     }
 }
 
@@ -42,7 +104,25 @@ impl Cartridge for CartridgeImpl {
         self.mapper.cpu_write(addr, value);
     }
 
-    fn ppu_read(&self, addr: u16) -> u8 {
+    fn peek(&self, addr: u16) -> Option<u8> {
+        // 按 Mapper 当前的 bank 映射直接读取存储器，不经过 Mapper 的寄存器
+        let bank_map = self.mapper.bank_map();
+        let mapping = bank_map.find(BankBus::Cpu, addr)?;
+        let offset = mapping.bank * mapping.size + (addr - mapping.start) as usize;
+        match mapping.memory {
+            BankMemory::PrgRom => {
+                let prg_rom = self.prg_rom.borrow();
+                Some(prg_rom[offset % prg_rom.len()])
+            }
+            BankMemory::PrgRam => {
+                let sram = self.sram.as_ref()?;
+                Some(sram.borrow().read((offset % self.sram_size) as u16))
+            }
+            _ => None,
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
         self.mapper.ppu_read(addr)
     }
 
@@ -51,6 +131,30 @@ impl Cartridge for CartridgeImpl {
     }
 
     fn mirroring(&self) -> Mirroring {
-        self.mirroring
+        self.mapper.mirroring().unwrap_or(self.mirroring)
+    }
+
+    fn clock(&mut self) {
+        self.mapper.clock();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.mapper.check_irq_interrupt()
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.mapper.audio_output()
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        self.mapper.nametable_read(addr)
+    }
+
+    fn nametable_write(&mut self, addr: u16, value: u8) -> bool {
+        self.mapper.nametable_write(addr, value)
+    }
+
+    fn ppu_register_write(&mut self, addr: u16, value: u8) {
+        self.mapper.ppu_register_write(addr, value);
     }
 }
@@ -2,11 +2,12 @@ use std::{cell::RefCell, rc::Rc};
 
 use nes_base::Ram;
 
-use crate::mapper::Mapper;
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
 
 pub struct Mapper0 {
     prg_banks: u8,
     chr_rom: Rc<RefCell<Vec<u8>>>,
+    chr_ram: bool,
     prg_rom: Rc<RefCell<Vec<u8>>>,
     sram: Option<Rc<RefCell<dyn Ram>>>,
 }
@@ -18,9 +19,15 @@ impl Mapper0 {
         prg_rom: Rc<RefCell<Vec<u8>>>,
         sram: Option<Rc<RefCell<dyn Ram>>>,
     ) -> Self {
+        // 没有 CHR-ROM 时使用 8KB 的 CHR-RAM
+        let chr_ram = chr_rom.borrow().is_empty();
+        if chr_ram {
+            chr_rom.borrow_mut().resize(0x2000, 0);
+        }
         Mapper0 {
             prg_banks,
             chr_rom,
+            chr_ram,
             prg_rom,
             sram,
         }
@@ -61,6 +68,10 @@ impl Mapper0 {
 
     fn write(&mut self, addr: u16, value: u8) {
         match addr {
+            0x0000..0x2000 if self.chr_ram => {
+                // CHR RAM
+                self.chr_rom.borrow_mut()[addr as usize] = value;
+            }
             0x0000..0x2000 => {
                 // CHR ROM is read-only in Mapper 0
                 panic!("Attempt to write to CHR ROM at address 0x{:04X}", addr);
@@ -101,11 +112,42 @@ impl Mapper for Mapper0 {
         self.write(addr, value);
     }
 
-    fn ppu_read(&self, addr: u16) -> u8 {
+    fn ppu_read(&mut self, addr: u16) -> u8 {
         self.read(addr)
     }
 
     fn ppu_write(&mut self, addr: u16, value: u8) {
         self.write(addr, value);
     }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_memory = if self.chr_ram {
+            BankMemory::ChrRam
+        } else {
+            BankMemory::ChrRom
+        };
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(0x8000, 0x4000, BankMemory::PrgRom, 0)
+            .cpu(
+                0xC000,
+                0x4000,
+                BankMemory::PrgRom,
+                self.prg_banks as usize - 1,
+            )
+            .ppu(0x0000, 0x2000, chr_memory, 0)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        if self.chr_ram {
+            state.write_bytes(&self.chr_rom.borrow());
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        if self.chr_ram {
+            state.read_bytes_into(&mut self.chr_rom.borrow_mut(), "CHR-RAM")?;
+        }
+        Ok(())
+    }
 }
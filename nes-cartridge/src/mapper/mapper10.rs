@@ -0,0 +1,146 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{
+    BankMap, BankMemory, StateError, StateReader, StateWriter,
+    mapper::{
+        Mapper,
+        mapper9::{ChrLatch, write_latch_register},
+    },
+};
+
+/// MMC4 (FxROM)
+/// 与 MMC2 相同的 CHR 锁存器，区别在于:
+///   PRG-ROM: [0x8000, 0xC000) 16KB 可切换，[0xC000, 0xFFFF] 固定为最后一个 16KB bank
+///   低窗口锁存器在 [0x0FD8, 0x0FDF] 和 [0x0FE8, 0x0FEF] 范围内都会切换
+pub struct Mapper10 {
+    prg_bank: u8,
+    chr_latch: ChrLatch,
+    mirroring: Mirroring,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper10 {
+    pub fn new(
+        _prg_banks: u8,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper10 {
+            prg_bank: 0,
+            chr_latch: ChrLatch::new(false),
+            mirroring: Mirroring::Vertical,
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+}
+
+impl Mapper for Mapper10 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000..0xC000 => {
+                // PRG ROM 16KB 可切换 Bank
+                let prg_rom = self.prg_rom.borrow();
+                let offset = self.prg_bank as usize * 0x4000 + (addr as usize - 0x8000);
+                prg_rom[offset % prg_rom.len()]
+            }
+            0xC000.. => {
+                // PRG ROM 固定为最后 16KB
+                let prg_rom = self.prg_rom.borrow();
+                prg_rom[prg_rom.len() - 0x4000 + (addr as usize - 0xC000)]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0xA000..0xB000 => {
+                // PRG ROM Bank selection
+                self.prg_bank = value & 0x0F;
+            }
+            0x8000.. => {
+                write_latch_register(&mut self.chr_latch, &mut self.mirroring, addr, value);
+            }
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM
+            let value = {
+                let chr_rom = self.chr_rom.borrow();
+                chr_rom[self.chr_latch.chr_offset(addr) % chr_rom.len()]
+            };
+            self.chr_latch.update(addr);
+            value
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let bank_count = self.prg_rom.borrow().len() / 0x4000;
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(
+                0x8000,
+                0x4000,
+                BankMemory::PrgRom,
+                self.prg_bank as usize % bank_count,
+            )
+            .cpu(0xC000, 0x4000, BankMemory::PrgRom, bank_count - 1)
+            .ppu(0x0000, 0x1000, BankMemory::ChrRom, self.chr_latch.bank(0))
+            .ppu(0x1000, 0x1000, BankMemory::ChrRom, self.chr_latch.bank(1))
+            .register("latch 0", self.chr_latch.latch(0) as u32)
+            .register("latch 1", self.chr_latch.latch(1) as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank);
+        state.write_mirroring(self.mirroring);
+        self.chr_latch.save_state(state);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_bank = state.read_u8()?;
+        self.mirroring = state.read_mirroring()?;
+        self.chr_latch.load_state(state)
+    }
+}
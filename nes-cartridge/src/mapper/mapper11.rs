@@ -0,0 +1,147 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::Ram;
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// Color Dreams
+/// PRG-ROM 按 32KB 切换，CHR-ROM 按 8KB 切换
+/// 写入 [0x8000, 0xFFFF]:
+///   bit 0-1 选择 32KB 的 PRG bank
+///   bit 4-7 选择 8KB 的 CHR bank
+pub struct Mapper11 {
+    prg_bank: u8,
+    chr_bank: u8,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    chr_ram: bool,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper11 {
+    pub fn new(
+        _prg_banks: u8,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        // 没有 CHR-ROM 时使用 8KB 的 CHR-RAM
+        let chr_ram = chr_rom.borrow().is_empty();
+        if chr_ram {
+            chr_rom.borrow_mut().resize(0x2000, 0);
+        }
+        Mapper11 {
+            prg_bank: 0,
+            chr_bank: 0,
+            chr_rom,
+            chr_ram,
+            prg_rom,
+            sram,
+        }
+    }
+}
+
+impl Mapper for Mapper11 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG ROM 32KB Bank
+                let prg_rom = self.prg_rom.borrow();
+                let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
+                prg_rom[offset % prg_rom.len()]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG/CHR ROM Bank selection
+                self.prg_bank = value & 0x03;
+                self.chr_bank = value >> 4;
+            }
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 8KB Bank
+            let chr_rom = self.chr_rom.borrow();
+            let offset = self.chr_bank as usize * 0x2000 + addr as usize;
+            chr_rom[offset % chr_rom.len()]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 && self.chr_ram {
+            // CHR RAM
+            self.chr_rom.borrow_mut()[addr as usize] = value;
+        } else if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_memory = if self.chr_ram {
+            BankMemory::ChrRam
+        } else {
+            BankMemory::ChrRom
+        };
+        let prg_count = self.prg_rom.borrow().len() / 0x8000;
+        let chr_count = self.chr_rom.borrow().len() / 0x2000;
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(
+                0x8000,
+                0x8000,
+                BankMemory::PrgRom,
+                self.prg_bank as usize % prg_count.max(1),
+            )
+            .ppu(
+                0x0000,
+                0x2000,
+                chr_memory,
+                self.chr_bank as usize % chr_count.max(1),
+            )
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank);
+        state.write_u8(self.chr_bank);
+        if self.chr_ram {
+            state.write_bytes(&self.chr_rom.borrow());
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_bank = state.read_u8()?;
+        self.chr_bank = state.read_u8()?;
+        if self.chr_ram {
+            state.read_bytes_into(&mut self.chr_rom.borrow_mut(), "CHR-RAM")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// 芯片内部 RAM 的大小，[0x40, 0x7F] 同时是声道寄存器
+const INTERNAL_RAM_SIZE: usize = 0x80;
+/// 每隔 15 个 CPU 周期更新一个声道
+const CHANNEL_UPDATE_CYCLES: u8 = 15;
+/// 单个声道的最大输出为 15 * 8
+const AUDIO_SCALE: f32 = 0.25 / 120.0;
+
+/// Namco 163 (Mapper 19)
+/// PRG-ROM: 三个 8KB 可切换 bank ($E000/$E800/$F000)，$E000 固定为最后一个 bank
+/// CHR-ROM: 八个 1KB 图案表 bank ($8000-$B800)，四个名称表 bank ($C000-$D800)，
+///   名称表 bank 的值 >= $E0 时使用 VRAM 的第 (值 & 1) 页，否则使用 CHR-ROM 作为名称表
+/// IRQ: 15 位计数器每个 CPU 周期加一，到达 $7FFF 时产生 IRQ
+/// 扩展音频: 最多八个波表声道，波形与声道寄存器都存放在 128 字节的内部 RAM 中，
+///   通过 $F800 设置地址，$4800 读写数据
+pub struct Mapper19 {
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    nametable_banks: [u8; 4],
+    sound_disabled: bool,
+    /// $F800: PRG-RAM 写保护
+    write_protect: u8,
+    internal_ram: [u8; INTERNAL_RAM_SIZE],
+    /// $F800: 内部 RAM 地址与自动递增标志，读取 $4800 时也会递增
+    ram_address: Cell<u8>,
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_pending: bool,
+    /// 每个声道最近一次更新的输出
+    channel_outputs: [i16; 8],
+    current_channel: usize,
+    update_cycles: u8,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper19 {
+    pub fn new(
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper19 {
+            prg_banks: [0, 1, 2],
+            chr_banks: [0; 8],
+            nametable_banks: [0xE0, 0xE1, 0xE0, 0xE1],
+            sound_disabled: false,
+            write_protect: 0,
+            internal_ram: [0; INTERNAL_RAM_SIZE],
+            ram_address: Cell::new(0),
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            channel_outputs: [0; 8],
+            current_channel: 7,
+            update_cycles: 0,
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let len = self.prg_rom.borrow().len();
+        let bank = match addr {
+            0x8000..0xE000 => self.prg_banks[(addr as usize - 0x8000) / 0x2000] as usize,
+            _ => len / 0x2000 - 1,
+        };
+        (bank * 0x2000 + (addr as usize & 0x1FFF)) % len
+    }
+
+    /// 读写内部 RAM 后，根据 $F800 的 bit7 自动递增地址
+    fn next_ram_address(&self) -> usize {
+        let address = self.ram_address.get();
+        if address & 0x80 != 0 {
+            self.ram_address
+                .set((address & 0x80) | (address.wrapping_add(1) & 0x7F));
+        }
+        (address & 0x7F) as usize
+    }
+
+    /// 当前启用的声道数量，由 $7F 的 bit4-6 决定
+    fn channel_count(&self) -> usize {
+        ((self.internal_ram[0x7F] >> 4) & 0x07) as usize + 1
+    }
+
+    fn update_channel(&mut self, channel: usize) {
+        let base = 0x40 + channel * 8;
+        let ram = &mut self.internal_ram;
+        let frequency =
+            ram[base] as u32 | (ram[base + 2] as u32) << 8 | ((ram[base + 4] & 0x03) as u32) << 16;
+        let mut phase =
+            ram[base + 1] as u32 | (ram[base + 3] as u32) << 8 | (ram[base + 5] as u32) << 16;
+        let length = 256 - (ram[base + 4] & 0xFC) as u32;
+        let wave_address = ram[base + 6] as u32;
+        let volume = (ram[base + 7] & 0x0F) as i16;
+
+        phase = (phase + frequency) % (length << 16);
+        ram[base + 1] = phase as u8;
+        ram[base + 3] = (phase >> 8) as u8;
+        ram[base + 5] = (phase >> 16) as u8;
+
+        // 每个字节存放两个 4 位采样，低半字节在前
+        let index = ((phase >> 16) + wave_address) as usize & 0xFF;
+        let sample = (ram[index / 2] >> ((index & 1) * 4)) & 0x0F;
+        self.channel_outputs[channel] = (sample as i16 - 8) * volume;
+    }
+
+    fn clock_audio(&mut self) {
+        self.update_cycles += 1;
+        if self.update_cycles < CHANNEL_UPDATE_CYCLES {
+            return;
+        }
+        self.update_cycles = 0;
+        // 从声道 7 开始，依次更新到声道 8 - N
+        let first = 8 - self.channel_count();
+        self.current_channel = if self.current_channel <= first {
+            7
+        } else {
+            self.current_channel - 1
+        };
+        self.update_channel(self.current_channel);
+    }
+
+    fn sram_writable(&self, addr: u16) -> bool {
+        let window = (addr - 0x6000) / 0x800;
+        self.write_protect & 0xF0 == 0x40 && self.write_protect & (1 << window) == 0
+    }
+}
+
+impl Mapper for Mapper19 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x4800..0x5000 => self.internal_ram[self.next_ram_address()],
+            0x5000..0x5800 => self.irq_counter as u8,
+            0x5800..0x6000 => ((self.irq_enabled as u8) << 7) | (self.irq_counter >> 8) as u8,
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => self.prg_rom.borrow()[self.prg_offset(addr)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4800..0x5000 => {
+                let address = self.next_ram_address();
+                self.internal_ram[address] = value;
+            }
+            0x5000..0x5800 => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | value as u16;
+                self.irq_pending = false;
+            }
+            0x5800..0x6000 => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16 & 0x7F) << 8);
+                self.irq_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0x6000..0x8000 => {
+                // SRAM
+                if !self.sram_writable(addr) {
+                    return;
+                }
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000..0xC000 => self.chr_banks[(addr as usize - 0x8000) / 0x800] = value,
+            0xC000..0xE000 => self.nametable_banks[(addr as usize - 0xC000) / 0x800] = value,
+            0xE000..0xE800 => {
+                self.prg_banks[0] = value & 0x3F;
+                self.sound_disabled = value & 0x40 != 0;
+            }
+            0xE800..0xF000 => self.prg_banks[1] = value & 0x3F,
+            0xF000..0xF800 => self.prg_banks[2] = value & 0x3F,
+            0xF800.. => {
+                self.write_protect = value;
+                self.ram_address.set(value);
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 1KB Bank
+            let chr_rom = self.chr_rom.borrow();
+            let bank = self.chr_banks[addr as usize / 0x400] as usize;
+            chr_rom[(bank * 0x400 + (addr as usize & 0x3FF)) % chr_rom.len()]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        // 使用 CHR-ROM 的名称表由 nametable_read 处理
+        let page = |index: usize| self.nametable_banks[index] & 0x01;
+        Some(Mirroring::Mapped([page(0), page(1), page(2), page(3)]))
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        let index = ((addr - 0x2000) % 0x1000) as usize / 0x400;
+        let bank = self.nametable_banks[index];
+        if bank >= 0xE0 {
+            return None;
+        }
+        let chr_rom = self.chr_rom.borrow();
+        Some(chr_rom[(bank as usize * 0x400 + (addr as usize & 0x3FF)) % chr_rom.len()])
+    }
+
+    fn nametable_write(&mut self, addr: u16, _: u8) -> bool {
+        // CHR-ROM 名称表只读
+        let index = ((addr - 0x2000) % 0x1000) as usize / 0x400;
+        self.nametable_banks[index] < 0xE0
+    }
+
+    fn clock(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+            if self.irq_counter == 0x7FFF {
+                self.irq_pending = true;
+            }
+        }
+        self.clock_audio();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn audio_output(&self) -> f32 {
+        if self.sound_disabled {
+            return 0.0;
+        }
+        // 硬件依次输出各个声道，这里取平均值
+        let count = self.channel_count();
+        let sum: i16 = self.channel_outputs[8 - count..].iter().sum();
+        sum as f32 / count as f32 * AUDIO_SCALE
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_count = self.chr_rom.borrow().len() / 0x400;
+        let map = (0..4).fold(BankMap::new().prg_ram(self.sram.is_some()), |map, i| {
+            let addr = 0x8000 + i * 0x2000;
+            map.cpu(
+                addr,
+                0x2000,
+                BankMemory::PrgRom,
+                self.prg_offset(addr) / 0x2000,
+            )
+        });
+        let map = (0..8).fold(map, |map, slot| {
+            let bank = self.chr_banks[slot] as usize % chr_count;
+            map.ppu(slot as u16 * 0x400, 0x400, BankMemory::ChrRom, bank)
+        });
+        (0..4)
+            .fold(map, |map, i| {
+                let addr = 0x2000 + i as u16 * 0x400;
+                match self.nametable_banks[i] {
+                    bank @ 0xE0.. => map.ppu(addr, 0x400, BankMemory::Vram, bank as usize & 1),
+                    bank => map.ppu(addr, 0x400, BankMemory::ChrRom, bank as usize % chr_count),
+                }
+            })
+            .register("IRQ counter", self.irq_counter as u32)
+            .register("IRQ enabled", self.irq_enabled as u32)
+            .register("sound channels", self.channel_count() as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.prg_banks);
+        state.write_bytes(&self.chr_banks);
+        state.write_bytes(&self.nametable_banks);
+        state.write_bool(self.sound_disabled);
+        state.write_u8(self.write_protect);
+        state.write_bytes(&self.internal_ram);
+        state.write_u8(self.ram_address.get());
+        state.write_u16(self.irq_counter);
+        state.write_bool(self.irq_enabled);
+        state.write_bool(self.irq_pending);
+        for output in self.channel_outputs {
+            state.write_u16(output as u16);
+        }
+        state.write_u8(self.current_channel as u8);
+        state.write_u8(self.update_cycles);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        state.read_bytes_into(&mut self.prg_banks, "PRG banks")?;
+        state.read_bytes_into(&mut self.chr_banks, "CHR banks")?;
+        state.read_bytes_into(&mut self.nametable_banks, "nametable banks")?;
+        self.sound_disabled = state.read_bool()?;
+        self.write_protect = state.read_u8()?;
+        state.read_bytes_into(&mut self.internal_ram, "internal RAM")?;
+        self.ram_address.set(state.read_u8()?);
+        self.irq_counter = state.read_u16()? & 0x7FFF;
+        self.irq_enabled = state.read_bool()?;
+        self.irq_pending = state.read_bool()?;
+        for output in &mut self.channel_outputs {
+            *output = state.read_u16()? as i16;
+        }
+        self.current_channel = (state.read_u8()? & 0x07) as usize;
+        self.update_cycles = state.read_u8()?;
+        Ok(())
+    }
+}
@@ -2,13 +2,14 @@ use std::{cell::RefCell, rc::Rc};
 
 use nes_base::Ram;
 
-use crate::mapper::Mapper;
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
 
 pub struct Mapper2 {
     prg_banks: u8,
     prg_bank1: u8,
     prg_bank2: u8,
     chr_rom: Rc<RefCell<Vec<u8>>>,
+    chr_ram: bool,
     prg_rom: Rc<RefCell<Vec<u8>>>,
     sram: Option<Rc<RefCell<dyn Ram>>>,
 }
@@ -20,6 +21,11 @@ impl Mapper2 {
         prg_rom: Rc<RefCell<Vec<u8>>>,
         sram: Option<Rc<RefCell<dyn Ram>>>,
     ) -> Self {
+        // 没有 CHR-ROM 时使用 8KB 的 CHR-RAM
+        let chr_ram = chr_rom.borrow().is_empty();
+        if chr_ram {
+            chr_rom.borrow_mut().resize(0x2000, 0);
+        }
         let prg_bank1 = 0;
         let prg_bank2 = prg_banks - 1;
         Mapper2 {
@@ -27,6 +33,7 @@ impl Mapper2 {
             prg_bank1,
             prg_bank2,
             chr_rom,
+            chr_ram,
             prg_rom,
             sram,
         }
@@ -52,12 +59,12 @@ impl Mapper2 {
             0x8000..0xC000 => {
                 // PRG ROM Bank 1
                 let prg_rom = self.prg_rom.borrow();
-                prg_rom[self.prg_bank1 as usize * 0x4000 + (addr as usize - 0xC000)]
+                prg_rom[self.prg_bank1 as usize * 0x4000 + (addr as usize - 0x8000)]
             }
             0xC000.. => {
                 // PRG ROM Bank 2
                 let prg_rom = self.prg_rom.borrow();
-                prg_rom[self.prg_bank2 as usize * 0x4000 + (addr as usize - 0x8000)]
+                prg_rom[self.prg_bank2 as usize * 0x4000 + (addr as usize - 0xC000)]
             }
             _ => panic!("Address out of range: {}", addr),
         }
@@ -95,7 +102,7 @@ impl Mapper for Mapper2 {
         self.write(addr, value);
     }
 
-    fn ppu_read(&self, addr: u16) -> u8 {
+    fn ppu_read(&mut self, addr: u16) -> u8 {
         if addr < 0x2000 {
             // CHR ROM
             let chr_rom = self.chr_rom.borrow();
@@ -105,12 +112,47 @@ impl Mapper for Mapper2 {
         }
     }
 
-    fn ppu_write(&mut self, addr: u16, _: u8) {
-        if addr < 0x2000 {
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 && self.chr_ram {
+            // CHR RAM
+            self.chr_rom.borrow_mut()[addr as usize] = value;
+        } else if addr < 0x2000 {
             // CHR ROM is read-only, no write operation
             panic!("Cannot write to CHR ROM at address: {}", addr);
         } else {
             panic!("PPU write out of range: {}", addr);
         }
     }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_memory = if self.chr_ram {
+            BankMemory::ChrRam
+        } else {
+            BankMemory::ChrRom
+        };
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(0x8000, 0x4000, BankMemory::PrgRom, self.prg_bank1 as usize)
+            .cpu(0xC000, 0x4000, BankMemory::PrgRom, self.prg_bank2 as usize)
+            .ppu(0x0000, 0x2000, chr_memory, 0)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank1);
+        if self.chr_ram {
+            state.write_bytes(&self.chr_rom.borrow());
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        let prg_bank1 = state.read_u8()?;
+        if prg_bank1 >= self.prg_banks {
+            return Err(StateError::InvalidValue("PRG bank"));
+        }
+        self.prg_bank1 = prg_bank1;
+        if self.chr_ram {
+            state.read_bytes_into(&mut self.chr_rom.borrow_mut(), "CHR-RAM")?;
+        }
+        Ok(())
+    }
 }
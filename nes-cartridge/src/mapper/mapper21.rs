@@ -0,0 +1,383 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// Konami VRC 系列芯片的寄存器选择线
+/// 不同的板子把芯片的 A0/A1 接到了不同的 CPU 地址线上，
+/// 多个变体共用同一个 Mapper 编号时，把它们的地址线取或
+#[derive(Debug, Clone, Copy)]
+pub struct VrcWiring {
+    a0_mask: u16,
+    a1_mask: u16,
+}
+
+impl VrcWiring {
+    /// VRC4a (A1, A2) + VRC4c (A6, A7)
+    pub const MAPPER_21: Self = Self::new(0x02 | 0x40, 0x04 | 0x80);
+    /// VRC2a (A1, A0)
+    pub const MAPPER_22: Self = Self::new(0x02, 0x01);
+    /// VRC2b/VRC4f (A0, A1) + VRC4e (A2, A3)
+    pub const MAPPER_23: Self = Self::new(0x01 | 0x04, 0x02 | 0x08);
+    /// VRC6a (A0, A1)
+    pub const MAPPER_24: Self = Self::new(0x01, 0x02);
+    /// VRC2c/VRC4b (A1, A0) + VRC4d (A3, A2)
+    pub const MAPPER_25: Self = Self::new(0x02 | 0x08, 0x01 | 0x04);
+    /// VRC6b (A1, A0)
+    pub const MAPPER_26: Self = Self::new(0x02, 0x01);
+
+    const fn new(a0_mask: u16, a1_mask: u16) -> Self {
+        Self { a0_mask, a1_mask }
+    }
+
+    /// 将 CPU 地址转换为 $x000-$x003 形式的寄存器地址
+    pub fn register(&self, addr: u16) -> u16 {
+        let a0 = (addr & self.a0_mask != 0) as u16;
+        let a1 = (addr & self.a1_mask != 0) as u16;
+        (addr & 0xF000) | (a1 << 1) | a0
+    }
+}
+
+/// VRC4/VRC6/VRC7 共用的 IRQ 计数器
+/// 8 位计数器向上计数，溢出时重新装载锁存值并产生 IRQ
+/// 周期模式下每个 CPU 周期计数一次，
+/// 扫描线模式下由预分频器每 341/3 个 CPU 周期 (即一条扫描线) 计数一次
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enable_after_ack: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl VrcIrq {
+    pub fn write_latch_low(&mut self, value: u8) {
+        self.latch = (self.latch & 0xF0) | (value & 0x0F);
+    }
+
+    pub fn write_latch_high(&mut self, value: u8) {
+        self.latch = (self.latch & 0x0F) | ((value & 0x0F) << 4);
+    }
+
+    pub fn write_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    pub fn write_control(&mut self, value: u8) {
+        self.enable_after_ack = value & 0x01 != 0;
+        self.enabled = value & 0x02 != 0;
+        self.cycle_mode = value & 0x04 != 0;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = 341;
+        }
+        self.pending = false;
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if self.cycle_mode {
+            self.clock_counter();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += 341;
+                self.clock_counter();
+            }
+        }
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn counter(&self) -> u8 {
+        self.counter
+    }
+
+    pub fn latch(&self) -> u8 {
+        self.latch
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.latch);
+        state.write_u8(self.counter);
+        state.write_u16(self.prescaler as u16);
+        state.write_bool(self.enabled);
+        state.write_bool(self.enable_after_ack);
+        state.write_bool(self.cycle_mode);
+        state.write_bool(self.pending);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.latch = state.read_u8()?;
+        self.counter = state.read_u8()?;
+        self.prescaler = state.read_u16()? as i16;
+        self.enabled = state.read_bool()?;
+        self.enable_after_ack = state.read_bool()?;
+        self.cycle_mode = state.read_bool()?;
+        self.pending = state.read_bool()?;
+        Ok(())
+    }
+}
+
+/// Konami VRC2/VRC4 (Mapper 21, 22, 23, 25)
+/// PRG-ROM: 两个 8KB 可切换 bank，其余两个固定为倒数第二和最后一个 bank，
+///   VRC4 可以通过 $9002 交换 $8000 与 $C000 的映射
+/// CHR-ROM: 八个 1KB 可切换 bank，每个 bank 编号分高低两个半字节写入
+/// 寄存器:
+///   $8000 PRG bank 0, $9000 镜像方式, $9002 PRG 模式 (VRC4)
+///   $A000 PRG bank 1, $B000-$E003 CHR bank, $F000-$F003 IRQ (VRC4)
+pub struct Mapper21 {
+    wiring: VrcWiring,
+    /// VRC2a 的 CHR bank 编号忽略最低位
+    chr_shift: u8,
+    prg_banks: [u8; 2],
+    prg_swap_mode: bool,
+    chr_banks: [u16; 8],
+    mirroring: Mirroring,
+    irq: VrcIrq,
+    /// 没有 PRG-RAM 的 VRC2 在 [0x6000, 0x7000) 有一个 1 位的锁存器
+    microwire_latch: u8,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    chr_ram: bool,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper21 {
+    pub fn new(
+        wiring: VrcWiring,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        let chr_shift = if wiring.a0_mask == VrcWiring::MAPPER_22.a0_mask
+            && wiring.a1_mask == VrcWiring::MAPPER_22.a1_mask
+        {
+            1
+        } else {
+            0
+        };
+        // 没有 CHR-ROM 时使用 8KB 的 CHR-RAM
+        let chr_ram = chr_rom.borrow().is_empty();
+        if chr_ram {
+            chr_rom.borrow_mut().resize(0x2000, 0);
+        }
+        Mapper21 {
+            wiring,
+            chr_shift,
+            prg_banks: [0, 0],
+            prg_swap_mode: false,
+            chr_banks: [0; 8],
+            mirroring: Mirroring::Vertical,
+            irq: VrcIrq::default(),
+            microwire_latch: 0,
+            chr_rom,
+            chr_ram,
+            prg_rom,
+            sram,
+        }
+    }
+
+    fn prg_bank(&self, addr: u16) -> usize {
+        let bank_count = self.prg_rom.borrow().len() / 0x2000;
+        let second_last = bank_count - 2;
+        let bank = match (addr - 0x8000) / 0x2000 {
+            0 if self.prg_swap_mode => second_last,
+            0 => self.prg_banks[0] as usize,
+            1 => self.prg_banks[1] as usize,
+            2 if self.prg_swap_mode => self.prg_banks[0] as usize,
+            2 => second_last,
+            _ => bank_count - 1,
+        };
+        bank % bank_count
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        self.prg_bank(addr) * 0x2000 + (addr as usize & 0x1FFF)
+    }
+
+    /// 当前 1KB CHR bank，不考虑 CHR-ROM 大小
+    fn chr_bank(&self, slot: usize) -> usize {
+        (self.chr_banks[slot] >> self.chr_shift) as usize
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = self.chr_bank(addr as usize / 0x400);
+        (bank * 0x400 + (addr as usize & 0x3FF)) % self.chr_rom.borrow().len()
+    }
+
+    fn write_chr_bank(&mut self, register: u16, value: u8) {
+        // $B000 -> bank 0/1, $C000 -> bank 2/3, ...
+        let index = (((register >> 12) - 0xB) * 2 + ((register >> 1) & 1)) as usize;
+        let bank = &mut self.chr_banks[index];
+        if register & 1 == 0 {
+            *bank = (*bank & 0x1F0) | (value as u16 & 0x0F);
+        } else {
+            *bank = (*bank & 0x00F) | ((value as u16 & 0x1F) << 4);
+        }
+    }
+}
+
+impl Mapper for Mapper21 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else if addr < 0x7000 {
+                    0x60 | self.microwire_latch
+                } else {
+                    0x60
+                }
+            }
+            0x8000.. => {
+                let offset = self.prg_offset(addr);
+                self.prg_rom.borrow()[offset]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else if addr < 0x7000 {
+                    self.microwire_latch = value & 0x01;
+                }
+            }
+            0x8000.. => match self.wiring.register(addr) {
+                0x8000..=0x8003 => self.prg_banks[0] = value & 0x1F,
+                0x9000 | 0x9001 => {
+                    self.mirroring = match value & 0x03 {
+                        0 => Mirroring::Vertical,
+                        1 => Mirroring::Horizontal,
+                        2 => Mirroring::SingleScreenLower,
+                        _ => Mirroring::SingleScreenUpper,
+                    };
+                }
+                0x9002 | 0x9003 => self.prg_swap_mode = value & 0x02 != 0,
+                0xA000..=0xA003 => self.prg_banks[1] = value & 0x1F,
+                register @ 0xB000..=0xEFFF => self.write_chr_bank(register, value),
+                0xF000 => self.irq.write_latch_low(value),
+                0xF001 => self.irq.write_latch_high(value),
+                0xF002 => self.irq.write_control(value),
+                0xF003 => self.irq.acknowledge(),
+                _ => {}
+            },
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 1KB Bank
+            let offset = self.chr_offset(addr);
+            self.chr_rom.borrow()[offset]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 && self.chr_ram {
+            // CHR RAM 1KB Bank
+            let offset = self.chr_offset(addr);
+            self.chr_rom.borrow_mut()[offset] = value;
+        } else if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_count = self.chr_rom.borrow().len() / 0x400;
+        let chr_memory = if self.chr_ram {
+            BankMemory::ChrRam
+        } else {
+            BankMemory::ChrRom
+        };
+        let map = (0..4).fold(BankMap::new().prg_ram(self.sram.is_some()), |map, i| {
+            let addr = 0x8000 + i * 0x2000;
+            map.cpu(addr, 0x2000, BankMemory::PrgRom, self.prg_bank(addr))
+        });
+        (0..8)
+            .fold(map, |map, slot| {
+                let bank = self.chr_bank(slot) % chr_count;
+                map.ppu(slot as u16 * 0x400, 0x400, chr_memory, bank)
+            })
+            .nametables(self.mirroring)
+            .register("IRQ counter", self.irq.counter() as u32)
+            .register("IRQ latch", self.irq.latch() as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_banks[0]);
+        state.write_u8(self.prg_banks[1]);
+        state.write_bool(self.prg_swap_mode);
+        for bank in self.chr_banks {
+            state.write_u16(bank);
+        }
+        state.write_mirroring(self.mirroring);
+        self.irq.save_state(state);
+        state.write_u8(self.microwire_latch);
+        if self.chr_ram {
+            state.write_bytes(&self.chr_rom.borrow());
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_banks[0] = state.read_u8()?;
+        self.prg_banks[1] = state.read_u8()?;
+        self.prg_swap_mode = state.read_bool()?;
+        for bank in &mut self.chr_banks {
+            *bank = state.read_u16()?;
+        }
+        self.mirroring = state.read_mirroring()?;
+        self.irq.load_state(state)?;
+        self.microwire_latch = state.read_u8()?;
+        if self.chr_ram {
+            state.read_bytes_into(&mut self.chr_rom.borrow_mut(), "CHR-RAM")?;
+        }
+        Ok(())
+    }
+}
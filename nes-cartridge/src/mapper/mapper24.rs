@@ -0,0 +1,432 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{
+    BankMap, BankMemory, StateError, StateReader, StateWriter,
+    mapper::{
+        Mapper,
+        mapper21::{VrcIrq, VrcWiring},
+    },
+};
+
+/// VRC6 三个声道相加后的最大值为 15 + 15 + 31
+const AUDIO_SCALE: f32 = 0.25 / 61.0;
+
+/// VRC6 方波声道
+/// 16 步的占空比序列，模式位置位时直接输出音量
+#[derive(Default)]
+struct Vrc6Pulse {
+    volume: u8,
+    duty: u8,
+    ignore_duty: bool,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    step: u8,
+}
+
+impl Vrc6Pulse {
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0x03 {
+            0 => {
+                self.volume = value & 0x0F;
+                self.duty = (value >> 4) & 0x07;
+                self.ignore_duty = value & 0x80 != 0;
+            }
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 15;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step = if self.step == 0 { 15 } else { self.step - 1 };
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.volume);
+        state.write_u8(self.duty);
+        state.write_bool(self.ignore_duty);
+        state.write_u16(self.period);
+        state.write_bool(self.enabled);
+        state.write_u16(self.timer);
+        state.write_u8(self.step);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.volume = state.read_u8()?;
+        self.duty = state.read_u8()?;
+        self.ignore_duty = state.read_bool()?;
+        self.period = state.read_u16()?;
+        self.enabled = state.read_bool()?;
+        self.timer = state.read_u16()?;
+        self.step = state.read_u8()? & 0x0F;
+        Ok(())
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.ignore_duty || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+/// VRC6 锯齿波声道
+/// 每两个时钟累加一次速率，累加 6 次 (14 个时钟) 后清零
+#[derive(Default)]
+struct Vrc6Saw {
+    rate: u8,
+    period: u16,
+    enabled: bool,
+    timer: u16,
+    step: u8,
+    accumulator: u8,
+}
+
+impl Vrc6Saw {
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0x03 {
+            0 => self.rate = value & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | value as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer == 0 {
+            self.timer = self.period >> shift;
+            self.step += 1;
+            if self.step == 14 {
+                self.step = 0;
+                self.accumulator = 0;
+            } else if self.step & 1 == 0 {
+                self.accumulator = self.accumulator.wrapping_add(self.rate);
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.rate);
+        state.write_u16(self.period);
+        state.write_bool(self.enabled);
+        state.write_u16(self.timer);
+        state.write_u8(self.step);
+        state.write_u8(self.accumulator);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.rate = state.read_u8()?;
+        self.period = state.read_u16()?;
+        self.enabled = state.read_bool()?;
+        self.timer = state.read_u16()?;
+        self.step = state.read_u8()? % 14;
+        self.accumulator = state.read_u8()?;
+        Ok(())
+    }
+
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
+
+/// Konami VRC6 (Mapper 24 VRC6a, Mapper 26 VRC6b)
+/// PRG-ROM: $8000 16KB 可切换, $C000 8KB 可切换, $E000 固定为最后一个 8KB bank
+/// CHR-ROM: 八个 CHR 寄存器，按 $B003 的模式映射为 1KB 或 2KB bank
+/// 扩展音频: 两个方波声道和一个锯齿波声道
+pub struct Mapper24 {
+    wiring: VrcWiring,
+    prg_16k_bank: u8,
+    prg_8k_bank: u8,
+    chr_registers: [u8; 8],
+    banking_mode: u8,
+    mirroring: Mirroring,
+    irq: VrcIrq,
+    pulse1: Vrc6Pulse,
+    pulse2: Vrc6Pulse,
+    saw: Vrc6Saw,
+    /// $9003: bit0 停止所有声道, bit1/bit2 将频率提高 16/256 倍
+    audio_halt: bool,
+    audio_shift: u8,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    chr_ram: bool,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper24 {
+    pub fn new(
+        wiring: VrcWiring,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        // 没有 CHR-ROM 时使用 8KB 的 CHR-RAM
+        let chr_ram = chr_rom.borrow().is_empty();
+        if chr_ram {
+            chr_rom.borrow_mut().resize(0x2000, 0);
+        }
+        Mapper24 {
+            wiring,
+            prg_16k_bank: 0,
+            prg_8k_bank: 0,
+            chr_registers: [0; 8],
+            banking_mode: 0,
+            mirroring: Mirroring::Vertical,
+            irq: VrcIrq::default(),
+            pulse1: Vrc6Pulse::default(),
+            pulse2: Vrc6Pulse::default(),
+            saw: Vrc6Saw::default(),
+            audio_halt: false,
+            audio_shift: 0,
+            chr_rom,
+            chr_ram,
+            prg_rom,
+            sram,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let len = self.prg_rom.borrow().len();
+        let offset = match addr {
+            0x8000..0xC000 => self.prg_16k_bank as usize * 0x4000 + (addr as usize - 0x8000),
+            0xC000..0xE000 => self.prg_8k_bank as usize * 0x2000 + (addr as usize - 0xC000),
+            _ => len - 0x2000 + (addr as usize - 0xE000),
+        };
+        offset % len
+    }
+
+    /// 计算 PPU 地址所在的 1KB CHR bank
+    fn chr_bank(&self, addr: u16) -> usize {
+        let slot = addr as usize / 0x400;
+        let registers = &self.chr_registers;
+        match self.banking_mode & 0x03 {
+            // 八个 1KB bank
+            0 => registers[slot] as usize,
+            // 四个 2KB bank，使用 R0-R3
+            1 => registers[slot / 2] as usize * 2 + (slot & 1),
+            // $0000-$0FFF 为 1KB bank，$1000-$1FFF 为 R4/R5 的 2KB bank
+            _ => {
+                if slot < 4 {
+                    registers[slot] as usize
+                } else {
+                    registers[4 + (slot - 4) / 2] as usize * 2 + (slot & 1)
+                }
+            }
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let offset = self.chr_bank(addr) * 0x400 + (addr as usize & 0x3FF);
+        offset % self.chr_rom.borrow().len()
+    }
+
+    fn write_banking_mode(&mut self, value: u8) {
+        self.banking_mode = value;
+        self.mirroring = match (value >> 2) & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        };
+    }
+
+    fn sram_enabled(&self) -> bool {
+        self.banking_mode & 0x80 != 0
+    }
+}
+
+impl Mapper for Mapper24 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                match self.sram {
+                    Some(ref sram) if self.sram_enabled() => sram.borrow().read(addr - 0x6000),
+                    _ => 0,
+                }
+            }
+            0x8000.. => self.prg_rom.borrow()[self.prg_offset(addr)],
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram
+                    && self.banking_mode & 0x80 != 0
+                {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                }
+            }
+            0x8000.. => match self.wiring.register(addr) {
+                0x8000..=0x8003 => self.prg_16k_bank = value & 0x0F,
+                register @ 0x9000..=0x9002 => self.pulse1.write(register, value),
+                0x9003 => {
+                    self.audio_halt = value & 0x01 != 0;
+                    self.audio_shift = match value & 0x06 {
+                        0 => 0,
+                        0x02 => 4,
+                        _ => 8,
+                    };
+                }
+                register @ 0xA000..=0xA002 => self.pulse2.write(register, value),
+                register @ 0xB000..=0xB002 => self.saw.write(register, value),
+                0xB003 => self.write_banking_mode(value),
+                0xC000..=0xC003 => self.prg_8k_bank = value & 0x1F,
+                register @ 0xD000..=0xE003 => {
+                    let index = ((register >> 12) - 0xD) * 4 + (register & 0x03);
+                    self.chr_registers[index as usize] = value;
+                }
+                0xF000 => self.irq.write_latch(value),
+                0xF001 => self.irq.write_control(value),
+                0xF002 => self.irq.acknowledge(),
+                _ => {}
+            },
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 1KB Bank
+            let offset = self.chr_offset(addr);
+            self.chr_rom.borrow()[offset]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 && self.chr_ram {
+            // CHR RAM 1KB Bank
+            let offset = self.chr_offset(addr);
+            self.chr_rom.borrow_mut()[offset] = value;
+        } else if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+        if !self.audio_halt {
+            self.pulse1.clock(self.audio_shift);
+            self.pulse2.clock(self.audio_shift);
+            self.saw.clock(self.audio_shift);
+        }
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn audio_output(&self) -> f32 {
+        let sum = self.pulse1.output() + self.pulse2.output() + self.saw.output();
+        sum as f32 * AUDIO_SCALE
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let prg_len = self.prg_rom.borrow().len();
+        let chr_count = self.chr_rom.borrow().len() / 0x400;
+        let chr_memory = if self.chr_ram {
+            BankMemory::ChrRam
+        } else {
+            BankMemory::ChrRom
+        };
+        let map = BankMap::new()
+            .prg_ram(self.sram.is_some() && self.sram_enabled())
+            .cpu(
+                0x8000,
+                0x4000,
+                BankMemory::PrgRom,
+                self.prg_offset(0x8000) / 0x4000,
+            )
+            .cpu(
+                0xC000,
+                0x2000,
+                BankMemory::PrgRom,
+                self.prg_offset(0xC000) / 0x2000,
+            )
+            .cpu(0xE000, 0x2000, BankMemory::PrgRom, prg_len / 0x2000 - 1);
+        (0..8)
+            .fold(map, |map, slot| {
+                let addr = slot * 0x400;
+                let bank = self.chr_bank(addr) % chr_count;
+                map.ppu(addr, 0x400, chr_memory, bank)
+            })
+            .nametables(self.mirroring)
+            .register("IRQ counter", self.irq.counter() as u32)
+            .register("IRQ latch", self.irq.latch() as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_16k_bank);
+        state.write_u8(self.prg_8k_bank);
+        state.write_bytes(&self.chr_registers);
+        state.write_u8(self.banking_mode);
+        state.write_mirroring(self.mirroring);
+        self.irq.save_state(state);
+        self.pulse1.save_state(state);
+        self.pulse2.save_state(state);
+        self.saw.save_state(state);
+        state.write_bool(self.audio_halt);
+        state.write_u8(self.audio_shift);
+        if self.chr_ram {
+            state.write_bytes(&self.chr_rom.borrow());
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_16k_bank = state.read_u8()?;
+        self.prg_8k_bank = state.read_u8()?;
+        state.read_bytes_into(&mut self.chr_registers, "CHR registers")?;
+        self.banking_mode = state.read_u8()?;
+        self.mirroring = state.read_mirroring()?;
+        self.irq.load_state(state)?;
+        self.pulse1.load_state(state)?;
+        self.pulse2.load_state(state)?;
+        self.saw.load_state(state)?;
+        self.audio_halt = state.read_bool()?;
+        self.audio_shift = state.read_u8()?;
+        if self.chr_ram {
+            state.read_bytes_into(&mut self.chr_rom.borrow_mut(), "CHR-RAM")?;
+        }
+        Ok(())
+    }
+}
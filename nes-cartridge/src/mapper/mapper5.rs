@@ -0,0 +1,732 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+const EXRAM_SIZE: usize = 0x400;
+/// 连续这么多个 CPU 周期没有 PPU 读取，认为 PPU 已经停止渲染
+const IDLE_CYCLES_LIMIT: u8 = 3;
+/// 每条扫描线中背景图块的取数次数，32 个图块，每个图块取名称表、属性表和两次图案
+const BG_FETCHES: u16 = 32 * 4;
+/// 每条扫描线中精灵图案的取数次数，8 个精灵，每个精灵四次
+const SPRITE_FETCHES: u16 = 8 * 4;
+/// 扩展音频的帧计数器频率为 240Hz
+const AUDIO_FRAME_CYCLES: u16 = 7457;
+const PCM_SCALE: f32 = 0.25;
+
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+/// MMC5 方波声道，与 APU 的方波声道相同，但没有扫频单元
+#[derive(Default)]
+struct Mmc5Pulse {
+    enabled: bool,
+    duty: u8,
+    step: u8,
+    period: u16,
+    timer: u16,
+    length: u8,
+    halt: bool,
+    constant_volume: bool,
+    volume: u8,
+    envelope_start: bool,
+    envelope_divider: u8,
+    envelope_decay: u8,
+}
+
+impl Mmc5Pulse {
+    fn write(&mut self, register: u16, value: u8) {
+        match register & 0x03 {
+            0 => {
+                self.duty = value >> 6;
+                self.halt = value & 0x20 != 0;
+                self.constant_volume = value & 0x10 != 0;
+                self.volume = value & 0x0F;
+            }
+            2 => self.period = (self.period & 0x0700) | value as u16,
+            3 => {
+                self.period = (self.period & 0x00FF) | ((value as u16 & 0x07) << 8);
+                if self.enabled {
+                    self.length = LENGTH_TABLE[(value >> 3) as usize];
+                }
+                self.step = 0;
+                self.envelope_start = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.length = 0;
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) & 0x07;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        if self.envelope_start {
+            self.envelope_start = false;
+            self.envelope_decay = 15;
+            self.envelope_divider = self.volume;
+        } else if self.envelope_divider == 0 {
+            self.envelope_divider = self.volume;
+            if self.envelope_decay > 0 {
+                self.envelope_decay -= 1;
+            } else if self.halt {
+                self.envelope_decay = 15;
+            }
+        } else {
+            self.envelope_divider -= 1;
+        }
+    }
+
+    fn clock_length(&mut self) {
+        if !self.halt && self.length > 0 {
+            self.length -= 1;
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.enabled);
+        state.write_u8(self.duty);
+        state.write_u8(self.step);
+        state.write_u16(self.period);
+        state.write_u16(self.timer);
+        state.write_u8(self.length);
+        state.write_bool(self.halt);
+        state.write_bool(self.constant_volume);
+        state.write_u8(self.volume);
+        state.write_bool(self.envelope_start);
+        state.write_u8(self.envelope_divider);
+        state.write_u8(self.envelope_decay);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.enabled = state.read_bool()?;
+        self.duty = state.read_u8()? & 0x03;
+        self.step = state.read_u8()? & 0x07;
+        self.period = state.read_u16()?;
+        self.timer = state.read_u16()?;
+        self.length = state.read_u8()?;
+        self.halt = state.read_bool()?;
+        self.constant_volume = state.read_bool()?;
+        self.volume = state.read_u8()?;
+        self.envelope_start = state.read_bool()?;
+        self.envelope_divider = state.read_u8()?;
+        self.envelope_decay = state.read_u8()?;
+        Ok(())
+    }
+
+    fn output(&self) -> u8 {
+        if self.length == 0 || DUTY_TABLE[self.duty as usize][self.step as usize] == 0 {
+            0
+        } else if self.constant_volume {
+            self.volume
+        } else {
+            self.envelope_decay
+        }
+    }
+}
+
+/// PRG 地址映射到的存储器
+enum PrgTarget {
+    Rom(usize),
+    Ram(u16),
+}
+
+/// Nintendo MMC5 (Mapper 5)
+/// PRG: 四种 bank 模式 ($5100)，$8000-$DFFF 的 bank 可以选择 PRG-ROM 或 PRG-RAM
+/// CHR: 四种 bank 模式 ($5101)，8x16 精灵模式下精灵与背景使用两组独立的 bank
+/// ExRAM: 1KB 片上 RAM，可作为额外的名称表、扩展属性表或普通 RAM ($5104)
+/// 名称表: 四个名称表可分别映射到 VRAM 的两页、ExRAM 或填充数据 ($5105)
+/// IRQ: 通过监听 PPU 的取数检测扫描线，在指定扫描线产生 IRQ ($5203/$5204)
+/// 乘法器: $5205 * $5206 的 16 位乘积
+/// 扩展音频: 两个方波声道与一个 PCM 声道
+/// 未实现垂直分屏 ($5200-$5202)
+pub struct Mapper5 {
+    prg_mode: u8,
+    chr_mode: u8,
+    prg_ram_protect: [u8; 2],
+    exram_mode: u8,
+    nametable_mapping: u8,
+    fill_tile: u8,
+    fill_attribute: u8,
+    /// $5113-$5117
+    prg_registers: [u8; 5],
+    /// $5120-$5127，8x16 模式下用于精灵
+    chr_sprite_banks: [u16; 8],
+    /// $5128-$512B，8x16 模式下用于背景
+    chr_bg_banks: [u16; 4],
+    chr_upper: u8,
+    /// 非 8x16 模式时，使用最后一次写入的那组 CHR bank
+    last_chr_bg_write: bool,
+    exram: [u8; EXRAM_SIZE],
+    /// 扩展属性模式下当前图块对应的 ExRAM 数据
+    ex_attribute: u8,
+
+    sprite_8x16: bool,
+    rendering_enabled: bool,
+    in_frame: bool,
+    scanline: u8,
+    last_ppu_addr: u16,
+    same_addr_reads: u8,
+    /// 当前扫描线中 PPU 的取数次数
+    fetch_count: u16,
+    idle_cycles: u8,
+
+    irq_compare: u8,
+    irq_enabled: bool,
+    /// $5204 读取时清除，IRQ 允许时向 CPU 发出请求
+    irq_pending: Cell<bool>,
+
+    multiplicand: u8,
+    multiplier: u8,
+
+    pulse1: Mmc5Pulse,
+    pulse2: Mmc5Pulse,
+    pcm_read_mode: bool,
+    pcm_irq_enabled: bool,
+    pcm: Cell<u8>,
+    pcm_irq: Cell<bool>,
+    audio_cycles: u16,
+
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper5 {
+    pub fn new(
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper5 {
+            prg_mode: 3,
+            chr_mode: 0,
+            prg_ram_protect: [0, 0],
+            exram_mode: 0,
+            nametable_mapping: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            prg_registers: [0, 0xFF, 0xFF, 0xFF, 0xFF],
+            chr_sprite_banks: [0; 8],
+            chr_bg_banks: [0; 4],
+            chr_upper: 0,
+            last_chr_bg_write: false,
+            exram: [0; EXRAM_SIZE],
+            ex_attribute: 0,
+            sprite_8x16: false,
+            rendering_enabled: false,
+            in_frame: false,
+            scanline: 0,
+            last_ppu_addr: 0,
+            same_addr_reads: 0,
+            fetch_count: 0,
+            idle_cycles: 0,
+            irq_compare: 0,
+            irq_enabled: false,
+            irq_pending: Cell::new(false),
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            pulse1: Mmc5Pulse::default(),
+            pulse2: Mmc5Pulse::default(),
+            pcm_read_mode: false,
+            pcm_irq_enabled: false,
+            pcm: Cell::new(0),
+            pcm_irq: Cell::new(false),
+            audio_cycles: 0,
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+
+    fn prg_target(&self, addr: u16) -> PrgTarget {
+        // 寄存器下标与以 8KB 为单位的 bank 大小
+        let (index, size) = match (self.prg_mode, addr) {
+            (0, _) => (4, 4),
+            (1, 0x8000..0xC000) => (2, 2),
+            (1, _) => (4, 2),
+            (2, 0x8000..0xC000) => (2, 2),
+            (2, 0xC000..0xE000) => (3, 1),
+            (2, _) => (4, 1),
+            _ => (1 + (addr as usize - 0x8000) / 0x2000, 1),
+        };
+        let mut register = self.prg_registers[index];
+        if index == 4 {
+            // $5117 总是映射 PRG-ROM
+            register |= 0x80;
+        }
+        let bank =
+            (register as usize & 0x7F & !(size - 1)) + (addr as usize - 0x8000) / 0x2000 % size;
+        if register & 0x80 != 0 {
+            let len = self.prg_rom.borrow().len();
+            PrgTarget::Rom((bank * 0x2000 + (addr as usize & 0x1FFF)) % len)
+        } else {
+            PrgTarget::Ram(((bank & 0x07) * 0x2000 + (addr as usize & 0x1FFF)) as u16)
+        }
+    }
+
+    fn prg_ram_writable(&self) -> bool {
+        self.prg_ram_protect[0] & 0x03 == 0x02 && self.prg_ram_protect[1] & 0x03 == 0x01
+    }
+
+    fn sram_read(&self, offset: u16) -> u8 {
+        if let Some(ref sram) = self.sram {
+            sram.borrow().read(offset)
+        } else {
+            panic!("SRAM not available");
+        }
+    }
+
+    fn sram_write(&mut self, offset: u16, value: u8) {
+        if !self.prg_ram_writable() {
+            return;
+        }
+        if let Some(ref mut sram) = self.sram {
+            sram.borrow_mut().write(offset, value);
+        } else {
+            panic!("SRAM not available");
+        }
+    }
+
+    /// 当前扫描线是否处于精灵图案的取数阶段
+    fn in_sprite_fetch(&self) -> bool {
+        self.in_frame && (BG_FETCHES + 1..=BG_FETCHES + SPRITE_FETCHES).contains(&self.fetch_count)
+    }
+
+    /// 记录 PPU 的每次读取，连续三次读取同一个名称表地址表示新扫描线的开始
+    fn track_ppu_read(&mut self, addr: u16) {
+        self.idle_cycles = 0;
+        self.fetch_count = self.fetch_count.saturating_add(1);
+        if addr == self.last_ppu_addr && (0x2000..0x3000).contains(&addr) {
+            self.same_addr_reads += 1;
+            if self.same_addr_reads == 2 {
+                self.detect_scanline();
+            }
+        } else {
+            self.same_addr_reads = 0;
+        }
+        self.last_ppu_addr = addr;
+    }
+
+    fn detect_scanline(&mut self) {
+        // 检测发生在新扫描线的第一次名称表取数时
+        self.fetch_count = 1;
+        if !self.in_frame {
+            self.in_frame = true;
+            self.scanline = 0;
+            self.irq_pending.set(false);
+        } else {
+            self.scanline = self.scanline.wrapping_add(1);
+            if self.scanline == self.irq_compare {
+                self.irq_pending.set(true);
+            }
+        }
+    }
+
+    fn leave_frame(&mut self) {
+        self.in_frame = false;
+        self.same_addr_reads = 0;
+    }
+
+    /// 计算 PPU 地址对应的 1KB CHR bank
+    fn chr_bank(&self, addr: u16) -> usize {
+        let slot = addr as usize / 0x400;
+        // 以 1KB 为单位的 bank 大小
+        let size = 8 >> self.chr_mode;
+        let use_bg_banks = if self.sprite_8x16 && self.in_frame {
+            !self.in_sprite_fetch()
+        } else {
+            self.last_chr_bg_write
+        };
+        if use_bg_banks {
+            // 背景只有四个寄存器，[0x1000, 0x1FFF] 镜像 [0x0000, 0x0FFF]
+            let group = size.min(4);
+            let register = self.chr_bg_banks[(slot % 4 / group + 1) * group - 1];
+            register as usize * size + slot % size
+        } else {
+            let register = self.chr_sprite_banks[(slot / size + 1) * size - 1];
+            register as usize * size + slot % size
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5000..=0x5003 => self.pulse1.write(addr, value),
+            0x5004..=0x5007 => self.pulse2.write(addr, value),
+            0x5010 => {
+                self.pcm_read_mode = value & 0x01 != 0;
+                self.pcm_irq_enabled = value & 0x80 != 0;
+            }
+            // 写入 0 无效
+            0x5011 if !self.pcm_read_mode && value != 0 => self.pcm.set(value),
+            0x5015 => {
+                self.pulse1.set_enabled(value & 0x01 != 0);
+                self.pulse2.set_enabled(value & 0x02 != 0);
+            }
+            0x5100 => self.prg_mode = value & 0x03,
+            0x5101 => self.chr_mode = value & 0x03,
+            0x5102 => self.prg_ram_protect[0] = value,
+            0x5103 => self.prg_ram_protect[1] = value,
+            0x5104 => self.exram_mode = value & 0x03,
+            0x5105 => self.nametable_mapping = value,
+            0x5106 => self.fill_tile = value,
+            0x5107 => self.fill_attribute = value & 0x03,
+            0x5113..=0x5117 => self.prg_registers[(addr - 0x5113) as usize] = value,
+            0x5120..=0x5127 => {
+                self.chr_sprite_banks[(addr - 0x5120) as usize] =
+                    ((self.chr_upper as u16) << 8) | value as u16;
+                self.last_chr_bg_write = false;
+            }
+            0x5128..=0x512B => {
+                self.chr_bg_banks[(addr - 0x5128) as usize] =
+                    ((self.chr_upper as u16) << 8) | value as u16;
+                self.last_chr_bg_write = true;
+            }
+            0x5130 => self.chr_upper = value & 0x03,
+            0x5203 => self.irq_compare = value,
+            0x5204 => {
+                self.irq_enabled = value & 0x80 != 0;
+            }
+            0x5205 => self.multiplicand = value,
+            0x5206 => self.multiplier = value,
+            0x5C00..=0x5FFF => {
+                let offset = (addr - 0x5C00) as usize;
+                match self.exram_mode {
+                    // 作为名称表使用时，只有在渲染期间才能写入，否则写入 0
+                    0 | 1 => self.exram[offset] = if self.in_frame { value } else { 0 },
+                    2 => self.exram[offset] = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_register(&self, addr: u16) -> u8 {
+        match addr {
+            0x5010 => {
+                let irq = self.pcm_irq.replace(false);
+                ((irq as u8) << 7) | self.pcm_read_mode as u8
+            }
+            0x5015 => (self.pulse1.length > 0) as u8 | (((self.pulse2.length > 0) as u8) << 1),
+            0x5204 => {
+                let pending = self.irq_pending.replace(false);
+                ((pending as u8) << 7) | ((self.in_frame as u8) << 6)
+            }
+            0x5205 => (self.multiplicand as u16 * self.multiplier as u16) as u8,
+            0x5206 => ((self.multiplicand as u16 * self.multiplier as u16) >> 8) as u8,
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => self.exram[(addr - 0x5C00) as usize],
+            _ => 0,
+        }
+    }
+
+    fn clock_audio(&mut self) {
+        self.audio_cycles += 1;
+        if self.audio_cycles & 1 == 0 {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+        if self.audio_cycles >= AUDIO_FRAME_CYCLES {
+            self.audio_cycles = 0;
+            for pulse in [&mut self.pulse1, &mut self.pulse2] {
+                pulse.clock_envelope();
+                pulse.clock_length();
+            }
+        }
+    }
+}
+
+impl Mapper for Mapper5 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x5000..0x6000 => self.read_register(addr),
+            0x6000..0x8000 => {
+                // PRG-RAM
+                let bank = (self.prg_registers[0] & 0x07) as u16;
+                self.sram_read(bank * 0x2000 + (addr - 0x6000))
+            }
+            0x8000.. => {
+                let value = match self.prg_target(addr) {
+                    PrgTarget::Rom(offset) => self.prg_rom.borrow()[offset],
+                    PrgTarget::Ram(offset) => self.sram_read(offset),
+                };
+                // PCM 读取模式下，CPU 从 [0x8000, 0xBFFF] 读取的数据作为 PCM 采样
+                if self.pcm_read_mode && addr < 0xC000 {
+                    if value == 0 {
+                        self.pcm_irq.set(self.pcm_irq_enabled);
+                    } else {
+                        self.pcm.set(value);
+                    }
+                }
+                value
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5000..0x6000 => self.write_register(addr, value),
+            0x6000..0x8000 => {
+                // PRG-RAM
+                let bank = (self.prg_registers[0] & 0x07) as u16;
+                self.sram_write(bank * 0x2000 + (addr - 0x6000), value);
+            }
+            0x8000..0xE000 => {
+                if let PrgTarget::Ram(offset) = self.prg_target(addr) {
+                    self.sram_write(offset, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr >= 0x2000 {
+            panic!("PPU read out of range: {}", addr);
+        }
+        self.track_ppu_read(addr);
+        let chr_rom = self.chr_rom.borrow();
+        let offset = if self.exram_mode == 1 && self.in_frame && !self.in_sprite_fetch() {
+            // 扩展属性模式下，背景图块使用 ExRAM 指定的 4KB bank
+            let bank = (self.ex_attribute as usize & 0x3F) | ((self.chr_upper as usize) << 6);
+            bank * 0x1000 + (addr as usize & 0x0FFF)
+        } else {
+            self.chr_bank(addr) * 0x400 + (addr as usize & 0x3FF)
+        };
+        chr_rom[offset % chr_rom.len()]
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        // ExRAM 和填充模式的名称表由 nametable_read 处理，这里只关心 VRAM 的页
+        let page = |index: u8| (self.nametable_mapping >> (index * 2)) & 0x01;
+        Some(Mirroring::Mapped([page(0), page(1), page(2), page(3)]))
+    }
+
+    fn nametable_read(&mut self, addr: u16) -> Option<u8> {
+        self.track_ppu_read(addr);
+        let index = ((addr - 0x2000) % 0x1000) / 0x400;
+        let offset = (addr & 0x3FF) as usize;
+        let is_attribute = offset >= 0x3C0;
+        if self.exram_mode == 1 && self.in_frame && !self.in_sprite_fetch() {
+            // 扩展属性模式: 取名称表时记录 ExRAM 数据，取属性表时返回 ExRAM 中的调色板
+            if is_attribute {
+                return Some((self.ex_attribute >> 6) * 0x55);
+            }
+            self.ex_attribute = self.exram[offset];
+        }
+        match (self.nametable_mapping >> (index * 2)) & 0x03 {
+            2 if self.exram_mode <= 1 => Some(self.exram[offset]),
+            2 => Some(0),
+            3 if is_attribute => Some(self.fill_attribute * 0x55),
+            3 => Some(self.fill_tile),
+            _ => None,
+        }
+    }
+
+    fn nametable_write(&mut self, addr: u16, value: u8) -> bool {
+        let index = ((addr - 0x2000) % 0x1000) / 0x400;
+        match (self.nametable_mapping >> (index * 2)) & 0x03 {
+            2 => {
+                if self.exram_mode <= 1 {
+                    self.exram[(addr & 0x3FF) as usize] = value;
+                }
+                true
+            }
+            3 => true,
+            _ => false,
+        }
+    }
+
+    fn ppu_register_write(&mut self, addr: u16, value: u8) {
+        match (addr - 0x2000) % 8 {
+            0 => self.sprite_8x16 = value & 0x20 != 0,
+            1 => {
+                self.rendering_enabled = value & 0x18 != 0;
+                if !self.rendering_enabled {
+                    self.leave_frame();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.idle_cycles < IDLE_CYCLES_LIMIT {
+            self.idle_cycles += 1;
+            if self.idle_cycles == IDLE_CYCLES_LIMIT {
+                self.leave_frame();
+            }
+        }
+        self.clock_audio();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        (self.irq_enabled && self.irq_pending.get()) || self.pcm_irq.get()
+    }
+
+    fn audio_output(&self) -> f32 {
+        let pulse = (self.pulse1.output() + self.pulse2.output()) as f32;
+        let pulse_out = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+        pulse_out + self.pcm.get() as f32 / 255.0 * PCM_SCALE
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let chr_count = self.chr_rom.borrow().len() / 0x400;
+        let map = BankMap::new().cpu(
+            0x6000,
+            0x2000,
+            BankMemory::PrgRam,
+            (self.prg_registers[0] & 0x07) as usize,
+        );
+        let map = (0..4).fold(map, |map, i| {
+            let addr = 0x8000 + i * 0x2000;
+            match self.prg_target(addr) {
+                PrgTarget::Rom(offset) => {
+                    map.cpu(addr, 0x2000, BankMemory::PrgRom, offset / 0x2000)
+                }
+                PrgTarget::Ram(offset) => {
+                    map.cpu(addr, 0x2000, BankMemory::PrgRam, offset as usize / 0x2000)
+                }
+            }
+        });
+        let map = (0..8).fold(map, |map, slot| {
+            let addr = slot * 0x400;
+            let bank = self.chr_bank(addr) % chr_count;
+            map.ppu(addr, 0x400, BankMemory::ChrRom, bank)
+        });
+        (0..4)
+            .fold(map, |map, i| {
+                let addr = 0x2000 + i * 0x400;
+                match (self.nametable_mapping >> (i * 2)) & 0x03 {
+                    page @ (0 | 1) => map.ppu(addr, 0x400, BankMemory::Vram, page as usize),
+                    2 => map.ppu(addr, 0x400, BankMemory::MapperRam, 0),
+                    _ => map.ppu(addr, 0x400, BankMemory::Fill, 0),
+                }
+            })
+            .register("ExRAM mode", self.exram_mode as u32)
+            .register("scanline", self.scanline as u32)
+            .register("in frame", self.in_frame as u32)
+            .register("IRQ compare", self.irq_compare as u32)
+            .register("IRQ enabled", self.irq_enabled as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_mode);
+        state.write_u8(self.chr_mode);
+        state.write_bytes(&self.prg_ram_protect);
+        state.write_u8(self.exram_mode);
+        state.write_u8(self.nametable_mapping);
+        state.write_u8(self.fill_tile);
+        state.write_u8(self.fill_attribute);
+        state.write_bytes(&self.prg_registers);
+        for bank in self.chr_sprite_banks.iter().chain(&self.chr_bg_banks) {
+            state.write_u16(*bank);
+        }
+        state.write_u8(self.chr_upper);
+        state.write_bool(self.last_chr_bg_write);
+        state.write_bytes(&self.exram);
+        state.write_u8(self.ex_attribute);
+        state.write_bool(self.sprite_8x16);
+        state.write_bool(self.rendering_enabled);
+        state.write_bool(self.in_frame);
+        state.write_u8(self.scanline);
+        state.write_u16(self.last_ppu_addr);
+        state.write_u8(self.same_addr_reads);
+        state.write_u16(self.fetch_count);
+        state.write_u8(self.idle_cycles);
+        state.write_u8(self.irq_compare);
+        state.write_bool(self.irq_enabled);
+        state.write_bool(self.irq_pending.get());
+        state.write_u8(self.multiplicand);
+        state.write_u8(self.multiplier);
+        self.pulse1.save_state(state);
+        self.pulse2.save_state(state);
+        state.write_bool(self.pcm_read_mode);
+        state.write_bool(self.pcm_irq_enabled);
+        state.write_u8(self.pcm.get());
+        state.write_bool(self.pcm_irq.get());
+        state.write_u16(self.audio_cycles);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_mode = state.read_u8()? & 0x03;
+        self.chr_mode = state.read_u8()? & 0x03;
+        state.read_bytes_into(&mut self.prg_ram_protect, "PRG-RAM protect")?;
+        self.exram_mode = state.read_u8()? & 0x03;
+        self.nametable_mapping = state.read_u8()?;
+        self.fill_tile = state.read_u8()?;
+        self.fill_attribute = state.read_u8()? & 0x03;
+        state.read_bytes_into(&mut self.prg_registers, "PRG registers")?;
+        for bank in self
+            .chr_sprite_banks
+            .iter_mut()
+            .chain(self.chr_bg_banks.iter_mut())
+        {
+            *bank = state.read_u16()?;
+        }
+        self.chr_upper = state.read_u8()?;
+        self.last_chr_bg_write = state.read_bool()?;
+        state.read_bytes_into(&mut self.exram, "ExRAM")?;
+        self.ex_attribute = state.read_u8()?;
+        self.sprite_8x16 = state.read_bool()?;
+        self.rendering_enabled = state.read_bool()?;
+        self.in_frame = state.read_bool()?;
+        self.scanline = state.read_u8()?;
+        self.last_ppu_addr = state.read_u16()?;
+        self.same_addr_reads = state.read_u8()?;
+        self.fetch_count = state.read_u16()?;
+        self.idle_cycles = state.read_u8()?;
+        self.irq_compare = state.read_u8()?;
+        self.irq_enabled = state.read_bool()?;
+        self.irq_pending.set(state.read_bool()?);
+        self.multiplicand = state.read_u8()?;
+        self.multiplier = state.read_u8()?;
+        self.pulse1.load_state(state)?;
+        self.pulse2.load_state(state)?;
+        self.pcm_read_mode = state.read_bool()?;
+        self.pcm_irq_enabled = state.read_bool()?;
+        self.pcm.set(state.read_u8()?);
+        self.pcm_irq.set(state.read_bool()?);
+        self.audio_cycles = state.read_u16()?;
+        Ok(())
+    }
+}
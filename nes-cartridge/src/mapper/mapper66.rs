@@ -0,0 +1,126 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::Ram;
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// GxROM
+/// PRG-ROM 按 32KB 切换，CHR-ROM 按 8KB 切换
+/// 写入 [0x8000, 0xFFFF]:
+///   bit 4-5 选择 32KB 的 PRG bank
+///   bit 0-1 选择 8KB 的 CHR bank
+pub struct Mapper66 {
+    prg_bank: u8,
+    chr_bank: u8,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper66 {
+    pub fn new(
+        _prg_banks: u8,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper66 {
+            prg_bank: 0,
+            chr_bank: 0,
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+}
+
+impl Mapper for Mapper66 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG ROM 32KB Bank
+                let prg_rom = self.prg_rom.borrow();
+                let offset = self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000);
+                prg_rom[offset % prg_rom.len()]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG/CHR ROM Bank selection
+                self.prg_bank = (value >> 4) & 0x03;
+                self.chr_bank = value & 0x03;
+            }
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 8KB Bank
+            let chr_rom = self.chr_rom.borrow();
+            let offset = self.chr_bank as usize * 0x2000 + addr as usize;
+            chr_rom[offset % chr_rom.len()]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let prg_count = self.prg_rom.borrow().len() / 0x8000;
+        let chr_count = self.chr_rom.borrow().len() / 0x2000;
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(
+                0x8000,
+                0x8000,
+                BankMemory::PrgRom,
+                self.prg_bank as usize % prg_count.max(1),
+            )
+            .ppu(
+                0x0000,
+                0x2000,
+                BankMemory::ChrRom,
+                self.chr_bank as usize % chr_count.max(1),
+            )
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank);
+        state.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_bank = state.read_u8()?;
+        self.chr_bank = state.read_u8()?;
+        Ok(())
+    }
+}
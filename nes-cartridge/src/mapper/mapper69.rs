@@ -0,0 +1,450 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// 5B 的内部时钟为 CPU 时钟的 1/16
+const AUDIO_DIVIDER: u8 = 16;
+/// 三个声道同时以最大音量输出时的缩放系数
+const AUDIO_SCALE: f32 = 0.25 / 3.0;
+
+/// Sunsoft 5B 的方波声道
+#[derive(Default)]
+struct ToneChannel {
+    period: u16,
+    counter: u16,
+    output: bool,
+    /// bit0-3 音量，bit4 使用包络
+    volume: u8,
+}
+
+impl ToneChannel {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u16(self.period);
+        state.write_u16(self.counter);
+        state.write_bool(self.output);
+        state.write_u8(self.volume);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.period = state.read_u16()?;
+        self.counter = state.read_u16()?;
+        self.output = state.read_bool()?;
+        self.volume = state.read_u8()?;
+        Ok(())
+    }
+
+    fn clock(&mut self) {
+        self.counter += 1;
+        if self.counter >= self.period.max(1) {
+            self.counter = 0;
+            self.output = !self.output;
+        }
+    }
+}
+
+/// Sunsoft 5B 扩展音频，即 YM2149F (AY-3-8910) 的一个变种
+/// 三个方波声道，共用一个噪声发生器和一个包络发生器
+/// 通过 $C000 选择寄存器，$E000 写入数据
+#[derive(Default)]
+struct Sunsoft5B {
+    register: u8,
+    tones: [ToneChannel; 3],
+    noise_period: u8,
+    noise_counter: u8,
+    /// 17 位线性反馈移位寄存器
+    noise_shift: u32,
+    /// bit0-2 关闭方波，bit3-5 关闭噪声
+    mixer: u8,
+    envelope_period: u16,
+    envelope_counter: u16,
+    envelope_step: u8,
+    envelope_continue: bool,
+    envelope_attack: bool,
+    envelope_alternate: bool,
+    envelope_hold: bool,
+    envelope_holding: bool,
+    divider: u8,
+}
+
+impl Sunsoft5B {
+    fn new() -> Self {
+        Sunsoft5B {
+            noise_shift: 1,
+            // 上电时所有声道都被关闭
+            mixer: 0x3F,
+            ..Default::default()
+        }
+    }
+
+    fn write(&mut self, value: u8) {
+        match self.register {
+            0x00 | 0x02 | 0x04 => {
+                let tone = &mut self.tones[self.register as usize / 2];
+                tone.period = (tone.period & 0x0F00) | value as u16;
+            }
+            0x01 | 0x03 | 0x05 => {
+                let tone = &mut self.tones[self.register as usize / 2];
+                tone.period = (tone.period & 0x00FF) | ((value as u16 & 0x0F) << 8);
+            }
+            0x06 => self.noise_period = value & 0x1F,
+            0x07 => self.mixer = value,
+            0x08..=0x0A => self.tones[self.register as usize - 0x08].volume = value & 0x1F,
+            0x0B => self.envelope_period = (self.envelope_period & 0xFF00) | value as u16,
+            0x0C => self.envelope_period = (self.envelope_period & 0x00FF) | ((value as u16) << 8),
+            0x0D => {
+                self.envelope_continue = value & 0x08 != 0;
+                self.envelope_attack = value & 0x04 != 0;
+                self.envelope_alternate = value & 0x02 != 0;
+                self.envelope_hold = value & 0x01 != 0;
+                self.envelope_holding = false;
+                self.envelope_step = 0;
+                self.envelope_counter = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn clock(&mut self) {
+        self.divider += 1;
+        if self.divider < AUDIO_DIVIDER {
+            return;
+        }
+        self.divider = 0;
+
+        for tone in &mut self.tones {
+            tone.clock();
+        }
+
+        self.noise_counter += 1;
+        if self.noise_counter >= self.noise_period.max(1) * 2 {
+            self.noise_counter = 0;
+            let feedback = (self.noise_shift ^ (self.noise_shift >> 3)) & 0x01;
+            self.noise_shift = (self.noise_shift >> 1) | (feedback << 16);
+        }
+
+        self.envelope_counter += 1;
+        if self.envelope_counter >= self.envelope_period.max(1) {
+            self.envelope_counter = 0;
+            self.clock_envelope();
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.register);
+        for tone in &self.tones {
+            tone.save_state(state);
+        }
+        state.write_u8(self.noise_period);
+        state.write_u8(self.noise_counter);
+        state.write_u32(self.noise_shift);
+        state.write_u8(self.mixer);
+        state.write_u16(self.envelope_period);
+        state.write_u16(self.envelope_counter);
+        state.write_u8(self.envelope_step);
+        state.write_bool(self.envelope_continue);
+        state.write_bool(self.envelope_attack);
+        state.write_bool(self.envelope_alternate);
+        state.write_bool(self.envelope_hold);
+        state.write_bool(self.envelope_holding);
+        state.write_u8(self.divider);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.register = state.read_u8()? & 0x0F;
+        for tone in &mut self.tones {
+            tone.load_state(state)?;
+        }
+        self.noise_period = state.read_u8()? & 0x1F;
+        self.noise_counter = state.read_u8()?;
+        self.noise_shift = state.read_u32()?;
+        self.mixer = state.read_u8()?;
+        self.envelope_period = state.read_u16()?;
+        self.envelope_counter = state.read_u16()?;
+        self.envelope_step = state.read_u8()? & 0x0F;
+        self.envelope_continue = state.read_bool()?;
+        self.envelope_attack = state.read_bool()?;
+        self.envelope_alternate = state.read_bool()?;
+        self.envelope_hold = state.read_bool()?;
+        self.envelope_holding = state.read_bool()?;
+        self.divider = state.read_u8()?;
+        Ok(())
+    }
+
+    fn clock_envelope(&mut self) {
+        if self.envelope_holding {
+            return;
+        }
+        if self.envelope_step < 15 {
+            self.envelope_step += 1;
+            return;
+        }
+        // 一个周期结束
+        if !self.envelope_continue {
+            self.envelope_holding = true;
+            self.envelope_attack = false;
+            self.envelope_step = 15;
+        } else if self.envelope_hold {
+            self.envelope_holding = true;
+            if self.envelope_alternate {
+                self.envelope_attack = !self.envelope_attack;
+            }
+        } else {
+            if self.envelope_alternate {
+                self.envelope_attack = !self.envelope_attack;
+            }
+            self.envelope_step = 0;
+        }
+    }
+
+    fn envelope_volume(&self) -> u8 {
+        if self.envelope_attack {
+            self.envelope_step
+        } else {
+            15 - self.envelope_step
+        }
+    }
+
+    /// 5B 的音量是对数的，每一级相差 3dB
+    fn level(volume: u8) -> f32 {
+        if volume == 0 {
+            0.0
+        } else {
+            10f32.powf((volume as f32 - 15.0) * 0.15)
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let noise = self.noise_shift & 0x01 != 0;
+        self.tones
+            .iter()
+            .enumerate()
+            .map(|(i, tone)| {
+                let tone_on = tone.output || self.mixer & (1 << i) != 0;
+                let noise_on = noise || self.mixer & (0x08 << i) != 0;
+                if !(tone_on && noise_on) {
+                    return 0.0;
+                }
+                if tone.volume & 0x10 != 0 {
+                    Self::level(self.envelope_volume())
+                } else {
+                    Self::level(tone.volume & 0x0F)
+                }
+            })
+            .sum()
+    }
+}
+
+/// Sunsoft FME-7 (Mapper 69)，以及带有 5B 扩展音频的 Sunsoft 5B
+/// 通过 $8000 选择命令，$A000 写入参数:
+///   $0-$7 CHR 1KB bank, $8 [0x6000, 0x7FFF] 的 PRG-ROM/PRG-RAM bank,
+///   $9-$B PRG 8KB bank, $C 镜像方式, $D IRQ 控制, $E/$F IRQ 计数器低/高字节
+/// PRG-ROM: [0xE000, 0xFFFF] 固定为最后一个 8KB bank
+/// IRQ: 16 位计数器每个 CPU 周期减一，从 $0000 下溢到 $FFFF 时产生 IRQ
+pub struct Mapper69 {
+    command: u8,
+    chr_banks: [u8; 8],
+    prg_banks: [u8; 3],
+    /// 命令 $8: bit0-5 bank, bit6 选择 PRG-RAM, bit7 启用 PRG-RAM
+    prg_6000: u8,
+    mirroring: Mirroring,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_counter: u16,
+    irq_pending: bool,
+    audio: Sunsoft5B,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper69 {
+    pub fn new(
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper69 {
+            command: 0,
+            chr_banks: [0; 8],
+            prg_banks: [0; 3],
+            prg_6000: 0,
+            mirroring: Mirroring::Vertical,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+            irq_pending: false,
+            audio: Sunsoft5B::new(),
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+
+    fn prg_rom_read(&self, bank: usize, addr: u16) -> u8 {
+        let prg_rom = self.prg_rom.borrow();
+        prg_rom[(bank * 0x2000 + (addr as usize & 0x1FFF)) % prg_rom.len()]
+    }
+
+    fn write_parameter(&mut self, value: u8) {
+        match self.command {
+            0x0..=0x7 => self.chr_banks[self.command as usize] = value,
+            0x8 => self.prg_6000 = value,
+            0x9..=0xB => self.prg_banks[self.command as usize - 0x9] = value & 0x3F,
+            0xC => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            0xD => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_counter_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | value as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16) << 8),
+        }
+    }
+}
+
+impl Mapper for Mapper69 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                if self.prg_6000 & 0x40 == 0 {
+                    self.prg_rom_read((self.prg_6000 & 0x3F) as usize, addr)
+                } else if let (true, Some(sram)) = (self.prg_6000 & 0x80 != 0, &self.sram) {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    // PRG-RAM 未启用时为开路总线
+                    0
+                }
+            }
+            0x8000..0xE000 => {
+                let bank = self.prg_banks[(addr as usize - 0x8000) / 0x2000] as usize;
+                self.prg_rom_read(bank, addr)
+            }
+            0xE000.. => {
+                let bank_count = self.prg_rom.borrow().len() / 0x2000;
+                self.prg_rom_read(bank_count - 1, addr)
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                if self.prg_6000 & 0xC0 == 0xC0
+                    && let Some(ref mut sram) = self.sram
+                {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                }
+            }
+            0x8000..0xA000 => self.command = value & 0x0F,
+            0xA000..0xC000 => self.write_parameter(value),
+            0xC000..0xE000 => self.audio.register = value & 0x0F,
+            0xE000.. => self.audio.write(value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM 1KB Bank
+            let chr_rom = self.chr_rom.borrow();
+            let bank = self.chr_banks[addr as usize / 0x400] as usize;
+            chr_rom[(bank * 0x400 + (addr as usize & 0x3FF)) % chr_rom.len()]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn clock(&mut self) {
+        if self.irq_counter_enabled {
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+            if self.irq_counter == 0xFFFF && self.irq_enabled {
+                self.irq_pending = true;
+            }
+        }
+        self.audio.clock();
+    }
+
+    fn check_irq_interrupt(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output() * AUDIO_SCALE
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let prg_count = self.prg_rom.borrow().len() / 0x2000;
+        let chr_count = self.chr_rom.borrow().len() / 0x400;
+        let map = BankMap::new();
+        let map = if self.prg_6000 & 0x40 == 0 {
+            let bank = (self.prg_6000 & 0x3F) as usize % prg_count;
+            map.cpu(0x6000, 0x2000, BankMemory::PrgRom, bank)
+        } else {
+            map.prg_ram(self.sram.is_some() && self.prg_6000 & 0x80 != 0)
+        };
+        let map = (0..3).fold(map, |map, i| {
+            let bank = self.prg_banks[i] as usize % prg_count;
+            map.cpu(0x8000 + i as u16 * 0x2000, 0x2000, BankMemory::PrgRom, bank)
+        });
+        (0..8)
+            .fold(
+                map.cpu(0xE000, 0x2000, BankMemory::PrgRom, prg_count - 1),
+                |map, slot| {
+                    let bank = self.chr_banks[slot] as usize % chr_count;
+                    map.ppu(slot as u16 * 0x400, 0x400, BankMemory::ChrRom, bank)
+                },
+            )
+            .nametables(self.mirroring)
+            .register("IRQ counter", self.irq_counter as u32)
+            .register("IRQ enabled", self.irq_enabled as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.command);
+        state.write_bytes(&self.chr_banks);
+        state.write_bytes(&self.prg_banks);
+        state.write_u8(self.prg_6000);
+        state.write_mirroring(self.mirroring);
+        state.write_bool(self.irq_enabled);
+        state.write_bool(self.irq_counter_enabled);
+        state.write_u16(self.irq_counter);
+        state.write_bool(self.irq_pending);
+        self.audio.save_state(state);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.command = state.read_u8()? & 0x0F;
+        state.read_bytes_into(&mut self.chr_banks, "CHR banks")?;
+        state.read_bytes_into(&mut self.prg_banks, "PRG banks")?;
+        self.prg_6000 = state.read_u8()?;
+        self.mirroring = state.read_mirroring()?;
+        self.irq_enabled = state.read_bool()?;
+        self.irq_counter_enabled = state.read_bool()?;
+        self.irq_counter = state.read_u16()?;
+        self.irq_pending = state.read_bool()?;
+        self.audio.load_state(state)
+    }
+}
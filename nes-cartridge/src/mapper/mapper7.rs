@@ -0,0 +1,127 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// AxROM
+/// PRG-ROM 按 32KB 切换，CHR 为 8KB 的 CHR-RAM
+/// 写入 [0x8000, 0xFFFF]:
+///   bit 0-2 选择 32KB 的 PRG bank
+///   bit 4 选择单屏镜像使用的名称表
+pub struct Mapper7 {
+    prg_banks: u8,
+    prg_bank: u8,
+    mirroring: Mirroring,
+    chr_ram: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper7 {
+    pub fn new(
+        prg_banks: u8,
+        chr_ram: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        chr_ram.borrow_mut().resize(0x2000, 0);
+        Mapper7 {
+            prg_banks,
+            prg_bank: 0,
+            mirroring: Mirroring::SingleScreenLower,
+            chr_ram,
+            prg_rom,
+            sram,
+        }
+    }
+}
+
+impl Mapper for Mapper7 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG ROM 32KB Bank
+                let prg_rom = self.prg_rom.borrow();
+                prg_rom
+                    [(self.prg_bank as usize * 0x8000 + (addr as usize - 0x8000)) % prg_rom.len()]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000.. => {
+                // PRG ROM Bank selection && 单屏镜像选择
+                let prg_banks_32k = (self.prg_banks / 2).max(1);
+                self.prg_bank = (value & 0x07) % prg_banks_32k;
+                self.mirroring = if value & 0x10 != 0 {
+                    Mirroring::SingleScreenUpper
+                } else {
+                    Mirroring::SingleScreenLower
+                };
+            }
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR RAM
+            self.chr_ram.borrow()[addr as usize]
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            // CHR RAM
+            self.chr_ram.borrow_mut()[addr as usize] = value;
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn bank_map(&self) -> BankMap {
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(0x8000, 0x8000, BankMemory::PrgRom, self.prg_bank as usize)
+            .ppu(0x0000, 0x2000, BankMemory::ChrRam, 0)
+            .nametables(self.mirroring)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank);
+        state.write_mirroring(self.mirroring);
+        state.write_bytes(&self.chr_ram.borrow());
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_bank = state.read_u8()?;
+        self.mirroring = state.read_mirroring()?;
+        state.read_bytes_into(&mut self.chr_ram.borrow_mut(), "CHR-RAM")?;
+        Ok(())
+    }
+}
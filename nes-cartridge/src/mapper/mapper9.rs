@@ -0,0 +1,269 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::{Mirroring, Ram};
+
+use crate::{BankMap, BankMemory, StateError, StateReader, StateWriter, mapper::Mapper};
+
+/// MMC2/MMC4 的 CHR 锁存器
+/// [0x0000, 0x1000) 和 [0x1000, 0x2000) 两个 4KB 窗口各有一个锁存器，
+/// 每个锁存器在 $FD 和 $FE 两个 bank 之间选择，
+/// PPU 读取特定图案 ($FD/$FE 图块的最后一行) 后锁存器自动切换
+pub struct ChrLatch {
+    /// 每个窗口的 $FD/$FE bank 编号，[窗口][锁存器]
+    banks: [[u8; 2]; 2],
+    /// 每个窗口当前的锁存器状态，0 表示 $FD，1 表示 $FE
+    latches: [usize; 2],
+    /// MMC2 的低窗口只在读取 $0FD8/$0FE8 时切换，MMC4 则是整个 8 字节范围
+    exact_low_trigger: bool,
+}
+
+impl ChrLatch {
+    pub fn new(exact_low_trigger: bool) -> Self {
+        Self {
+            banks: [[0; 2]; 2],
+            latches: [1, 1],
+            exact_low_trigger,
+        }
+    }
+
+    /// 设置窗口 `window` 在锁存器为 `latch` 时使用的 4KB bank
+    pub fn set_bank(&mut self, window: usize, latch: usize, bank: u8) {
+        self.banks[window][latch] = bank & 0x1F;
+    }
+
+    /// 获取地址对应的 CHR-ROM 偏移
+    pub fn chr_offset(&self, addr: u16) -> usize {
+        let window = (addr as usize >> 12) & 1;
+        self.bank(window) * 0x1000 + (addr as usize & 0x0FFF)
+    }
+
+    /// 窗口当前使用的 4KB bank
+    pub fn bank(&self, window: usize) -> usize {
+        self.banks[window][self.latches[window]] as usize
+    }
+
+    /// 窗口当前的锁存器状态，$FD 或 $FE
+    pub fn latch(&self, window: usize) -> u8 {
+        0xFD + self.latches[window] as u8
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        for window in 0..2 {
+            state.write_u8(self.banks[window][0]);
+            state.write_u8(self.banks[window][1]);
+            state.write_u8(self.latches[window] as u8);
+        }
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        for window in 0..2 {
+            self.banks[window][0] = state.read_u8()?;
+            self.banks[window][1] = state.read_u8()?;
+            self.latches[window] = (state.read_u8()? & 1) as usize;
+        }
+        Ok(())
+    }
+
+    /// 在 PPU 读取之后更新锁存器，切换在下一次读取时生效
+    pub fn update(&mut self, addr: u16) {
+        match addr {
+            0x0FD8 => self.latches[0] = 0,
+            0x0FE8 => self.latches[0] = 1,
+            0x0FD9..=0x0FDF if !self.exact_low_trigger => self.latches[0] = 0,
+            0x0FE9..=0x0FEF if !self.exact_low_trigger => self.latches[0] = 1,
+            0x1FD8..=0x1FDF => self.latches[1] = 0,
+            0x1FE8..=0x1FEF => self.latches[1] = 1,
+            _ => {}
+        }
+    }
+}
+
+/// MMC2 (PxROM)
+/// PRG-ROM: [0x8000, 0xA000) 8KB 可切换，[0xA000, 0xFFFF] 固定为最后三个 8KB bank
+/// CHR-ROM: 两个 4KB 窗口，由锁存器自动切换
+/// 寄存器:
+///   [0xA000, 0xB000) PRG bank
+///   [0xB000, 0xC000) 低窗口 $FD bank
+///   [0xC000, 0xD000) 低窗口 $FE bank
+///   [0xD000, 0xE000) 高窗口 $FD bank
+///   [0xE000, 0xF000) 高窗口 $FE bank
+///   [0xF000, 0xFFFF] 镜像方式，0 为垂直，1 为水平
+pub struct Mapper9 {
+    prg_bank: u8,
+    chr_latch: ChrLatch,
+    mirroring: Mirroring,
+    chr_rom: Rc<RefCell<Vec<u8>>>,
+    prg_rom: Rc<RefCell<Vec<u8>>>,
+    sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+impl Mapper9 {
+    pub fn new(
+        _prg_banks: u8,
+        chr_rom: Rc<RefCell<Vec<u8>>>,
+        prg_rom: Rc<RefCell<Vec<u8>>>,
+        sram: Option<Rc<RefCell<dyn Ram>>>,
+    ) -> Self {
+        Mapper9 {
+            prg_bank: 0,
+            chr_latch: ChrLatch::new(true),
+            mirroring: Mirroring::Vertical,
+            chr_rom,
+            prg_rom,
+            sram,
+        }
+    }
+
+    /// [0xA000, 0xFFFF] 中第 window 个 8KB 窗口固定的 bank，即最后三个 bank
+    /// PRG-ROM 不足 24KB 时按 bank 数回绕，$E000 总是最后一个 bank
+    fn fixed_bank(bank_count: usize, window: usize) -> usize {
+        (bank_count * 3 + window - 3) % bank_count
+    }
+}
+
+/// MMC2/MMC4 共用的寄存器写入，返回 false 表示地址不属于这些寄存器
+pub fn write_latch_register(
+    chr_latch: &mut ChrLatch,
+    mirroring: &mut Mirroring,
+    addr: u16,
+    value: u8,
+) -> bool {
+    match addr {
+        0xB000..0xC000 => chr_latch.set_bank(0, 0, value),
+        0xC000..0xD000 => chr_latch.set_bank(0, 1, value),
+        0xD000..0xE000 => chr_latch.set_bank(1, 0, value),
+        0xE000..0xF000 => chr_latch.set_bank(1, 1, value),
+        0xF000.. => {
+            *mirroring = if value & 1 == 0 {
+                Mirroring::Vertical
+            } else {
+                Mirroring::Horizontal
+            };
+        }
+        _ => return false,
+    }
+    true
+}
+
+impl Mapper for Mapper9 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref sram) = self.sram {
+                    sram.borrow().read(addr - 0x6000)
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0x8000..0xA000 => {
+                // PRG ROM 8KB 可切换 Bank
+                let prg_rom = self.prg_rom.borrow();
+                let offset = self.prg_bank as usize * 0x2000 + (addr as usize - 0x8000);
+                prg_rom[offset % prg_rom.len()]
+            }
+            0xA000.. => {
+                // PRG ROM 固定为最后 24KB
+                let prg_rom = self.prg_rom.borrow();
+                let window = (addr as usize - 0xA000) / 0x2000;
+                let bank = Self::fixed_bank(prg_rom.len() / 0x2000, window);
+                prg_rom[bank * 0x2000 + (addr as usize & 0x1FFF)]
+            }
+            _ => panic!("Address out of range: {}", addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..0x8000 => {
+                // SRAM
+                if let Some(ref mut sram) = self.sram {
+                    sram.borrow_mut().write(addr - 0x6000, value);
+                } else {
+                    panic!("SRAM not available");
+                }
+            }
+            0xA000..0xB000 => {
+                // PRG ROM Bank selection
+                self.prg_bank = value & 0x0F;
+            }
+            0x8000.. => {
+                write_latch_register(&mut self.chr_latch, &mut self.mirroring, addr, value);
+            }
+            _ => panic!("Write out of range: {}", addr),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        if addr < 0x2000 {
+            // CHR ROM
+            let value = {
+                let chr_rom = self.chr_rom.borrow();
+                chr_rom[self.chr_latch.chr_offset(addr) % chr_rom.len()]
+            };
+            self.chr_latch.update(addr);
+            value
+        } else {
+            panic!("PPU read out of range: {}", addr);
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, _: u8) {
+        if addr < 0x2000 {
+            // CHR ROM is read-only, no write operation
+            panic!("Cannot write to CHR ROM at address: {}", addr);
+        } else {
+            panic!("PPU write out of range: {}", addr);
+        }
+    }
+
+    fn mirroring(&self) -> Option<Mirroring> {
+        Some(self.mirroring)
+    }
+
+    fn bank_map(&self) -> BankMap {
+        let bank_count = self.prg_rom.borrow().len() / 0x2000;
+        BankMap::new()
+            .prg_ram(self.sram.is_some())
+            .cpu(
+                0x8000,
+                0x2000,
+                BankMemory::PrgRom,
+                self.prg_bank as usize % bank_count,
+            )
+            .cpu(
+                0xA000,
+                0x2000,
+                BankMemory::PrgRom,
+                Self::fixed_bank(bank_count, 0),
+            )
+            .cpu(
+                0xC000,
+                0x2000,
+                BankMemory::PrgRom,
+                Self::fixed_bank(bank_count, 1),
+            )
+            .cpu(
+                0xE000,
+                0x2000,
+                BankMemory::PrgRom,
+                Self::fixed_bank(bank_count, 2),
+            )
+            .ppu(0x0000, 0x1000, BankMemory::ChrRom, self.chr_latch.bank(0))
+            .ppu(0x1000, 0x1000, BankMemory::ChrRom, self.chr_latch.bank(1))
+            .register("latch 0", self.chr_latch.latch(0) as u32)
+            .register("latch 1", self.chr_latch.latch(1) as u32)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.prg_bank);
+        state.write_mirroring(self.mirroring);
+        self.chr_latch.save_state(state);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.prg_bank = state.read_u8()?;
+        self.mirroring = state.read_mirroring()?;
+        self.chr_latch.load_state(state)
+    }
+}
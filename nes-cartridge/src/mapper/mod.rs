@@ -1,29 +1,67 @@
-use std::{cell::RefCell, rc::Rc};
+use nes_base::Mirroring;
 
-use nes_base::Ram;
-
-use crate::mapper::{mapper0::Mapper0, mapper2::Mapper2};
+use crate::{BankMap, StateError, StateReader, StateWriter};
 
 mod mapper0;
+mod mapper10;
+mod mapper11;
+mod mapper19;
 mod mapper2;
+mod mapper21;
+mod mapper24;
+mod mapper3;
+mod mapper5;
+mod mapper66;
+mod mapper69;
+mod mapper7;
+mod mapper9;
+mod registry;
+
+pub use registry::{MapperConstructor, MapperContext, MapperEntry, MapperRegistry};
 
+/// 卡带上的 Mapper 芯片，其他 crate 可以实现后注册到 MapperRegistry
 pub trait Mapper {
     fn cpu_read(&self, addr: u16) -> u8;
     fn cpu_write(&mut self, addr: u16, value: u8);
-    fn ppu_read(&self, addr: u16) -> u8;
+    /// PPU 读取需要可变引用，以便 Mapper 可以观察 PPU 的取数过程
+    fn ppu_read(&mut self, addr: u16) -> u8;
     fn ppu_write(&mut self, addr: u16, value: u8);
-}
 
-pub fn get_mapper_by_id(
-    mapper_id: u8,
-    prg_banks: u8,
-    chr_rom: Rc<RefCell<Vec<u8>>>,
-    prg_rom: Rc<RefCell<Vec<u8>>>,
-    sram: Option<Rc<RefCell<dyn Ram>>>,
-) -> Box<dyn Mapper> {
-    match mapper_id {
-        0 => Box::new(Mapper0::new(prg_banks, chr_rom, prg_rom, sram)),
-        2 => Box::new(Mapper2::new(prg_banks, chr_rom, prg_rom, sram)),
-        _ => panic!("Unsupported mapper ID: {}", mapper_id),
+    /// 当前的 bank 映射与 IRQ 计数器等内部寄存器，供调试器显示
+    fn bank_map(&self) -> BankMap;
+    /// 保存内部寄存器，PRG-RAM 由卡带统一保存，CHR-RAM 由 Mapper 自己保存
+    fn save_state(&self, state: &mut StateWriter);
+    /// 恢复 save_state 保存的内部寄存器
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError>;
+
+    /// 由 Mapper 控制的镜像方式，返回 None 时使用文件头中的镜像方式
+    fn mirroring(&self) -> Option<Mirroring> {
+        None
+    }
+
+    /// 每个 CPU 周期调用一次
+    fn clock(&mut self) {}
+
+    /// IRQ 输出线的电平，由 Mapper 在寄存器读写时自行应答
+    fn check_irq_interrupt(&self) -> bool {
+        false
     }
+
+    /// 扩展音频输出
+    fn audio_output(&self) -> f32 {
+        0.0
+    }
+
+    /// 由 Mapper 提供的名称表数据，返回 None 时使用 PPU 内部的 VRAM
+    fn nametable_read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// 写入 Mapper 提供的名称表，返回 false 时写入 PPU 内部的 VRAM
+    fn nametable_write(&mut self, _addr: u16, _value: u8) -> bool {
+        false
+    }
+
+    /// CPU 写入 PPU 寄存器时调用
+    fn ppu_register_write(&mut self, _addr: u16, _value: u8) {}
 }
@@ -0,0 +1,243 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nes_base::Ram;
+
+use crate::{
+    RomError,
+    mapper::{
+        Mapper, mapper0::Mapper0, mapper2::Mapper2, mapper3::Mapper3, mapper5::Mapper5,
+        mapper7::Mapper7, mapper9::Mapper9, mapper10::Mapper10, mapper11::Mapper11,
+        mapper19::Mapper19, mapper21::Mapper21, mapper21::VrcWiring, mapper24::Mapper24,
+        mapper66::Mapper66, mapper69::Mapper69,
+    },
+};
+
+/// 创建 Mapper 所需的卡带数据
+pub struct MapperContext {
+    pub mapper_id: u8,
+    pub submapper: u8,
+    /// PRG-ROM banks 的数量，每 16KB 一块
+    pub prg_banks: u8,
+    pub chr_rom: Rc<RefCell<Vec<u8>>>,
+    pub prg_rom: Rc<RefCell<Vec<u8>>>,
+    /// 映射在 [0x6000, 0x8000) 的 PRG-RAM，与卡带共享
+    pub sram: Option<Rc<RefCell<dyn Ram>>>,
+}
+
+pub type MapperConstructor = fn(MapperContext) -> Box<dyn Mapper>;
+
+/// 一个已注册的 Mapper
+#[derive(Clone, Copy)]
+pub struct MapperEntry {
+    pub mapper_id: u8,
+    /// NES 2.0 的子 Mapper 编号，None 表示适用于所有子 Mapper
+    pub submapper: Option<u8>,
+    /// 芯片或板名，用于诊断信息
+    pub name: &'static str,
+    /// 卡带上固定带有的 PRG-RAM 大小，即使没有电池也需要分配
+    pub prg_ram_size: Option<usize>,
+    pub constructor: MapperConstructor,
+}
+
+impl MapperEntry {
+    pub fn new(mapper_id: u8, name: &'static str, constructor: MapperConstructor) -> Self {
+        Self {
+            mapper_id,
+            submapper: None,
+            name,
+            prg_ram_size: None,
+            constructor,
+        }
+    }
+
+    pub fn submapper(mut self, submapper: u8) -> Self {
+        self.submapper = Some(submapper);
+        self
+    }
+
+    pub fn prg_ram_size(mut self, size: usize) -> Self {
+        self.prg_ram_size = Some(size);
+        self
+    }
+}
+
+/// 由 Mapper 编号与子 Mapper 编号查找构造函数
+/// 其他 crate 可以注册自己的 Mapper 实现，后注册的优先，因此也可以替换内置的实现
+#[derive(Clone, Default)]
+pub struct MapperRegistry {
+    entries: Vec<MapperEntry>,
+}
+
+impl MapperRegistry {
+    /// 空的注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 包含所有内置 Mapper 的注册表
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        let entries = [
+            MapperEntry::new(0, "NROM", |c| {
+                Box::new(Mapper0::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(2, "UxROM", |c| {
+                Box::new(Mapper2::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(3, "CNROM", |c| {
+                Box::new(Mapper3::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            // MMC5 最多可以寻址 64KB PRG-RAM
+            MapperEntry::new(5, "MMC5", |c| {
+                Box::new(Mapper5::new(c.chr_rom, c.prg_rom, c.sram))
+            })
+            .prg_ram_size(0x10000),
+            MapperEntry::new(7, "AxROM", |c| {
+                Box::new(Mapper7::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(9, "MMC2", |c| {
+                Box::new(Mapper9::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(10, "MMC4", |c| {
+                Box::new(Mapper10::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(11, "Color Dreams", |c| {
+                Box::new(Mapper11::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(19, "Namco 163", |c| {
+                Box::new(Mapper19::new(c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(21, "VRC4a/VRC4c", |c| {
+                Box::new(Mapper21::new(
+                    VrcWiring::MAPPER_21,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(22, "VRC2a", |c| {
+                Box::new(Mapper21::new(
+                    VrcWiring::MAPPER_22,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(23, "VRC2b/VRC4e", |c| {
+                Box::new(Mapper21::new(
+                    VrcWiring::MAPPER_23,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(24, "VRC6a", |c| {
+                Box::new(Mapper24::new(
+                    VrcWiring::MAPPER_24,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(25, "VRC4b/VRC4d", |c| {
+                Box::new(Mapper21::new(
+                    VrcWiring::MAPPER_25,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(26, "VRC6b", |c| {
+                Box::new(Mapper24::new(
+                    VrcWiring::MAPPER_26,
+                    c.chr_rom,
+                    c.prg_rom,
+                    c.sram,
+                ))
+            }),
+            MapperEntry::new(66, "GxROM", |c| {
+                Box::new(Mapper66::new(c.prg_banks, c.chr_rom, c.prg_rom, c.sram))
+            }),
+            MapperEntry::new(69, "Sunsoft FME-7", |c| {
+                Box::new(Mapper69::new(c.chr_rom, c.prg_rom, c.sram))
+            }),
+        ];
+        for entry in entries {
+            registry.register(entry);
+        }
+        registry
+    }
+
+    pub fn register(&mut self, entry: MapperEntry) {
+        self.entries.push(entry);
+    }
+
+    /// 查找 Mapper，优先匹配子 Mapper 编号完全相同的项
+    pub fn find(&self, mapper_id: u8, submapper: u8) -> Option<&MapperEntry> {
+        let mut candidates = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.mapper_id == mapper_id);
+        candidates
+            .clone()
+            .find(|entry| entry.submapper == Some(submapper))
+            .or_else(|| candidates.find(|entry| entry.submapper.is_none()))
+    }
+
+    /// 所有已注册的 Mapper，按编号排序并去掉被覆盖的项
+    pub fn entries(&self) -> Vec<&MapperEntry> {
+        let mut entries: Vec<&MapperEntry> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if !entries
+                .iter()
+                .any(|e| e.mapper_id == entry.mapper_id && e.submapper == entry.submapper)
+            {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|entry| (entry.mapper_id, entry.submapper));
+        entries
+    }
+
+    /// 找不到 Mapper 时的错误，附带板名与支持的 Mapper 列表
+    pub fn unsupported(&self, mapper_id: u8, submapper: u8, board: Option<&str>) -> RomError {
+        RomError::UnsupportedMapper {
+            mapper_id,
+            submapper,
+            board: board.or_else(|| mapper_name(mapper_id)).map(str::to_string),
+            supported: self
+                .entries()
+                .iter()
+                .map(|entry| match entry.submapper {
+                    Some(submapper) => {
+                        format!("{}.{} ({})", entry.mapper_id, submapper, entry.name)
+                    }
+                    None => format!("{} ({})", entry.mapper_id, entry.name),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 常见但尚未实现的 Mapper 的名称，仅用于诊断信息
+fn mapper_name(mapper_id: u8) -> Option<&'static str> {
+    Some(match mapper_id {
+        1 => "MMC1 (SxROM)",
+        4 => "MMC3 (TxROM)",
+        13 => "CPROM",
+        16 => "Bandai FCG",
+        18 => "Jaleco SS88006",
+        28 => "Action 53",
+        30 => "UNROM 512",
+        34 => "BNROM/NINA-001",
+        64 => "Tengen RAMBO-1",
+        71 => "Camerica/Codemasters",
+        79 => "NINA-03/NINA-06",
+        85 => "VRC7",
+        118 => "TxSROM",
+        119 => "TQROM",
+        206 => "Namco 118/DxROM",
+        _ => return None,
+    })
+}
@@ -1,5 +1,11 @@
 use nes_base::Mirroring;
 
+use crate::{
+    RomError, RomHash,
+    database::{self, GameInfo},
+    patch, unif,
+};
+
 trait BitOperations {
     fn get_bit(&self, bit: u8) -> bool;
 }
@@ -13,6 +19,13 @@ impl BitOperations for u8 {
 pub struct NESFile {
     bytes: Vec<u8>,
     header: NESHeader,
+    hash: RomHash,
+    /// 在游戏数据库中找到的记录
+    game: Option<&'static GameInfo>,
+    /// UNIF 文件的板名，iNES 文件为 None
+    board: Option<String>,
+    /// UNIF 文件中记录的游戏名
+    name: Option<String>,
 }
 
 pub struct NESHeader {
@@ -20,7 +33,7 @@ pub struct NESHeader {
     pub magic: [u8; 4],
     /// PRG-ROM banks 的数量，每 16KB 一块
     pub prg_banks: u8,
-    /// CHR-ROM banks 的数量，每 8KB 一块
+    /// CHR-ROM banks 的数量，每 8KB 一块，为 0 时卡带使用 CHR-RAM
     pub chr_banks: u8,
     /// 画面映射方式
     pub mirroring: Mirroring,
@@ -30,6 +43,8 @@ pub struct NESHeader {
     pub has_trainer: bool,
     /// Mapper ID
     pub mapper_id: u8,
+    /// NES 2.0 的子 Mapper 编号，iNES 文件为 0
+    pub submapper: u8,
 }
 
 impl From<&[u8; 16]> for NESHeader {
@@ -46,7 +61,13 @@ impl From<&[u8; 16]> for NESHeader {
         };
         let has_battery_backed = bytes[6].get_bit(1);
         let has_trainer = bytes[6].get_bit(2);
-        let mapper_id = (bytes[7] >> 4) | (bytes[6] & 0xF0);
+        let mapper_id = (bytes[7] & 0xF0) | (bytes[6] >> 4);
+        // byte 7 的 bit2-3 为 10 时是 NES 2.0 格式
+        let submapper = if bytes[7] & 0x0C == 0x08 {
+            bytes[8] >> 4
+        } else {
+            0
+        };
 
         Self {
             magic,
@@ -56,6 +77,7 @@ impl From<&[u8; 16]> for NESHeader {
             has_battery_backed,
             has_trainer,
             mapper_id,
+            submapper,
         }
     }
 }
@@ -63,33 +85,140 @@ impl From<&[u8; 16]> for NESHeader {
 const PRG_BANK_SIZE: usize = 0x4000; // 16KB
 const CHR_BANK_SIZE: usize = 0x2000; // 8KB
 const TRAINER_SIZE: usize = 0x0200; // 512 bytes
+const UNIF_MAGIC: &[u8] = b"UNIF";
 
 impl NESFile {
     /// 从文件路径加载 NES 文件
-    pub fn from_file(path: &str) -> Self {
-        let bytes = std::fs::read(path)
-            .unwrap_or_else(|_| panic!("Failed to read NES file from path: {}", path));
+    pub fn from_file(path: &str) -> Result<Self, RomError> {
+        let bytes = std::fs::read(path).map_err(|source| RomError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Self::new(bytes)
+    }
+
+    /// 依次应用补丁后再解析，补丁作用于包含文件头在内的原始数据
+    pub fn with_patches<P: AsRef<[u8]>>(bytes: Vec<u8>, patches: &[P]) -> Result<Self, RomError> {
+        let bytes = patches.iter().try_fold(bytes, |bytes, patch| {
+            patch::apply_patch(&bytes, patch.as_ref())
+        })?;
         Self::new(bytes)
     }
 
-    pub fn new(bytes: Vec<u8>) -> Self {
-        let header = NESHeader::from(&bytes[0..16].try_into().expect("Invalid NES header length"));
+    /// 解析 iNES 或 UNIF 格式的 ROM 数据
+    pub fn new(bytes: Vec<u8>) -> Result<Self, RomError> {
+        if bytes.starts_with(UNIF_MAGIC) {
+            return Self::from_unif(&bytes);
+        }
+        if bytes.len() < 16 {
+            return Err(RomError::Truncated {
+                expected: 16,
+                actual: bytes.len(),
+            });
+        }
+        let header = NESHeader::from(&bytes[0..16].try_into().unwrap());
         if header.magic != [0x4E, 0x45, 0x53, 0x1A] {
-            panic!("Invalid NES file magic number");
+            return Err(RomError::InvalidMagic(header.magic));
         }
         if header.prg_banks == 0 {
-            panic!("NES file must have at least one PRG-ROM bank");
+            return Err(RomError::NoPrgRom);
         }
-        if header.chr_banks == 0 {
-            panic!("NES file must have at least one CHR-ROM bank");
+
+        let mut nes = Self {
+            bytes,
+            header,
+            hash: RomHash::new(&[]),
+            game: None,
+            board: None,
+            name: None,
+        };
+        // 检查文件长度，防止截断的文件在切片时越界
+        let expected = nes.chr_rom_start() + nes.header.chr_banks as usize * CHR_BANK_SIZE;
+        if nes.bytes.len() < expected {
+            return Err(RomError::Truncated {
+                expected,
+                actual: nes.bytes.len(),
+            });
         }
-        Self { bytes, header }
+
+        // 很多流传的 ROM 文件头是错的，以数据库中的记录为准
+        nes.hash = RomHash::new(&nes.bytes[nes.prg_rom_start()..expected]);
+        nes.game = database::lookup(&nes.hash);
+        if let Some(game) = nes.game {
+            nes.header.mapper_id = game.mapper_id;
+            nes.header.mirroring = game.mirroring;
+            nes.header.has_battery_backed = game.has_battery_backed;
+        }
+        Ok(nes)
     }
 
+    /// 文件头，如果在游戏数据库中找到了记录，则已按记录修正
     pub fn header(&self) -> &NESHeader {
         &self.header
     }
 
+    /// 将 UNIF 文件转换为等价的 iNES 数据后解析，板名按对应表换算为 Mapper 编号
+    fn from_unif(bytes: &[u8]) -> Result<Self, RomError> {
+        let image = unif::parse(bytes)?;
+        let unsupported = || RomError::UnsupportedBoard(image.board.clone());
+
+        // iNES 以 16KB/8KB 为单位，不足时重复 PRG-ROM、以 0 填充 CHR-ROM
+        let prg_banks = image.prg_rom.len().div_ceil(PRG_BANK_SIZE);
+        let chr_banks = image.chr_rom.len().div_ceil(CHR_BANK_SIZE);
+        let mut flags6 = (image.mapper_id << 4) | ((image.has_battery_backed as u8) << 1);
+        match image.mirroring {
+            Mirroring::Vertical => flags6 |= 0x01,
+            Mirroring::FourScreen => flags6 |= 0x08,
+            _ => {}
+        }
+        let mut ines = vec![
+            0x4E,
+            0x45,
+            0x53,
+            0x1A,
+            u8::try_from(prg_banks).map_err(|_| unsupported())?,
+            u8::try_from(chr_banks).map_err(|_| unsupported())?,
+            flags6,
+            image.mapper_id & 0xF0,
+        ];
+        ines.resize(16, 0);
+        ines.extend(image.prg_rom.iter().cycle().take(prg_banks * PRG_BANK_SIZE));
+        ines.extend_from_slice(&image.chr_rom);
+        ines.resize(
+            16 + (prg_banks * PRG_BANK_SIZE) + chr_banks * CHR_BANK_SIZE,
+            0,
+        );
+
+        let mut nes = Self::new(ines)?;
+        // iNES 文件头无法表示单屏镜像，这里直接设置
+        if nes.game.is_none() {
+            nes.header.mirroring = image.mirroring;
+        }
+        nes.board = Some(image.board);
+        nes.name = image.name;
+        Ok(nes)
+    }
+
+    /// UNIF 文件的板名，例如 "NES-UNROM"
+    pub fn board(&self) -> Option<&str> {
+        self.board.as_deref()
+    }
+
+    /// UNIF 文件中记录的游戏名
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// PRG-ROM 与 CHR-ROM 的校验值
+    pub fn hash(&self) -> &RomHash {
+        &self.hash
+    }
+
+    /// 游戏数据库中的记录，未收录时为 None
+    pub fn game(&self) -> Option<&'static GameInfo> {
+        self.game
+    }
+
     /// Get PRG-ROM data
     pub fn prg_rom(&self) -> Vec<u8> {
         let start = self.prg_rom_start();
@@ -97,21 +226,21 @@ impl NESFile {
         self.bytes[start..end].to_vec()
     }
 
-    /// Get CHR-ROM data
+    /// Get CHR-ROM data, empty if the cartridge uses CHR-RAM
     pub fn chr_rom(&self) -> Vec<u8> {
         let start = self.chr_rom_start();
         let end = start + (self.header.chr_banks as usize * CHR_BANK_SIZE);
         self.bytes[start..end].to_vec()
     }
 
-    /// Get trainer ROM data
-    pub fn trainer_rom(&self) -> Vec<u8> {
+    /// Get trainer ROM data, None if the file has no trainer
+    pub fn trainer_rom(&self) -> Option<Vec<u8>> {
         if !self.header.has_trainer {
-            panic!("Nes file is not support to get trainer rom");
+            return None;
         }
         let start = 0x10;
         let end = start + TRAINER_SIZE;
-        self.bytes[start..end].to_vec()
+        Some(self.bytes[start..end].to_vec())
     }
 
     fn prg_rom_start(&self) -> usize {
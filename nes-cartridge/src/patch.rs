@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+
+use crate::{PatchError, hash::crc32};
+
+/// 补丁格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Ups,
+}
+
+impl PatchFormat {
+    /// 根据文件头识别补丁格式
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(b"PATCH") {
+            Some(PatchFormat::Ips)
+        } else if patch.starts_with(b"BPS1") {
+            Some(PatchFormat::Bps)
+        } else if patch.starts_with(b"UPS1") {
+            Some(PatchFormat::Ups)
+        } else {
+            None
+        }
+    }
+}
+
+/// BPS/UPS 目标文件大小的上限，远大于任何 NES 游戏，防止恶意补丁申请过多内存
+const MAX_TARGET_SIZE: usize = 0x1000000; // 16MB
+
+/// 软补丁的扩展名，按优先顺序排列
+const PATCH_EXTENSIONS: [&str; 3] = ["bps", "ups", "ips"];
+
+/// 查找 ROM 同目录下的同名补丁文件，例如 Game.nes 对应 Game.ips
+pub fn soft_patch_path(rom_path: &Path) -> Option<PathBuf> {
+    PATCH_EXTENSIONS
+        .iter()
+        .map(|extension| rom_path.with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+/// 对 ROM 文件的原始数据应用补丁，格式由补丁的文件头决定
+pub fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match PatchFormat::detect(patch) {
+        Some(PatchFormat::Ips) => apply_ips(rom, patch),
+        Some(PatchFormat::Bps) => apply_bps(rom, patch),
+        Some(PatchFormat::Ups) => apply_ups(rom, patch),
+        None => Err(PatchError::UnknownFormat),
+    }
+}
+
+/// 按顺序读取补丁数据
+struct PatchReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Self {
+        Self { data, position }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(PatchError::UnexpectedEnd)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, PatchError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// 大端序整数，IPS 使用
+    fn read_be(&mut self, len: usize) -> Result<usize, PatchError> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0, |value, &byte| (value << 8) | byte as usize))
+    }
+
+    /// BPS/UPS 使用的变长整数，每个字节的低 7 位为数据，最高位为结束标志
+    fn read_varint(&mut self) -> Result<usize, PatchError> {
+        let mut value = 0usize;
+        let mut shift = 1usize;
+        loop {
+            let byte = self.read_u8()?;
+            value = (byte as usize & 0x7F)
+                .checked_mul(shift)
+                .and_then(|data| value.checked_add(data))
+                .ok_or(PatchError::Malformed("variable-length integer overflow"))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift
+                .checked_mul(0x80)
+                .ok_or(PatchError::Malformed("variable-length integer overflow"))?;
+            value = value
+                .checked_add(shift)
+                .ok_or(PatchError::Malformed("variable-length integer overflow"))?;
+        }
+    }
+}
+
+/// BPS/UPS 末尾的三个 CRC32：源文件、目标文件、补丁本身（不含最后 4 字节）
+struct Footer {
+    source: u32,
+    target: u32,
+}
+
+fn read_footer(patch: &[u8]) -> Result<Footer, PatchError> {
+    if patch.len() < 16 {
+        return Err(PatchError::UnexpectedEnd);
+    }
+    let footer = &patch[patch.len() - 12..];
+    let read = |i: usize| u32::from_le_bytes(footer[i..i + 4].try_into().unwrap());
+    let expected = read(8);
+    let actual = crc32(&patch[..patch.len() - 4]);
+    if expected != actual {
+        return Err(PatchError::ChecksumMismatch {
+            which: "patch",
+            expected,
+            actual,
+        });
+    }
+    Ok(Footer {
+        source: read(0),
+        target: read(4),
+    })
+}
+
+fn check_target_size(target_size: usize) -> Result<(), PatchError> {
+    if target_size > MAX_TARGET_SIZE {
+        return Err(PatchError::Malformed("target size too large"));
+    }
+    Ok(())
+}
+
+/// 从 offset 开始长度为 length 的区间，长度溢出时返回 None
+fn span(offset: usize, length: usize) -> Option<std::ops::Range<usize>> {
+    Some(offset..offset.checked_add(length)?)
+}
+
+fn check_crc(which: &'static str, expected: u32, data: &[u8]) -> Result<(), PatchError> {
+    let actual = crc32(data);
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(PatchError::ChecksumMismatch {
+            which,
+            expected,
+            actual,
+        })
+    }
+}
+
+/// IPS：若干条 (偏移, 数据) 记录，以 "EOF" 结束，之后可以跟一个 3 字节的截断长度
+fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut output = rom.to_vec();
+    let mut reader = PatchReader::new(patch, 5);
+    loop {
+        let offset_bytes = reader.take(3)?;
+        if offset_bytes == b"EOF" {
+            break;
+        }
+        let offset = offset_bytes
+            .iter()
+            .fold(0, |value, &byte| (value << 8) | byte as usize);
+        let size = reader.read_be(2)?;
+        // 长度为 0 时为 RLE 记录
+        let (size, data) = if size == 0 {
+            let count = reader.read_be(2)?;
+            (count, vec![reader.read_u8()?; count])
+        } else {
+            (size, reader.take(size)?.to_vec())
+        };
+        if output.len() < offset + size {
+            output.resize(offset + size, 0);
+        }
+        output[offset..offset + size].copy_from_slice(&data);
+    }
+    if let Ok(truncate) = reader.read_be(3) {
+        output.truncate(truncate);
+    }
+    Ok(output)
+}
+
+/// BPS：基于源文件与已生成目标数据的复制指令，带有 CRC32 校验
+fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let footer = read_footer(patch)?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - 12], 4);
+    let source_size = reader.read_varint()?;
+    let target_size = reader.read_varint()?;
+    let metadata_size = reader.read_varint()?;
+    reader.take(metadata_size)?;
+    if source_size != rom.len() {
+        return Err(PatchError::SourceSizeMismatch {
+            expected: source_size,
+            actual: rom.len(),
+        });
+    }
+    check_crc("source", footer.source, rom)?;
+    check_target_size(target_size)?;
+
+    let mut output = Vec::with_capacity(target_size);
+    let mut source_offset = 0usize;
+    let mut target_offset = 0usize;
+    let relative = |offset: usize, data: usize| {
+        let delta = data >> 1;
+        if data & 1 == 0 {
+            offset.checked_add(delta)
+        } else {
+            offset.checked_sub(delta)
+        }
+        .ok_or(PatchError::Malformed("copy offset out of range"))
+    };
+    while reader.position < reader.data.len() {
+        let data = reader.read_varint()?;
+        let length = (data >> 2) + 1;
+        if length > target_size - output.len() {
+            return Err(PatchError::Malformed("target size exceeded"));
+        }
+        match data & 0x03 {
+            // SourceRead：复制源文件同一位置的数据
+            0 => {
+                let bytes = span(output.len(), length)
+                    .and_then(|range| rom.get(range))
+                    .ok_or(PatchError::Malformed("source read out of range"))?;
+                output.extend_from_slice(bytes);
+            }
+            // TargetRead：数据直接存放在补丁中
+            1 => output.extend_from_slice(reader.take(length)?),
+            // SourceCopy：从源文件的任意位置复制
+            2 => {
+                source_offset = relative(source_offset, reader.read_varint()?)?;
+                let bytes = span(source_offset, length)
+                    .and_then(|range| rom.get(range))
+                    .ok_or(PatchError::Malformed("source copy out of range"))?;
+                output.extend_from_slice(bytes);
+                source_offset += length;
+            }
+            // TargetCopy：从已生成的目标数据复制，区域可以重叠，需要逐字节复制
+            _ => {
+                target_offset = relative(target_offset, reader.read_varint()?)?;
+                for _ in 0..length {
+                    let byte = *output
+                        .get(target_offset)
+                        .ok_or(PatchError::Malformed("target copy out of range"))?;
+                    output.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+    if output.len() != target_size {
+        return Err(PatchError::Malformed("target size mismatch"));
+    }
+    check_crc("target", footer.target, &output)?;
+    Ok(output)
+}
+
+/// UPS：跳过若干字节后与源文件做异或，每段以 0 结束
+fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let footer = read_footer(patch)?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - 12], 4);
+    let source_size = reader.read_varint()?;
+    let target_size = reader.read_varint()?;
+    if source_size != rom.len() {
+        return Err(PatchError::SourceSizeMismatch {
+            expected: source_size,
+            actual: rom.len(),
+        });
+    }
+    check_crc("source", footer.source, rom)?;
+    check_target_size(target_size)?;
+
+    let mut output = rom.to_vec();
+    output.resize(target_size, 0);
+    let mut offset = 0usize;
+    while reader.position < reader.data.len() {
+        offset = offset
+            .checked_add(reader.read_varint()?)
+            .ok_or(PatchError::Malformed("offset overflow"))?;
+        loop {
+            let byte = reader.read_u8()?;
+            if offset < output.len() {
+                output[offset] ^= byte;
+            }
+            offset = offset
+                .checked_add(1)
+                .ok_or(PatchError::Malformed("offset overflow"))?;
+            if byte == 0 {
+                break;
+            }
+        }
+    }
+    check_crc("target", footer.target, &output)?;
+    Ok(output)
+}
@@ -0,0 +1,93 @@
+use std::{
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use nes_base::{Reader, Writer};
+
+use crate::CartridgeImpl;
+
+/// 获取 ROM 文件对应的存档路径，即同目录下的同名 .sav 文件
+pub fn sav_path(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("sav")
+}
+
+impl CartridgeImpl {
+    /// 卡带是否带有电池供电的 PRG-NVRAM
+    pub fn has_battery_backed(&self) -> bool {
+        self.battery_backed
+    }
+
+    /// 自上次导入或写入存档后 PRG-NVRAM 是否被修改过
+    /// 只统计 Mapper 真正写入 PRG-RAM 的操作，被写保护拦下的写入不算
+    pub fn is_sram_dirty(&self) -> bool {
+        self.battery_backed
+            && self
+                .sram
+                .as_ref()
+                .is_some_and(|sram| sram.borrow().is_dirty())
+    }
+
+    /// 导出 PRG-NVRAM 的内容，没有电池时返回 None
+    pub fn export_sram(&self) -> Option<Vec<u8>> {
+        if !self.battery_backed {
+            return None;
+        }
+        let sram = self.sram.as_ref()?;
+        Some(
+            (0..self.sram_size)
+                .map(|addr| sram.borrow().read(addr as u16))
+                .collect(),
+        )
+    }
+
+    /// 导入 PRG-NVRAM 的内容，超出 PRG-RAM 大小的部分会被忽略，没有电池时不做任何事
+    pub fn import_sram(&mut self, data: &[u8]) {
+        if !self.battery_backed {
+            return;
+        }
+        if let Some(sram) = &self.sram {
+            for (addr, &value) in data.iter().take(self.sram_size).enumerate() {
+                sram.borrow_mut().write(addr as u16, value);
+            }
+            sram.borrow_mut().clear_dirty();
+        }
+    }
+
+    /// 从存档文件加载 PRG-NVRAM，文件不存在时返回 false
+    pub fn load_sav_file(&mut self, path: &Path) -> io::Result<bool> {
+        if !self.has_battery_backed() {
+            return Ok(false);
+        }
+        match fs::read(path) {
+            Ok(data) => {
+                self.import_sram(&data);
+                Ok(true)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 将 PRG-NVRAM 写入存档文件，没有电池时不做任何事
+    ///
+    /// 先写入同目录下的临时文件再重命名，写入中途崩溃也不会破坏原有的存档
+    pub fn write_sav_file(&mut self, path: &Path) -> io::Result<()> {
+        let Some(data) = self.export_sram() else {
+            return Ok(());
+        };
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)?;
+        if let Some(sram) = &self.sram {
+            sram.borrow_mut().clear_dirty();
+        }
+        Ok(())
+    }
+}
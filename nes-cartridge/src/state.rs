@@ -0,0 +1,362 @@
+use std::fmt;
+
+use nes_base::{Cartridge, Mirroring, Reader, Writer};
+
+use crate::{CartridgeImpl, StateError};
+
+/// bank 所在的总线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankBus {
+    Cpu,
+    Ppu,
+}
+
+/// bank 映射到的存储器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankMemory {
+    PrgRom,
+    PrgRam,
+    ChrRom,
+    ChrRam,
+    /// PPU 内部的 2KB 名称表 VRAM
+    Vram,
+    /// Mapper 芯片内部的 RAM，例如 MMC5 的 ExRAM
+    MapperRam,
+    /// 不对应任何存储器，例如 MMC5 的填充模式
+    Fill,
+}
+
+impl fmt::Display for BankMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BankMemory::PrgRom => "PRG-ROM",
+            BankMemory::PrgRam => "PRG-RAM",
+            BankMemory::ChrRom => "CHR-ROM",
+            BankMemory::ChrRam => "CHR-RAM",
+            BankMemory::Vram => "VRAM",
+            BankMemory::MapperRam => "Mapper RAM",
+            BankMemory::Fill => "Fill",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 一段地址当前映射到的 bank
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankMapping {
+    pub bus: BankBus,
+    pub start: u16,
+    /// 映射窗口的大小，以字节为单位
+    pub size: usize,
+    pub memory: BankMemory,
+    /// 以窗口大小为单位的 bank 编号
+    pub bank: usize,
+}
+
+/// Mapper 当前的 bank 映射以及 IRQ 计数器等内部寄存器，供调试器显示
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BankMap {
+    pub mappings: Vec<BankMapping>,
+    pub registers: Vec<(&'static str, u32)>,
+}
+
+impl BankMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cpu(mut self, start: u16, size: usize, memory: BankMemory, bank: usize) -> Self {
+        self.mappings.push(BankMapping {
+            bus: BankBus::Cpu,
+            start,
+            size,
+            memory,
+            bank,
+        });
+        self
+    }
+
+    pub fn ppu(mut self, start: u16, size: usize, memory: BankMemory, bank: usize) -> Self {
+        self.mappings.push(BankMapping {
+            bus: BankBus::Ppu,
+            start,
+            size,
+            memory,
+            bank,
+        });
+        self
+    }
+
+    /// 按镜像方式添加四个名称表的映射
+    pub fn nametables(self, mirroring: Mirroring) -> Self {
+        let pages = match mirroring {
+            Mirroring::Horizontal => [0, 0, 1, 1],
+            Mirroring::Vertical => [0, 1, 0, 1],
+            Mirroring::SingleScreenLower => [0; 4],
+            Mirroring::SingleScreenUpper => [1; 4],
+            Mirroring::FourScreen => [0, 1, 2, 3],
+            Mirroring::Mapped(pages) => pages,
+        };
+        pages.iter().enumerate().fold(self, |map, (i, &page)| {
+            map.ppu(
+                0x2000 + i as u16 * 0x400,
+                0x400,
+                BankMemory::Vram,
+                page as usize,
+            )
+        })
+    }
+
+    /// [0x6000, 0x7FFF] 的 8KB PRG-RAM，不存在时不添加
+    pub fn prg_ram(self, available: bool) -> Self {
+        if available {
+            self.cpu(0x6000, 0x2000, BankMemory::PrgRam, 0)
+        } else {
+            self
+        }
+    }
+
+    pub fn register(mut self, name: &'static str, value: u32) -> Self {
+        self.registers.push((name, value));
+        self
+    }
+
+    /// 查找地址所在的映射
+    pub fn find(&self, bus: BankBus, addr: u16) -> Option<&BankMapping> {
+        self.mappings.iter().find(|mapping| {
+            mapping.bus == bus
+                && addr >= mapping.start
+                && (addr as usize) < mapping.start as usize + mapping.size
+        })
+    }
+}
+
+impl fmt::Display for BankMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for mapping in &self.mappings {
+            let bus = match mapping.bus {
+                BankBus::Cpu => "CPU",
+                BankBus::Ppu => "PPU",
+            };
+            let end = mapping.start as usize + mapping.size - 1;
+            let size = if mapping.size >= 0x400 {
+                format!("{}KB", mapping.size / 0x400)
+            } else {
+                format!("{}B", mapping.size)
+            };
+            writeln!(
+                f,
+                "{} ${:04X}-${:04X}: {} #{} ({})",
+                bus, mapping.start, end, mapping.memory, mapping.bank, size
+            )?;
+        }
+        for (name, value) in &self.registers {
+            writeln!(f, "{} = ${:X}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// 将 Mapper 内部状态按顺序写入字节流
+#[derive(Default)]
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    /// 写入带长度前缀的字节数组
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn write_mirroring(&mut self, mirroring: Mirroring) {
+        match mirroring {
+            Mirroring::Horizontal => self.write_u8(0),
+            Mirroring::Vertical => self.write_u8(1),
+            Mirroring::SingleScreenLower => self.write_u8(2),
+            Mirroring::SingleScreenUpper => self.write_u8(3),
+            Mirroring::FourScreen => self.write_u8(4),
+            Mirroring::Mapped(pages) => {
+                self.write_u8(5);
+                self.data.extend_from_slice(&pages);
+            }
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// 按写入顺序从字节流中读取 Mapper 内部状态
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        let end = self.position + len;
+        if end > self.data.len() {
+            return Err(StateError::UnexpectedEnd);
+        }
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, StateError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, StateError> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.read_u32()? as usize;
+        self.take(len)
+    }
+
+    /// 读取字节数组到固定大小的缓冲区，长度必须一致
+    pub fn read_bytes_into(
+        &mut self,
+        buffer: &mut [u8],
+        name: &'static str,
+    ) -> Result<(), StateError> {
+        let bytes = self.read_bytes()?;
+        if bytes.len() != buffer.len() {
+            return Err(StateError::InvalidValue(name));
+        }
+        buffer.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn read_mirroring(&mut self) -> Result<Mirroring, StateError> {
+        Ok(match self.read_u8()? {
+            0 => Mirroring::Horizontal,
+            1 => Mirroring::Vertical,
+            2 => Mirroring::SingleScreenLower,
+            3 => Mirroring::SingleScreenUpper,
+            4 => Mirroring::FourScreen,
+            5 => {
+                let pages = self.take(4)?;
+                Mirroring::Mapped([pages[0], pages[1], pages[2], pages[3]])
+            }
+            _ => return Err(StateError::InvalidValue("mirroring")),
+        })
+    }
+
+    /// 确认所有数据都已读取
+    pub fn finish(self) -> Result<(), StateError> {
+        let remaining = self.data.len() - self.position;
+        if remaining == 0 {
+            Ok(())
+        } else {
+            Err(StateError::TrailingData(remaining))
+        }
+    }
+}
+
+impl CartridgeImpl {
+    /// 当前的 bank 映射，Mapper 没有给出名称表映射时按镜像方式补充
+    pub fn bank_map(&self) -> BankMap {
+        let map = self.mapper.bank_map();
+        let has_nametables = map
+            .mappings
+            .iter()
+            .any(|mapping| mapping.bus == BankBus::Ppu && mapping.start >= 0x2000);
+        if has_nametables {
+            map
+        } else {
+            map.nametables(self.mirroring())
+        }
+    }
+
+    /// 保存卡带状态，包括 Mapper 的内部寄存器与 PRG-RAM
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        state.write_u8(self.mapper_id);
+        self.mapper.save_state(&mut state);
+        if let Some(sram) = &self.sram {
+            let data: Vec<u8> = (0..self.sram_size)
+                .map(|addr| sram.borrow().read(addr as u16))
+                .collect();
+            state.write_bytes(&data);
+        }
+        state.into_bytes()
+    }
+
+    /// 恢复 save_state 保存的卡带状态，出错时卡带保持加载前的状态
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        // Mapper 的寄存器在读到 PRG-RAM 之前就已经写入，出错时用备份整体恢复
+        let backup = self.save_state();
+        let dirty = self.is_sram_dirty();
+        let result = self.load_state_from(data);
+        if result.is_err() {
+            self.load_state_from(&backup)
+                .expect("failed to restore cartridge state");
+            if let (false, Some(sram)) = (dirty, &self.sram) {
+                sram.borrow_mut().clear_dirty();
+            }
+        }
+        result
+    }
+
+    fn load_state_from(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut state = StateReader::new(data);
+        let mapper_id = state.read_u8()?;
+        if mapper_id != self.mapper_id {
+            return Err(StateError::MapperMismatch {
+                expected: self.mapper_id,
+                actual: mapper_id,
+            });
+        }
+        self.mapper.load_state(&mut state)?;
+        if let Some(sram) = &self.sram {
+            let data = state.read_bytes()?;
+            if data.len() != self.sram_size {
+                return Err(StateError::InvalidValue("PRG-RAM size"));
+            }
+            for (addr, &value) in data.iter().enumerate() {
+                sram.borrow_mut().write(addr as u16, value);
+            }
+        }
+        state.finish()
+    }
+}
@@ -0,0 +1,174 @@
+use nes_base::Mirroring;
+
+use crate::RomError;
+
+const HEADER_SIZE: usize = 32;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+/// UNIF 板名到 iNES Mapper 编号的对应关系，板名不含 NES-/HVC- 等前缀
+const BOARDS: &[(&str, u8)] = &[
+    ("NROM", 0),
+    ("NROM-128", 0),
+    ("NROM-256", 0),
+    ("RROM", 0),
+    ("RROM-128", 0),
+    ("SAROM", 1),
+    ("SBROM", 1),
+    ("SCROM", 1),
+    ("SEROM", 1),
+    ("SGROM", 1),
+    ("SKROM", 1),
+    ("SLROM", 1),
+    ("SL1ROM", 1),
+    ("SNROM", 1),
+    ("SOROM", 1),
+    ("SUROM", 1),
+    ("UNROM", 2),
+    ("UOROM", 2),
+    ("CNROM", 3),
+    ("TBROM", 4),
+    ("TEROM", 4),
+    ("TFROM", 4),
+    ("TGROM", 4),
+    ("TKROM", 4),
+    ("TLROM", 4),
+    ("TR1ROM", 4),
+    ("TSROM", 4),
+    ("TVROM", 4),
+    ("HKROM", 4),
+    ("EKROM", 5),
+    ("ELROM", 5),
+    ("ETROM", 5),
+    ("EWROM", 5),
+    ("AMROM", 7),
+    ("ANROM", 7),
+    ("AN1ROM", 7),
+    ("AOROM", 7),
+    ("PNROM", 9),
+    ("PEEOROM", 9),
+    ("FJROM", 10),
+    ("FKROM", 10),
+    ("NAMCOT-163", 19),
+    ("GNROM", 66),
+    ("MHROM", 66),
+];
+
+/// 板名前缀，表示厂商或授权情况，与 Mapper 无关
+const BOARD_PREFIXES: [&str; 5] = ["NES-", "HVC-", "UNL-", "BMC-", "BTL-"];
+
+/// 根据 UNIF 板名查找对应的 iNES Mapper 编号
+pub fn board_mapper_id(board: &str) -> Option<u8> {
+    let name = BOARD_PREFIXES
+        .iter()
+        .find_map(|prefix| board.strip_prefix(prefix))
+        .unwrap_or(board);
+    BOARDS
+        .iter()
+        .find(|(board, _)| board.eq_ignore_ascii_case(name))
+        .map(|&(_, mapper_id)| mapper_id)
+}
+
+/// 从 UNIF 文件中解析出的卡带数据
+pub(crate) struct UnifImage {
+    pub board: String,
+    pub name: Option<String>,
+    pub mapper_id: u8,
+    pub prg_rom: Vec<u8>,
+    pub chr_rom: Vec<u8>,
+    pub mirroring: Mirroring,
+    pub has_battery_backed: bool,
+}
+
+/// 读取以 0 结尾的字符串
+fn read_string(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).trim().to_string()
+}
+
+/// 解析 UNIF 文件：32 字节文件头之后是若干 (ID, 长度, 数据) 块
+/// PRG0-PRGF 与 CHR0-CHRF 按编号顺序拼接
+pub(crate) fn parse(bytes: &[u8]) -> Result<UnifImage, RomError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(RomError::Truncated {
+            expected: HEADER_SIZE,
+            actual: bytes.len(),
+        });
+    }
+
+    let mut board = None;
+    let mut name = None;
+    let mut prg_chunks: [Option<&[u8]>; 16] = [None; 16];
+    let mut chr_chunks: [Option<&[u8]>; 16] = [None; 16];
+    let mut mirroring = Mirroring::Horizontal;
+    let mut has_battery_backed = false;
+
+    let mut position = HEADER_SIZE;
+    while position < bytes.len() {
+        let expected = position + CHUNK_HEADER_SIZE;
+        if expected > bytes.len() {
+            return Err(RomError::Truncated {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        let id = &bytes[position..position + 4];
+        let len = u32::from_le_bytes(bytes[position + 4..expected].try_into().unwrap()) as usize;
+        let data = bytes
+            .get(expected..expected + len)
+            .ok_or(RomError::Truncated {
+                expected: expected + len,
+                actual: bytes.len(),
+            })?;
+        position = expected + len;
+
+        let index = (id[3] as char).to_digit(16).unwrap_or(0) as usize;
+        match &id[..3] {
+            b"PRG" => prg_chunks[index] = Some(data),
+            b"CHR" => chr_chunks[index] = Some(data),
+            _ => match id {
+                b"MAPR" => board = Some(read_string(data)),
+                b"NAME" => name = Some(read_string(data)),
+                b"BATR" => has_battery_backed = data.first().is_none_or(|&b| b != 0),
+                b"MIRR" => {
+                    mirroring = match data.first() {
+                        Some(1) => Mirroring::Vertical,
+                        Some(2) => Mirroring::SingleScreenLower,
+                        Some(3) => Mirroring::SingleScreenUpper,
+                        Some(4) => Mirroring::FourScreen,
+                        // 0 为水平镜像，5 为由 Mapper 控制
+                        _ => Mirroring::Horizontal,
+                    }
+                }
+                // 其余块（作者、校验值、手柄类型等）不影响运行
+                _ => {}
+            },
+        }
+    }
+
+    let board = board.ok_or(RomError::MissingChunk("MAPR"))?;
+    let mapper_id =
+        board_mapper_id(&board).ok_or_else(|| RomError::UnsupportedBoard(board.clone()))?;
+    let prg_rom: Vec<u8> = prg_chunks
+        .iter()
+        .flatten()
+        .flat_map(|c| c.iter().copied())
+        .collect();
+    let chr_rom: Vec<u8> = chr_chunks
+        .iter()
+        .flatten()
+        .flat_map(|c| c.iter().copied())
+        .collect();
+    if prg_rom.is_empty() {
+        return Err(RomError::NoPrgRom);
+    }
+
+    Ok(UnifImage {
+        board,
+        name,
+        mapper_id,
+        prg_rom,
+        chr_rom,
+        mirroring,
+        has_battery_backed,
+    })
+}
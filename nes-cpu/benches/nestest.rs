@@ -0,0 +1,59 @@
+use std::{cell::RefCell, hint::black_box, rc::Rc};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nes_base::{BusAdapter, Cpu, Reader, Writer};
+use nes_cpu::CpuImpl;
+
+/// nestest 自动模式从 $C000 运行到结束时的周期数，与 nestest.txt 最后一行一致
+const NESTEST_CYCLES: u32 = 26_554;
+
+/// 64K 平坦内存，nestest 自动模式不依赖 PPU/APU
+struct FlatBus(Vec<u8>);
+
+impl Reader for FlatBus {
+    fn read(&self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+}
+
+impl Writer for FlatBus {
+    fn write(&mut self, addr: u16, data: u8) {
+        self.0[addr as usize] = data;
+    }
+}
+
+impl BusAdapter for FlatBus {
+    fn address_accept(&self, _addr: u16) -> bool {
+        true
+    }
+}
+
+/// 把 nestest.nes 的 16K PRG 映射到 $8000 和 $C000
+fn load_nestest() -> Vec<u8> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../nes-test/testfiles/nestest.nes");
+    let rom = std::fs::read(path).unwrap();
+    let prg = &rom[16..16 + 0x4000];
+    let mut memory = vec![0; 0x10000];
+    memory[0x8000..0xC000].copy_from_slice(prg);
+    memory[0xC000..].copy_from_slice(prg);
+    memory
+}
+
+fn run_nestest(memory: &[u8]) -> u32 {
+    let mut cpu = CpuImpl::new();
+    cpu.attach_bus(Rc::new(RefCell::new(FlatBus(memory.to_vec()))));
+    cpu.reset();
+    cpu.set_reg_pc(0xC000);
+    while cpu.dump_state().total_cycles < NESTEST_CYCLES {
+        cpu.clock();
+    }
+    cpu.dump_state().reg_a as u32
+}
+
+fn bench_nestest(c: &mut Criterion) {
+    let memory = load_nestest();
+    c.bench_function("nestest", |b| b.iter(|| run_nestest(black_box(&memory))));
+}
+
+criterion_group!(benches, bench_nestest);
+criterion_main!(benches);
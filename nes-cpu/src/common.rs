@@ -1,3 +1,4 @@
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum InstructionEnum {
     ADC,
@@ -70,6 +71,72 @@ pub enum InstructionEnum {
     SRE,
     SKB,
     IGN,
+    XAA,
+    LXA,
+    AHX,
+    SHX,
+    SHY,
+    TAS,
+    LAS,
+    // 65C02 新增指令
+    BRA,
+    STZ,
+    PHX,
+    PHY,
+    PLX,
+    PLY,
+    TRB,
+    TSB,
+    RMB0,
+    RMB1,
+    RMB2,
+    RMB3,
+    RMB4,
+    RMB5,
+    RMB6,
+    RMB7,
+    SMB0,
+    SMB1,
+    SMB2,
+    SMB3,
+    SMB4,
+    SMB5,
+    SMB6,
+    SMB7,
+    BBR0,
+    BBR1,
+    BBR2,
+    BBR3,
+    BBR4,
+    BBR5,
+    BBR6,
+    BBR7,
+    BBS0,
+    BBS1,
+    BBS2,
+    BBS3,
+    BBS4,
+    BBS5,
+    BBS6,
+    BBS7,
+    JAM,
+}
+
+impl InstructionEnum {
+    pub fn is_branch(self) -> bool {
+        use InstructionEnum::*;
+        matches!(self, BCC | BCS | BEQ | BMI | BNE | BPL | BVC | BVS | BRA)
+    }
+
+    /// 65C02 的 RMBn/SMBn，清除或置位零页某一位 (枚举中 RMB0..=SMB7 连续排列)
+    pub fn is_bit_modify(self) -> bool {
+        (InstructionEnum::RMB0 as u8..=InstructionEnum::SMB7 as u8).contains(&(self as u8))
+    }
+
+    /// 65C02 的 BBRn/BBSn，测试零页某一位后分支 (枚举中 BBR0..=BBS7 连续排列)
+    pub fn is_bit_branch(self) -> bool {
+        (InstructionEnum::BBR0 as u8..=InstructionEnum::BBS7 as u8).contains(&(self as u8))
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -87,6 +154,25 @@ pub enum AddressingMode {
     Relative,
     Implied,
     Indirect,
+    /// 65C02 零页间接寻址 ($zp)
+    ZeroPageIndirect,
+    /// 65C02 JMP ($abs,X)
+    AbsoluteIndexedIndirect,
+    /// 65C02 BBRn/BBSn 的 $zp,rel
+    ZeroPageRelative,
+}
+
+/// CPU 型号，构造时选定，决定译码表与运算函数表
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CpuVariant {
+    /// NES 的 2A03，忽略 D 标志，支持非官方指令
+    #[default]
+    Ricoh2A03,
+    /// NMOS 6502 (Apple II/C64)，支持十进制运算，非官方指令与 2A03 相同
+    Nmos6502,
+    /// Rockwell 65C02：十进制运算的 N/Z 有效，新增 BRA/STZ/PHX/TRB/TSB/RMB/SMB/BBR/BBS 等指令，
+    /// 未定义的操作码都是 NOP。十进制模式下 ADC/SBC 多出的 1 个周期没有模拟
+    Cmos65C02,
 }
 
 pub fn is_page_crossed(addr1: u16, addr2: u16) -> bool {
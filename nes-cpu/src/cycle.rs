@@ -0,0 +1,561 @@
+use log::debug;
+
+use crate::common::{AddressingMode, CpuVariant, InstructionEnum, is_page_crossed};
+use crate::opcode::Op;
+use crate::state::{Context, StatusFlag, execute_instruction};
+
+// 逐周期执行模型，每个周期恰好进行一次真实的总线访问 (包括空读和读改写的两次写入)
+// see: https://www.nesdev.org/6502_cpu.txt
+
+const NMI_VECTOR: u16 = 0xFFFA;
+const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// 指令访问数据的方式，决定寻址完成后的周期序列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AccessKind {
+    Read,
+    Write,
+    Modify,
+}
+
+fn access_kind(instruction: InstructionEnum) -> AccessKind {
+    use InstructionEnum::*;
+    match instruction {
+        STA | STX | STY | STZ | SAX | AHX | SHX | SHY | TAS => AccessKind::Write,
+        ASL | LSR | ROL | ROR | INC | DEC | SLO | SRE | RLA | RRA | DCP | ISC | TSB | TRB => {
+            AccessKind::Modify
+        }
+        instruction if instruction.is_bit_modify() => AccessKind::Modify,
+        _ => AccessKind::Read,
+    }
+}
+
+/// 执行当前指令的下一个周期 (取指为第 1 周期)，指令结束时返回 true
+pub fn instruction_cycle(ctx: &mut Context) -> bool {
+    ctx.cycle += 1;
+    let op = ctx.op.expect("instruction cycle without an opcode");
+
+    use InstructionEnum::*;
+    let done = match op.instruction {
+        instruction if instruction.is_branch() => return branch_cycle(ctx, instruction),
+        instruction if instruction.is_bit_branch() => return bit_branch_cycle(ctx, instruction),
+        BRK => brk_cycle(ctx),
+        JSR => jsr_cycle(ctx),
+        RTS => rts_cycle(ctx),
+        RTI => rti_cycle(ctx),
+        JMP => jmp_cycle(ctx, op),
+        PHA | PHP | PHX | PHY => push_cycle(ctx, op.instruction),
+        PLA | PLP | PLX | PLY => pull_cycle(ctx, op.instruction),
+        // 65C02 的 $5C 是 3 字节 8 周期的 NOP，取完操作数后在 $FFxx 空读
+        NOP if op.cycles == 8 => {
+            if ctx.cycle <= 3 {
+                ctx.operand = ctx.fetch_pc();
+            } else {
+                ctx.read_bus_8bit(0xFF00 | ctx.operand as u16);
+            }
+            ctx.cycle == op.cycles
+        }
+        _ => match op.mode {
+            AddressingMode::Implied | AddressingMode::Accumulator => {
+                // 空读下一字节，PC 不变
+                ctx.read_bus_8bit(ctx.reg_pc);
+                if op.mode == AddressingMode::Accumulator {
+                    ctx.reg_a = execute_instruction(ctx, op.instruction, ctx.reg_a);
+                } else {
+                    execute_instruction(ctx, op.instruction, 0);
+                }
+                true
+            }
+            AddressingMode::Immediate => {
+                let value = ctx.fetch_pc();
+                execute_instruction(ctx, op.instruction, value);
+                true
+            }
+            _ => memory_cycle(ctx, op),
+        },
+    };
+
+    if done {
+        debug_assert_eq!(
+            ctx.cycle,
+            op.cycles + (ctx.page_crossed && op.increase_cycle_when_cross_page) as u8,
+            "cycle count mismatch for {:?} {:?}",
+            op.instruction,
+            op.mode
+        );
+    }
+    done
+}
+
+/// 访存指令：先按寻址模式计算有效地址，再按访问方式读写数据
+fn memory_cycle(ctx: &mut Context, op: Op) -> bool {
+    let instruction = op.instruction;
+    let kind = access_kind(instruction);
+    if ctx.access_cycle == 0 {
+        if address_cycle(ctx, op) {
+            ctx.access_cycle = ctx.cycle + 1;
+        }
+        return false;
+    }
+
+    let address = ctx.data_address;
+    match (kind, ctx.cycle - ctx.access_cycle) {
+        (AccessKind::Read, _) => {
+            let value = ctx.read_bus_8bit(address);
+            execute_instruction(ctx, instruction, value);
+            true
+        }
+        (AccessKind::Write, _) => {
+            // SHA/SHX/SHY/TAS 跨页时会改写目标地址，写入前重新读取
+            let value = execute_instruction(ctx, instruction, 0);
+            ctx.write_bus_8bit(ctx.data_address, value);
+            true
+        }
+        (AccessKind::Modify, 0) => {
+            ctx.operand = ctx.read_bus_8bit(address);
+            false
+        }
+        (AccessKind::Modify, 1) => {
+            // NMOS 的读改写指令先把原值写回一次，65C02 改为再读一次
+            if ctx.variant == CpuVariant::Cmos65C02 {
+                ctx.read_bus_8bit(address);
+            } else {
+                ctx.write_bus_8bit(address, ctx.operand);
+            }
+            ctx.operand = execute_instruction(ctx, instruction, ctx.operand);
+            false
+        }
+        (AccessKind::Modify, _) => {
+            ctx.write_bus_8bit(address, ctx.operand);
+            true
+        }
+    }
+}
+
+/// 寻址周期，有效地址就绪时返回 true
+///
+/// 变址未跨页时，只有跨页才多 1 个周期的指令 (读指令和 65C02 的移位指令) 可以省去修正周期
+fn address_cycle(ctx: &mut Context, op: Op) -> bool {
+    let mode = op.mode;
+    match (mode, ctx.cycle) {
+        (AddressingMode::ZeroPage, _) => {
+            ctx.data_address = ctx.fetch_pc() as u16;
+            true
+        }
+        (
+            AddressingMode::ZeroPageX | AddressingMode::ZeroPageY | AddressingMode::IndexedIndirect,
+            2,
+        ) => {
+            ctx.pointer = ctx.fetch_pc();
+            false
+        }
+        (AddressingMode::ZeroPageX | AddressingMode::ZeroPageY, _) => {
+            // 变址前先空读一次零页指针
+            ctx.read_bus_8bit(ctx.pointer as u16);
+            let index = if mode == AddressingMode::ZeroPageX {
+                ctx.reg_x
+            } else {
+                ctx.reg_y
+            };
+            ctx.data_address = ctx.pointer.wrapping_add(index) as u16;
+            true
+        }
+        (AddressingMode::Absolute | AddressingMode::AbsoluteX | AddressingMode::AbsoluteY, 2) => {
+            ctx.operand = ctx.fetch_pc();
+            false
+        }
+        (AddressingMode::Absolute, _) => {
+            ctx.data_address = (ctx.fetch_pc() as u16) << 8 | ctx.operand as u16;
+            true
+        }
+        (AddressingMode::AbsoluteX | AddressingMode::AbsoluteY, 3) => {
+            let base = (ctx.fetch_pc() as u16) << 8 | ctx.operand as u16;
+            let index = if mode == AddressingMode::AbsoluteX {
+                ctx.reg_x
+            } else {
+                ctx.reg_y
+            };
+            ctx.data_address = base.wrapping_add(index as u16);
+            ctx.page_crossed = is_page_crossed(base, ctx.data_address);
+            op.increase_cycle_when_cross_page && !ctx.page_crossed
+        }
+        (AddressingMode::AbsoluteX | AddressingMode::AbsoluteY, _) => {
+            dummy_read_unfixed(ctx);
+            true
+        }
+        (AddressingMode::IndexedIndirect, 3) => {
+            ctx.read_bus_8bit(ctx.pointer as u16);
+            ctx.pointer = ctx.pointer.wrapping_add(ctx.reg_x);
+            false
+        }
+        (AddressingMode::IndexedIndirect, 4) => {
+            ctx.operand = ctx.read_bus_8bit(ctx.pointer as u16);
+            false
+        }
+        (AddressingMode::IndexedIndirect, _) => {
+            let high = ctx.read_bus_8bit(ctx.pointer.wrapping_add(1) as u16);
+            ctx.data_address = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+        (AddressingMode::IndirectIndexed, 2) => {
+            ctx.pointer = ctx.fetch_pc();
+            false
+        }
+        (AddressingMode::IndirectIndexed, 3) => {
+            ctx.operand = ctx.read_bus_8bit(ctx.pointer as u16);
+            false
+        }
+        (AddressingMode::IndirectIndexed, 4) => {
+            let high = ctx.read_bus_8bit(ctx.pointer.wrapping_add(1) as u16);
+            let base = (high as u16) << 8 | ctx.operand as u16;
+            ctx.data_address = base.wrapping_add(ctx.reg_y as u16);
+            ctx.page_crossed = is_page_crossed(base, ctx.data_address);
+            op.increase_cycle_when_cross_page && !ctx.page_crossed
+        }
+        (AddressingMode::IndirectIndexed, _) => {
+            dummy_read_unfixed(ctx);
+            true
+        }
+        (AddressingMode::ZeroPageIndirect, 2) => {
+            ctx.pointer = ctx.fetch_pc();
+            false
+        }
+        (AddressingMode::ZeroPageIndirect, 3) => {
+            ctx.operand = ctx.read_bus_8bit(ctx.pointer as u16);
+            false
+        }
+        (AddressingMode::ZeroPageIndirect, _) => {
+            let high = ctx.read_bus_8bit(ctx.pointer.wrapping_add(1) as u16);
+            ctx.data_address = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+        _ => panic!("Unsupported addressing mode for memory access: {:?}", mode),
+    }
+}
+
+/// 变址后高字节尚未修正时的空读
+fn dummy_read_unfixed(ctx: &mut Context) {
+    let address = if ctx.page_crossed {
+        ctx.data_address.wrapping_sub(0x100)
+    } else {
+        ctx.data_address
+    };
+    ctx.read_bus_8bit(address);
+}
+
+/// 分支指令：不跳转 2 周期，跳转 3 周期，跳转跨页 4 周期
+fn branch_cycle(ctx: &mut Context, instruction: InstructionEnum) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.operand = ctx.fetch_pc();
+            !ctx.branch_taken(instruction)
+        }
+        3 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            let target = ctx.reg_pc.wrapping_add(ctx.operand as i8 as u16);
+            ctx.page_crossed = is_page_crossed(ctx.reg_pc, target);
+            ctx.data_address = target;
+            ctx.reg_pc = (ctx.reg_pc & 0xFF00) | (target & 0x00FF);
+            !ctx.page_crossed
+        }
+        _ => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            ctx.reg_pc = ctx.data_address;
+            true
+        }
+    }
+}
+
+/// BBRn/BBSn：读零页并空读一次后取偏移量，不跳转 5 周期，跳转 6 周期，跳转跨页 7 周期
+fn bit_branch_cycle(ctx: &mut Context, instruction: InstructionEnum) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.pointer = ctx.fetch_pc();
+            false
+        }
+        3 => {
+            ctx.operand = ctx.read_bus_8bit(ctx.pointer as u16);
+            false
+        }
+        4 => {
+            ctx.read_bus_8bit(ctx.pointer as u16);
+            false
+        }
+        5 => {
+            let taken = execute_instruction(ctx, instruction, ctx.operand) != 0;
+            ctx.operand = ctx.fetch_pc();
+            !taken
+        }
+        6 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            let target = ctx.reg_pc.wrapping_add(ctx.operand as i8 as u16);
+            ctx.page_crossed = is_page_crossed(ctx.reg_pc, target);
+            ctx.reg_pc = target;
+            !ctx.page_crossed
+        }
+        _ => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            true
+        }
+    }
+}
+
+fn brk_cycle(ctx: &mut Context) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.fetch_pc();
+            false
+        }
+        3 => {
+            ctx.push_stack((ctx.reg_pc >> 8) as u8);
+            false
+        }
+        4 => {
+            ctx.push_stack(ctx.reg_pc as u8);
+            false
+        }
+        5 => {
+            // 此时已有 NMI 则劫持 BRK，压栈的 B 位仍为 1
+            ctx.data_address = if take_nmi(ctx) {
+                NMI_VECTOR
+            } else {
+                IRQ_VECTOR
+            };
+            ctx.push_stack(ctx.reg_status | 0x30);
+            false
+        }
+        6 => {
+            ctx.operand = ctx.read_bus_8bit(ctx.data_address);
+            enter_handler(ctx);
+            false
+        }
+        _ => {
+            let high = ctx.read_bus_8bit(ctx.data_address + 1);
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+    }
+}
+
+fn jsr_cycle(ctx: &mut Context) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.operand = ctx.fetch_pc();
+            false
+        }
+        3 => {
+            ctx.peek_stack();
+            false
+        }
+        4 => {
+            ctx.push_stack((ctx.reg_pc >> 8) as u8);
+            false
+        }
+        5 => {
+            ctx.push_stack(ctx.reg_pc as u8);
+            false
+        }
+        _ => {
+            let high = ctx.read_bus_8bit(ctx.reg_pc);
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+    }
+}
+
+fn rts_cycle(ctx: &mut Context) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            false
+        }
+        3 => {
+            ctx.peek_stack();
+            false
+        }
+        4 => {
+            ctx.operand = ctx.pop_stack();
+            false
+        }
+        5 => {
+            let high = ctx.pop_stack();
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            false
+        }
+        _ => {
+            ctx.fetch_pc();
+            true
+        }
+    }
+}
+
+fn rti_cycle(ctx: &mut Context) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            false
+        }
+        3 => {
+            ctx.peek_stack();
+            false
+        }
+        4 => {
+            let status = ctx.pop_stack();
+            ctx.restore_status(status);
+            false
+        }
+        5 => {
+            ctx.operand = ctx.pop_stack();
+            false
+        }
+        _ => {
+            let high = ctx.pop_stack();
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+    }
+}
+
+fn jmp_cycle(ctx: &mut Context, op: Op) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.operand = ctx.fetch_pc();
+            false
+        }
+        3 => {
+            let high = ctx.read_bus_8bit(ctx.reg_pc);
+            ctx.data_address = (high as u16) << 8 | ctx.operand as u16;
+            if op.mode == AddressingMode::Absolute {
+                ctx.reg_pc = ctx.data_address;
+                return true;
+            }
+            false
+        }
+        // 65C02 多用 1 个周期加上变址
+        4 if op.cycles == 6 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            if op.mode == AddressingMode::AbsoluteIndexedIndirect {
+                ctx.data_address = ctx.data_address.wrapping_add(ctx.reg_x as u16);
+            }
+            false
+        }
+        cycle if cycle < op.cycles => {
+            ctx.operand = ctx.read_bus_8bit(ctx.data_address);
+            false
+        }
+        _ => {
+            // NMOS 间接跳转的指针不会跨页，$xxFF 的高字节取自 $xx00，65C02 修正了这个错误
+            let pointer = ctx.data_address;
+            let high_address = if ctx.variant == CpuVariant::Cmos65C02 {
+                pointer.wrapping_add(1)
+            } else {
+                (pointer & 0xFF00) | (pointer.wrapping_add(1) & 0x00FF)
+            };
+            let high = ctx.read_bus_8bit(high_address);
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            true
+        }
+    }
+}
+
+fn push_cycle(ctx: &mut Context, instruction: InstructionEnum) -> bool {
+    if ctx.cycle == 2 {
+        ctx.read_bus_8bit(ctx.reg_pc);
+        return false;
+    }
+    let value = match instruction {
+        InstructionEnum::PHP => ctx.reg_status | 0x30,
+        InstructionEnum::PHX => ctx.reg_x,
+        InstructionEnum::PHY => ctx.reg_y,
+        _ => ctx.reg_a,
+    };
+    ctx.push_stack(value);
+    true
+}
+
+fn pull_cycle(ctx: &mut Context, instruction: InstructionEnum) -> bool {
+    match ctx.cycle {
+        2 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            false
+        }
+        3 => {
+            ctx.peek_stack();
+            false
+        }
+        _ => {
+            let value = ctx.pop_stack();
+            match instruction {
+                InstructionEnum::PLP => ctx.restore_status(value),
+                InstructionEnum::PLX => ctx.reg_x = value,
+                InstructionEnum::PLY => ctx.reg_y = value,
+                _ => ctx.reg_a = value,
+            }
+            if instruction != InstructionEnum::PLP {
+                ctx.set_zero_negative(value);
+            }
+            true
+        }
+    }
+}
+
+/// 执行 NMI/IRQ 响应序列的下一个周期，共 7 个周期，结束时返回 true
+pub fn interrupt_cycle(ctx: &mut Context) -> bool {
+    ctx.cycle += 1;
+    match ctx.cycle {
+        1 | 2 => {
+            ctx.read_bus_8bit(ctx.reg_pc);
+            false
+        }
+        3 => {
+            ctx.push_stack((ctx.reg_pc >> 8) as u8);
+            false
+        }
+        4 => {
+            ctx.push_stack(ctx.reg_pc as u8);
+            false
+        }
+        5 => {
+            // 压栈状态寄存器时才决定向量，期间到来的 NMI 会劫持 IRQ
+            ctx.data_address = if take_nmi(ctx) {
+                NMI_VECTOR
+            } else {
+                IRQ_VECTOR
+            };
+            // 硬件中断压栈时 B 位为 0
+            ctx.push_stack((ctx.reg_status | 0x20) & !0x10);
+            false
+        }
+        6 => {
+            ctx.operand = ctx.read_bus_8bit(ctx.data_address);
+            enter_handler(ctx);
+            false
+        }
+        _ => {
+            let high = ctx.read_bus_8bit(ctx.data_address + 1);
+            ctx.reg_pc = (high as u16) << 8 | ctx.operand as u16;
+            debug!(
+                "Entered interrupt handler at {:04X} via {:04X}",
+                ctx.reg_pc, ctx.data_address
+            );
+            true
+        }
+    }
+}
+
+/// 进入中断处理程序时置位 I，65C02 同时清除 D
+fn enter_handler(ctx: &mut Context) {
+    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
+    if ctx.variant == CpuVariant::Cmos65C02 {
+        ctx.set_status_flag(StatusFlag::DecimalMode, false);
+    }
+}
+
+/// 取走已锁存的 NMI 请求，取走时计为响应了一次 NMI
+fn take_nmi(ctx: &mut Context) -> bool {
+    let nmi = std::mem::take(&mut ctx.nmi_pending);
+    if nmi {
+        ctx.nmi_count = ctx.nmi_count.wrapping_add(1);
+    }
+    nmi
+}
@@ -0,0 +1,324 @@
+use std::fmt;
+
+use nes_base::Reader;
+
+use crate::{
+    common::{AddressingMode, CpuVariant, InstructionEnum},
+    opcode::{is_unofficial, op_table},
+};
+
+/// 一条反汇编结果
+///
+/// 文本格式与 nestest.log (Nintendulator) 保持一致，例如 `LDA $0200,X @ 0205 = 3F`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    /// 指令所在地址
+    pub address: u16,
+    /// 操作码及操作数字节
+    pub bytes: Vec<u8>,
+    /// 助记符，非官方指令使用 nestest 的命名 (如 ISB、NOP)
+    pub mnemonic: &'static str,
+    /// 是否为非官方指令，显示时带 `*` 前缀
+    pub unofficial: bool,
+    /// 操作数文本，包含有效地址和内存值
+    pub operand: String,
+    /// 有效地址 (跳转/分支目标或读写的内存地址)
+    pub effective_address: Option<u16>,
+}
+
+impl Disassembly {
+    /// 指令长度 (字节)
+    pub fn len(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// 下一条指令的地址
+    pub fn next_address(&self) -> u16 {
+        self.address.wrapping_add(self.len())
+    }
+
+    /// 指令字节的十六进制文本，例如 `4C F5 C5`
+    pub fn bytes_hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unofficial {
+            write!(f, "*")?;
+        }
+        write!(f, "{}", self.mnemonic)?;
+        if !self.operand.is_empty() {
+            write!(f, " {}", self.operand)?;
+        }
+        Ok(())
+    }
+}
+
+/// 反汇编 `addr` 处的一条指令
+///
+/// `reg_x`/`reg_y` 用于计算变址寻址的有效地址。指令字节、指针和 `= vv` 的内存值都通过
+/// `Reader::peek` 读取，不会改变设备状态；无法无副作用读取的地址 (I/O 与 Mapper 寄存器)
+/// 与 nestest.log 一样显示为 FF。
+pub fn disassemble<R: Reader + ?Sized>(reader: &R, addr: u16, reg_x: u8, reg_y: u8) -> Disassembly {
+    disassemble_variant(CpuVariant::Ricoh2A03, reader, addr, reg_x, reg_y)
+}
+
+/// 按指定 CPU 型号的指令集反汇编 `addr` 处的一条指令
+pub fn disassemble_variant<R: Reader + ?Sized>(
+    variant: CpuVariant,
+    reader: &R,
+    addr: u16,
+    reg_x: u8,
+    reg_y: u8,
+) -> Disassembly {
+    let peek = |addr: u16| reader.peek(addr).unwrap_or(0xFF);
+    let opcode = peek(addr);
+    let op = op_table(variant)[opcode as usize];
+
+    let len = operand_len(op.mode) + 1;
+    let bytes: Vec<u8> = (0..len).map(|i| peek(addr.wrapping_add(i))).collect();
+    let lo = bytes.get(1).copied().unwrap_or(0);
+    let hi = bytes.get(2).copied().unwrap_or(0);
+    let abs = u16::from_le_bytes([lo, hi]);
+
+    // 零页内回绕读取 16 位指针，与 CPU 的间接寻址一致
+    let read_pointer = |ptr: u16| {
+        let next = (ptr & 0xFF00) | (ptr.wrapping_add(1) & 0x00FF);
+        u16::from_le_bytes([peek(ptr), peek(next)])
+    };
+    let jump = matches!(op.instruction, InstructionEnum::JMP | InstructionEnum::JSR);
+
+    let (operand, effective_address) = match op.mode {
+        AddressingMode::Implied => (String::new(), None),
+        AddressingMode::Accumulator => ("A".to_string(), None),
+        AddressingMode::Immediate => (format!("#${lo:02X}"), None),
+        AddressingMode::Relative => {
+            let target = addr.wrapping_add(2).wrapping_add(lo as i8 as u16);
+            (format!("${target:04X}"), Some(target))
+        }
+        AddressingMode::ZeroPage => {
+            let ea = lo as u16;
+            (format!("${lo:02X} = {:02X}", peek(ea)), Some(ea))
+        }
+        AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+            let (name, index) = if op.mode == AddressingMode::ZeroPageX {
+                ('X', reg_x)
+            } else {
+                ('Y', reg_y)
+            };
+            let ea = lo.wrapping_add(index) as u16;
+            (
+                format!("${lo:02X},{name} @ {ea:02X} = {:02X}", peek(ea)),
+                Some(ea),
+            )
+        }
+        AddressingMode::Absolute if jump => (format!("${abs:04X}"), Some(abs)),
+        AddressingMode::Absolute => (format!("${abs:04X} = {:02X}", peek(abs)), Some(abs)),
+        AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+            let (name, index) = if op.mode == AddressingMode::AbsoluteX {
+                ('X', reg_x)
+            } else {
+                ('Y', reg_y)
+            };
+            let ea = abs.wrapping_add(index as u16);
+            (
+                format!("${abs:04X},{name} @ {ea:04X} = {:02X}", peek(ea)),
+                Some(ea),
+            )
+        }
+        AddressingMode::IndexedIndirect => {
+            let ptr = lo.wrapping_add(reg_x);
+            let ea = read_pointer(ptr as u16);
+            (
+                format!("(${lo:02X},X) @ {ptr:02X} = {ea:04X} = {:02X}", peek(ea)),
+                Some(ea),
+            )
+        }
+        AddressingMode::IndirectIndexed => {
+            let base = read_pointer(lo as u16);
+            let ea = base.wrapping_add(reg_y as u16);
+            (
+                format!("(${lo:02X}),Y = {base:04X} @ {ea:04X} = {:02X}", peek(ea)),
+                Some(ea),
+            )
+        }
+        AddressingMode::Indirect => {
+            let target = read_pointer(abs);
+            (format!("(${abs:04X}) = {target:04X}"), Some(target))
+        }
+        AddressingMode::ZeroPageIndirect => {
+            let ea = read_pointer(lo as u16);
+            (
+                format!("(${lo:02X}) = {ea:04X} = {:02X}", peek(ea)),
+                Some(ea),
+            )
+        }
+        AddressingMode::AbsoluteIndexedIndirect => {
+            let ptr = abs.wrapping_add(reg_x as u16);
+            let target = u16::from_le_bytes([reader.read(ptr), reader.read(ptr.wrapping_add(1))]);
+            (format!("(${abs:04X},X) = {target:04X}"), Some(target))
+        }
+        AddressingMode::ZeroPageRelative => {
+            let target = addr.wrapping_add(3).wrapping_add(hi as i8 as u16);
+            (format!("${lo:02X},${target:04X}"), Some(target))
+        }
+    };
+
+    Disassembly {
+        address: addr,
+        bytes,
+        mnemonic: mnemonic(op.instruction),
+        unofficial: match variant {
+            // 65C02 没有非官方指令，只有未定义的 NOP
+            CpuVariant::Cmos65C02 => op.instruction == InstructionEnum::NOP && opcode != 0xEA,
+            _ => is_unofficial(opcode, op.instruction),
+        },
+        operand,
+        effective_address,
+    }
+}
+
+fn operand_len(mode: AddressingMode) -> u16 {
+    match mode {
+        AddressingMode::Implied | AddressingMode::Accumulator => 0,
+        AddressingMode::Absolute
+        | AddressingMode::AbsoluteX
+        | AddressingMode::AbsoluteY
+        | AddressingMode::Indirect
+        | AddressingMode::AbsoluteIndexedIndirect
+        | AddressingMode::ZeroPageRelative => 2,
+        _ => 1,
+    }
+}
+
+fn mnemonic(instruction: InstructionEnum) -> &'static str {
+    use InstructionEnum::*;
+    match instruction {
+        ADC => "ADC",
+        AND => "AND",
+        ASL => "ASL",
+        BCC => "BCC",
+        BCS => "BCS",
+        BEQ => "BEQ",
+        BIT => "BIT",
+        BMI => "BMI",
+        BNE => "BNE",
+        BPL => "BPL",
+        BRK => "BRK",
+        BVC => "BVC",
+        BVS => "BVS",
+        CLC => "CLC",
+        CLD => "CLD",
+        CLI => "CLI",
+        CLV => "CLV",
+        CMP => "CMP",
+        CPX => "CPX",
+        CPY => "CPY",
+        DEC => "DEC",
+        DEX => "DEX",
+        DEY => "DEY",
+        EOR => "EOR",
+        INC => "INC",
+        INX => "INX",
+        INY => "INY",
+        JMP => "JMP",
+        JSR => "JSR",
+        LDA => "LDA",
+        LDX => "LDX",
+        LDY => "LDY",
+        LSR => "LSR",
+        NOP | SKB | IGN => "NOP",
+        ORA => "ORA",
+        PHA => "PHA",
+        PHP => "PHP",
+        PLA => "PLA",
+        PLP => "PLP",
+        ROL => "ROL",
+        ROR => "ROR",
+        RTI => "RTI",
+        RTS => "RTS",
+        SBC => "SBC",
+        SEC => "SEC",
+        SED => "SED",
+        SEI => "SEI",
+        STA => "STA",
+        STX => "STX",
+        STY => "STY",
+        TAX => "TAX",
+        TAY => "TAY",
+        TSX => "TSX",
+        TXA => "TXA",
+        TXS => "TXS",
+        TYA => "TYA",
+        ALR => "ALR",
+        ANC => "ANC",
+        ARR => "ARR",
+        AXS => "AXS",
+        LAX => "LAX",
+        SAX => "SAX",
+        DCP => "DCP",
+        ISC => "ISB",
+        RLA => "RLA",
+        RRA => "RRA",
+        SLO => "SLO",
+        SRE => "SRE",
+        XAA => "XAA",
+        LXA => "LXA",
+        AHX => "AHX",
+        SHX => "SHX",
+        SHY => "SHY",
+        TAS => "TAS",
+        LAS => "LAS",
+        BRA => "BRA",
+        STZ => "STZ",
+        PHX => "PHX",
+        PHY => "PHY",
+        PLX => "PLX",
+        PLY => "PLY",
+        TRB => "TRB",
+        TSB => "TSB",
+        RMB0 => "RMB0",
+        RMB1 => "RMB1",
+        RMB2 => "RMB2",
+        RMB3 => "RMB3",
+        RMB4 => "RMB4",
+        RMB5 => "RMB5",
+        RMB6 => "RMB6",
+        RMB7 => "RMB7",
+        SMB0 => "SMB0",
+        SMB1 => "SMB1",
+        SMB2 => "SMB2",
+        SMB3 => "SMB3",
+        SMB4 => "SMB4",
+        SMB5 => "SMB5",
+        SMB6 => "SMB6",
+        SMB7 => "SMB7",
+        BBR0 => "BBR0",
+        BBR1 => "BBR1",
+        BBR2 => "BBR2",
+        BBR3 => "BBR3",
+        BBR4 => "BBR4",
+        BBR5 => "BBR5",
+        BBR6 => "BBR6",
+        BBR7 => "BBR7",
+        BBS0 => "BBS0",
+        BBS1 => "BBS1",
+        BBS2 => "BBS2",
+        BBS3 => "BBS3",
+        BBS4 => "BBS4",
+        BBS5 => "BBS5",
+        BBS6 => "BBS6",
+        BBS7 => "BBS7",
+        JAM => "JAM",
+    }
+}
@@ -1,18 +1,31 @@
-use addressing::get_data_address;
+use cycle::{instruction_cycle, interrupt_cycle};
 use log::debug;
 use nes_base::{BusAdapter, Cpu, CpuState, Interrupt};
-use state::{Context, execute_instruction, execute_interrupt};
+use state::{Context, execute_reset};
 use std::{cell::RefCell, rc::Rc};
 
-mod addressing;
+pub use common::CpuVariant;
+pub use disasm::{Disassembly, disassemble, disassemble_variant};
+pub use trace::{PpuPositionSource, Tracer, format_trace_line};
+
 mod common;
+mod cycle;
+mod disasm;
 mod opcode;
 mod state;
+mod trace;
 
 pub struct CpuImpl {
     context: Context,
-    interrupt: Option<Interrupt>,
+    /// 下一个周期执行复位
+    reset_pending: bool,
+    /// 正在执行中断响应序列，结束前不取指
+    servicing: bool,
+    /// 最近两个周期结束时的中断轮询结果，指令边界使用倒数第二个周期的结果
+    poll: bool,
+    prev_poll: bool,
     total_cycles: u32,
+    tracer: Option<Tracer>,
 }
 
 impl Default for CpuImpl {
@@ -22,11 +35,52 @@ impl Default for CpuImpl {
 }
 
 impl CpuImpl {
+    /// NES 使用的 2A03
     pub fn new() -> Self {
+        Self::with_variant(CpuVariant::Ricoh2A03)
+    }
+
+    /// 指定 CPU 型号，型号决定译码表和运算函数表，之后不能更改
+    pub fn with_variant(variant: CpuVariant) -> Self {
         Self {
-            context: Context::new(),
-            interrupt: None,
+            context: Context::new(variant),
+            reset_pending: false,
+            servicing: false,
+            poll: false,
+            prev_poll: false,
             total_cycles: 0,
+            tracer: None,
+        }
+    }
+
+    pub fn variant(&self) -> CpuVariant {
+        self.context.variant
+    }
+
+    /// 设置执行跟踪器，传入 None 关闭跟踪
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// 反汇编只通过 `Reader::peek` 读取总线，跟踪不会改变被跟踪的模拟状态
+    fn trace(&mut self) {
+        let Some(bus) = self.context.bus.clone() else {
+            return;
+        };
+        let dis = disassemble_variant(
+            self.context.variant,
+            &*bus.borrow(),
+            self.context.reg_pc,
+            self.context.reg_x,
+            self.context.reg_y,
+        );
+        let state = self.dump_state();
+        if let Some(tracer) = self.tracer.as_mut()
+            && let Err(e) = tracer.trace(&dis, &state)
+        {
+            // 写入失败时关闭跟踪，避免每条指令都报错
+            log::warn!("Failed to write trace, tracing disabled: {}", e);
+            self.tracer = None;
         }
     }
 }
@@ -35,10 +89,16 @@ impl Cpu for CpuImpl {
     fn set_reg_pc(&mut self, pc: u16) {
         self.context.reg_pc = pc;
         self.context.remaining_cycles = 0; // 重置剩余周期
+        self.context.cycle = 0; // 放弃执行到一半的指令
+        self.servicing = false;
     }
 
     fn reset(&mut self) {
-        execute_interrupt(&mut self.context, Interrupt::Reset);
+        execute_reset(&mut self.context);
+        self.reset_pending = false;
+        self.servicing = false;
+        self.poll = false;
+        self.prev_poll = false;
         self.total_cycles = 7;
     }
 
@@ -47,91 +107,128 @@ impl Cpu for CpuImpl {
     }
 
     fn trigger_interrupt(&mut self, interrupt: Interrupt) {
-        if self.interrupt.is_none() {
-            self.interrupt = Some(interrupt);
-            return;
+        match interrupt {
+            // NMI 边沿触发，响应前重复的请求合并为一次
+            Interrupt::Nmi => self.context.nmi_pending = true,
+            // IRQ 电平触发，这里只拉低 IRQ 线，由 set_irq_line 释放
+            Interrupt::Irq => self.context.irq_line = true,
+            Interrupt::Reset => self.reset_pending = true,
         }
-        // 如果已经有中断在等待，则不处理新的中断
-        // 这可以防止在处理中断时再次触发中断
-        // 例如在 NMI 中断处理期间不允许 IRQ 中断
-        log::warn!(
-            "CPU already has an interrupt pending, ignoring new interrupt: {:?}",
-            interrupt
-        );
+    }
+
+    fn set_irq_line(&mut self, asserted: bool) {
+        self.context.irq_line = asserted;
     }
 
     fn clock(&mut self) {
-        // 执行周期
+        if self.reset_pending {
+            self.reset();
+            return;
+        }
+
+        // 停机后地址总线停在 $FFFF，不再响应中断
+        if self.context.jammed {
+            self.context.read_bus_8bit(0xFFFF);
+            self.total_cycles += 1;
+            return;
+        }
+
+        // 采样上一周期结束时的中断请求
+        self.prev_poll = self.poll;
+        self.poll = self.context.interrupt_requested();
+
+        // 挂起周期
         if self.context.remaining_cycles > 0 {
             self.context.remaining_cycles -= 1;
             self.total_cycles += 1;
             return;
         }
 
-        // 中断周期
-        if let Some(interrupt) = self.interrupt.take() {
-            execute_interrupt(&mut self.context, interrupt);
-            self.interrupt = None;
+        // 指令或中断响应的后续周期
+        if self.context.cycle > 0 {
+            // 跳转且不跨页的分支在最后一个周期不轮询中断，新到的中断推迟到下一条指令之后
+            if !self.servicing
+                && self.context.cycle == 2
+                && self.context.op.is_some_and(|op| op.instruction.is_branch())
+                && self.poll
+                && !self.prev_poll
+            {
+                self.poll = false;
+            }
+
+            let done = if self.servicing {
+                interrupt_cycle(&mut self.context)
+            } else {
+                instruction_cycle(&mut self.context)
+            };
+            if done {
+                self.context.cycle = 0;
+                if std::mem::take(&mut self.servicing) {
+                    // 响应序列结束后至少执行一条处理程序的指令
+                    self.poll = false;
+                }
+            }
+            self.total_cycles += 1;
             return;
         }
 
-        // 取指 && 译码
-        let reg_pc = self.context.reg_pc; // 取指
-        let opcode = self.context.read_bus_8bit(reg_pc); // 从总线读取指令
-        let op = opcode::get_op(opcode); // 指令译码
-        self.context.op = Some(op);
+        // 指令边界：倒数第二个周期轮询到中断时进入响应序列
+        if self.prev_poll {
+            self.servicing = true;
+            interrupt_cycle(&mut self.context);
+            self.total_cycles += 1;
+            return;
+        }
 
-        // 译码获取操作数地址，并更新pc
-        let result = get_data_address(&self.context);
-        self.context.data_address = result.address;
-        self.context.reg_pc = self.context.reg_pc.wrapping_add(result.pc_increment);
-
-        if result.page_crossed && op.increase_cycle_when_cross_page {
-            debug!(
-                "Page crossed during addressing, increasing cycles for instruction: {:?}",
-                op.instruction
-            );
-            self.increase_cycles(1);
+        if self.tracer.is_some() {
+            self.trace();
         }
 
-        // 执行周期
+        // 取指 && 译码，占用第 1 个周期
+        let opcode = self.context.fetch_pc();
+        let op = self.context.op_table[opcode as usize];
+        self.context.op = Some(op);
+        // 65C02 的单字节 NOP 只占取指这 1 个周期
+        self.context.cycle = (op.cycles > 1) as u8;
+        self.context.access_cycle = 0;
+        self.context.page_crossed = false;
         debug!(
             "Executing instruction: {:?}, PC: {:04X}, A: {:02X}, X: {:02X}, Y: {:02X}, SP: {:02X}, Status: {:02X}",
             op.instruction,
-            self.context.reg_pc,
-            self.context.reg_a,
-            self.context.reg_x,
-            self.context.reg_y,
-            self.context.reg_sp,
-            self.context.reg_status
-        );
-        self.increase_cycles(op.cycles as u32);
-        execute_instruction(&mut self.context, op.instruction);
-        debug!(
-            "Instruction executed: {:?}, PC: {:04X}, A: {:02X}, X: {:02X}, Y: {:02X}, SP: {:02X}, Status: {:02X}",
-            op.instruction,
-            self.context.reg_pc,
+            self.context.reg_pc.wrapping_sub(1),
             self.context.reg_a,
             self.context.reg_x,
             self.context.reg_y,
             self.context.reg_sp,
             self.context.reg_status
         );
+        self.total_cycles += 1;
     }
 
     fn dump_state(&self) -> CpuState {
         CpuState {
             total_cycles: self.total_cycles,
-            remaining_cycles: self.context.remaining_cycles,
+            remaining_cycles: self.context.remaining_cycles + (self.context.cycle > 0) as u32,
             reg_a: self.context.reg_a,
             reg_x: self.context.reg_x,
             reg_y: self.context.reg_y,
             reg_sp: self.context.reg_sp,
             reg_pc: self.context.reg_pc,
             reg_status: self.context.reg_status.into(),
+            jammed: self.context.jammed,
+            nmi_count: self.context.nmi_count,
         }
     }
 
+    fn set_registers(&mut self, state: &CpuState) {
+        self.context.reg_a = state.reg_a;
+        self.context.reg_x = state.reg_x;
+        self.context.reg_y = state.reg_y;
+        self.context.reg_sp = state.reg_sp;
+        self.context.reg_pc = state.reg_pc;
+        self.context.reg_status = state.reg_status.into();
+    }
+
     fn attach_bus(&mut self, bus: Rc<RefCell<dyn BusAdapter>>) {
         self.context.bus = Some(bus);
     }
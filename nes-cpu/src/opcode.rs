@@ -1,7 +1,4 @@
-use std::collections::HashMap;
-use std::sync::LazyLock;
-
-use crate::common::{AddressingMode, InstructionEnum};
+use crate::common::{AddressingMode, CpuVariant, InstructionEnum};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct Op {
@@ -11,284 +8,404 @@ pub(crate) struct Op {
     pub increase_cycle_when_cross_page: bool,
 }
 
-struct OpcodeManager {
-    op_table: HashMap<u8, Op>,
+const fn op(
+    instruction: InstructionEnum,
+    mode: AddressingMode,
+    cycles: u8,
+    increase_cycle_when_cross_page: bool,
+) -> Op {
+    Op {
+        instruction,
+        mode,
+        cycles,
+        increase_cycle_when_cross_page,
+    }
 }
 
-impl OpcodeManager {
-    fn new() -> Self {
-        use AddressingMode::*;
-        use InstructionEnum::*;
-        let mut op_args_table: HashMap<u8, (InstructionEnum, AddressingMode, u8, bool)> =
-            HashMap::new();
+/// 2A03/NMOS 6502 的 256 个操作码的译码表，编译期生成，取指时直接按下标查询
+const NMOS_OP_TABLE: [Op; 256] = {
+    use AddressingMode::*;
+    use InstructionEnum::*;
+    let mut table: [Option<Op>; 256] = [None; 256];
+    // 完整插入所有操作码
+    table[0x69] = Some(op(ADC, Immediate, 2, false));
+    table[0x65] = Some(op(ADC, ZeroPage, 3, false));
+    table[0x75] = Some(op(ADC, ZeroPageX, 4, false));
+    table[0x6d] = Some(op(ADC, Absolute, 4, false));
+    table[0x7d] = Some(op(ADC, AbsoluteX, 4, true));
+    table[0x79] = Some(op(ADC, AbsoluteY, 4, true));
+    table[0x61] = Some(op(ADC, IndexedIndirect, 6, false));
+    table[0x71] = Some(op(ADC, IndirectIndexed, 5, true));
+    table[0x29] = Some(op(AND, Immediate, 2, false));
+    table[0x25] = Some(op(AND, ZeroPage, 3, false));
+    table[0x35] = Some(op(AND, ZeroPageX, 4, false));
+    table[0x2d] = Some(op(AND, Absolute, 4, false));
+    table[0x3d] = Some(op(AND, AbsoluteX, 4, true));
+    table[0x39] = Some(op(AND, AbsoluteY, 4, true));
+    table[0x21] = Some(op(AND, IndexedIndirect, 6, false));
+    table[0x31] = Some(op(AND, IndirectIndexed, 5, true));
+    table[0x0a] = Some(op(ASL, Accumulator, 2, false));
+    table[0x06] = Some(op(ASL, ZeroPage, 5, false));
+    table[0x16] = Some(op(ASL, ZeroPageX, 6, false));
+    table[0x0e] = Some(op(ASL, Absolute, 6, false));
+    table[0x1e] = Some(op(ASL, AbsoluteX, 7, false));
+    table[0x90] = Some(op(BCC, Relative, 2, true));
+    table[0xb0] = Some(op(BCS, Relative, 2, true));
+    table[0xf0] = Some(op(BEQ, Relative, 2, true));
+    table[0x24] = Some(op(BIT, ZeroPage, 3, false));
+    table[0x2c] = Some(op(BIT, Absolute, 4, false));
+    table[0x30] = Some(op(BMI, Relative, 2, true));
+    table[0xd0] = Some(op(BNE, Relative, 2, true));
+    table[0x10] = Some(op(BPL, Relative, 2, true));
+    table[0x00] = Some(op(BRK, Implied, 7, false));
+    table[0x50] = Some(op(BVC, Relative, 2, true));
+    table[0x70] = Some(op(BVS, Relative, 2, true));
+    table[0x18] = Some(op(CLC, Implied, 2, false));
+    table[0xd8] = Some(op(CLD, Implied, 2, false));
+    table[0x58] = Some(op(CLI, Implied, 2, false));
+    table[0xb8] = Some(op(CLV, Implied, 2, false));
+    table[0xc9] = Some(op(CMP, Immediate, 2, false));
+    table[0xc5] = Some(op(CMP, ZeroPage, 3, false));
+    table[0xd5] = Some(op(CMP, ZeroPageX, 4, false));
+    table[0xcd] = Some(op(CMP, Absolute, 4, false));
+    table[0xdd] = Some(op(CMP, AbsoluteX, 4, true));
+    table[0xd9] = Some(op(CMP, AbsoluteY, 4, true));
+    table[0xc1] = Some(op(CMP, IndexedIndirect, 6, true));
+    table[0xd1] = Some(op(CMP, IndirectIndexed, 5, true));
+    table[0xe0] = Some(op(CPX, Immediate, 2, false));
+    table[0xe4] = Some(op(CPX, ZeroPage, 3, false));
+    table[0xec] = Some(op(CPX, Absolute, 4, false));
+    table[0xc0] = Some(op(CPY, Immediate, 2, false));
+    table[0xc4] = Some(op(CPY, ZeroPage, 3, false));
+    table[0xcc] = Some(op(CPY, Absolute, 4, false));
+    table[0xc6] = Some(op(DEC, ZeroPage, 5, false));
+    table[0xd6] = Some(op(DEC, ZeroPageX, 6, false));
+    table[0xce] = Some(op(DEC, Absolute, 6, false));
+    table[0xde] = Some(op(DEC, AbsoluteX, 7, false));
+    table[0xca] = Some(op(DEX, Implied, 2, false));
+    table[0x88] = Some(op(DEY, Implied, 2, false));
+    table[0x49] = Some(op(EOR, Immediate, 2, false));
+    table[0x45] = Some(op(EOR, ZeroPage, 3, false));
+    table[0x55] = Some(op(EOR, ZeroPageX, 4, false));
+    table[0x4d] = Some(op(EOR, Absolute, 4, false));
+    table[0x5d] = Some(op(EOR, AbsoluteX, 4, true));
+    table[0x59] = Some(op(EOR, AbsoluteY, 4, true));
+    table[0x41] = Some(op(EOR, IndexedIndirect, 6, false));
+    table[0x51] = Some(op(EOR, IndirectIndexed, 5, true));
+    table[0xe6] = Some(op(INC, ZeroPage, 5, false));
+    table[0xf6] = Some(op(INC, ZeroPageX, 6, false));
+    table[0xee] = Some(op(INC, Absolute, 6, false));
+    table[0xfe] = Some(op(INC, AbsoluteX, 7, false));
+    table[0xe8] = Some(op(INX, Implied, 2, false));
+    table[0xc8] = Some(op(INY, Implied, 2, false));
+    table[0x4c] = Some(op(JMP, Absolute, 3, false));
+    table[0x6c] = Some(op(JMP, Indirect, 5, false));
+    table[0x20] = Some(op(JSR, Absolute, 6, false));
+    table[0xa9] = Some(op(LDA, Immediate, 2, false));
+    table[0xa5] = Some(op(LDA, ZeroPage, 3, false));
+    table[0xb5] = Some(op(LDA, ZeroPageX, 4, false));
+    table[0xad] = Some(op(LDA, Absolute, 4, false));
+    table[0xbd] = Some(op(LDA, AbsoluteX, 4, true));
+    table[0xb9] = Some(op(LDA, AbsoluteY, 4, true));
+    table[0xa1] = Some(op(LDA, IndexedIndirect, 6, false));
+    table[0xb1] = Some(op(LDA, IndirectIndexed, 5, true));
+    table[0xa2] = Some(op(LDX, Immediate, 2, false));
+    table[0xa6] = Some(op(LDX, ZeroPage, 3, false));
+    table[0xb6] = Some(op(LDX, ZeroPageY, 4, false));
+    table[0xae] = Some(op(LDX, Absolute, 4, false));
+    table[0xbe] = Some(op(LDX, AbsoluteY, 4, true));
+    table[0xa0] = Some(op(LDY, Immediate, 2, false));
+    table[0xa4] = Some(op(LDY, ZeroPage, 3, false));
+    table[0xb4] = Some(op(LDY, ZeroPageX, 4, false));
+    table[0xac] = Some(op(LDY, Absolute, 4, false));
+    table[0xbc] = Some(op(LDY, AbsoluteX, 4, true));
+    table[0x4a] = Some(op(LSR, Accumulator, 2, false));
+    table[0x46] = Some(op(LSR, ZeroPage, 5, false));
+    table[0x56] = Some(op(LSR, ZeroPageX, 6, false));
+    table[0x4e] = Some(op(LSR, Absolute, 6, false));
+    table[0x5e] = Some(op(LSR, AbsoluteX, 7, false));
+    table[0x1a] = Some(op(NOP, Implied, 2, false));
+    table[0x3a] = Some(op(NOP, Implied, 2, false));
+    table[0x5a] = Some(op(NOP, Implied, 2, false));
+    table[0x7a] = Some(op(NOP, Implied, 2, false));
+    table[0xda] = Some(op(NOP, Implied, 2, false));
+    table[0xea] = Some(op(NOP, Implied, 2, false));
+    table[0xfa] = Some(op(NOP, Implied, 2, false));
+    table[0x09] = Some(op(ORA, Immediate, 2, false));
+    table[0x05] = Some(op(ORA, ZeroPage, 3, false));
+    table[0x15] = Some(op(ORA, ZeroPageX, 4, false));
+    table[0x0d] = Some(op(ORA, Absolute, 4, false));
+    table[0x1d] = Some(op(ORA, AbsoluteX, 4, true));
+    table[0x19] = Some(op(ORA, AbsoluteY, 4, true));
+    table[0x01] = Some(op(ORA, IndexedIndirect, 6, false));
+    table[0x11] = Some(op(ORA, IndirectIndexed, 5, true));
+    table[0x48] = Some(op(PHA, Implied, 3, false));
+    table[0x08] = Some(op(PHP, Implied, 3, false));
+    table[0x68] = Some(op(PLA, Implied, 4, false));
+    table[0x28] = Some(op(PLP, Implied, 4, false));
+    table[0x2a] = Some(op(ROL, Accumulator, 2, false));
+    table[0x26] = Some(op(ROL, ZeroPage, 5, false));
+    table[0x36] = Some(op(ROL, ZeroPageX, 6, false));
+    table[0x2e] = Some(op(ROL, Absolute, 6, false));
+    table[0x3e] = Some(op(ROL, AbsoluteX, 7, false));
+    table[0x6a] = Some(op(ROR, Accumulator, 2, false));
+    table[0x66] = Some(op(ROR, ZeroPage, 5, false));
+    table[0x76] = Some(op(ROR, ZeroPageX, 6, false));
+    table[0x6e] = Some(op(ROR, Absolute, 6, false));
+    table[0x7e] = Some(op(ROR, AbsoluteX, 7, false));
+    table[0x40] = Some(op(RTI, Implied, 6, false));
+    table[0x60] = Some(op(RTS, Implied, 6, false));
+    table[0xeb] = Some(op(SBC, Immediate, 2, false));
+    table[0xe9] = Some(op(SBC, Immediate, 2, false));
+    table[0xe5] = Some(op(SBC, ZeroPage, 3, false));
+    table[0xf5] = Some(op(SBC, ZeroPageX, 4, false));
+    table[0xed] = Some(op(SBC, Absolute, 4, false));
+    table[0xfd] = Some(op(SBC, AbsoluteX, 4, true));
+    table[0xf9] = Some(op(SBC, AbsoluteY, 4, true));
+    table[0xe1] = Some(op(SBC, IndexedIndirect, 6, false));
+    table[0xf1] = Some(op(SBC, IndirectIndexed, 5, true));
+    table[0x38] = Some(op(SEC, Implied, 2, false));
+    table[0xf8] = Some(op(SED, Implied, 2, false));
+    table[0x78] = Some(op(SEI, Implied, 2, false));
+    table[0x85] = Some(op(STA, ZeroPage, 3, false));
+    table[0x95] = Some(op(STA, ZeroPageX, 4, false));
+    table[0x8d] = Some(op(STA, Absolute, 4, false));
+    table[0x9d] = Some(op(STA, AbsoluteX, 5, false));
+    table[0x99] = Some(op(STA, AbsoluteY, 5, false));
+    table[0x81] = Some(op(STA, IndexedIndirect, 6, false));
+    table[0x91] = Some(op(STA, IndirectIndexed, 6, false));
+    table[0x86] = Some(op(STX, ZeroPage, 3, false));
+    table[0x96] = Some(op(STX, ZeroPageY, 4, false));
+    table[0x8e] = Some(op(STX, Absolute, 4, false));
+    table[0x84] = Some(op(STY, ZeroPage, 3, false));
+    table[0x94] = Some(op(STY, ZeroPageX, 4, false));
+    table[0x8c] = Some(op(STY, Absolute, 4, false));
+    table[0xaa] = Some(op(TAX, Implied, 2, false));
+    table[0xa8] = Some(op(TAY, Implied, 2, false));
+    table[0xba] = Some(op(TSX, Implied, 2, false));
+    table[0x8a] = Some(op(TXA, Implied, 2, false));
+    table[0x9a] = Some(op(TXS, Implied, 2, false));
+    table[0x98] = Some(op(TYA, Implied, 2, false));
+    table[0x4b] = Some(op(ALR, Immediate, 2, false));
+    table[0x0b] = Some(op(ANC, Immediate, 2, false));
+    table[0x2b] = Some(op(ANC, Immediate, 2, false));
+    table[0x6b] = Some(op(ARR, Immediate, 2, false));
+    table[0xcb] = Some(op(AXS, Immediate, 2, false));
+    table[0xa7] = Some(op(LAX, ZeroPage, 3, false));
+    table[0xb7] = Some(op(LAX, ZeroPageY, 4, false));
+    table[0xaf] = Some(op(LAX, Absolute, 4, false));
+    table[0xbf] = Some(op(LAX, AbsoluteY, 4, true));
+    table[0xa3] = Some(op(LAX, IndexedIndirect, 6, false));
+    table[0xb3] = Some(op(LAX, IndirectIndexed, 5, true));
+    table[0x87] = Some(op(SAX, ZeroPage, 3, false));
+    table[0x97] = Some(op(SAX, ZeroPageY, 4, false));
+    table[0x8f] = Some(op(SAX, Absolute, 4, false));
+    table[0x83] = Some(op(SAX, IndexedIndirect, 6, true));
+    table[0xc7] = Some(op(DCP, ZeroPage, 5, false));
+    table[0xd7] = Some(op(DCP, ZeroPageX, 6, false));
+    table[0xcf] = Some(op(DCP, Absolute, 6, false));
+    table[0xdf] = Some(op(DCP, AbsoluteX, 7, false));
+    table[0xdb] = Some(op(DCP, AbsoluteY, 7, false));
+    table[0xc3] = Some(op(DCP, IndexedIndirect, 8, false));
+    table[0xd3] = Some(op(DCP, IndirectIndexed, 8, false));
+    table[0xe7] = Some(op(ISC, ZeroPage, 5, false));
+    table[0xf7] = Some(op(ISC, ZeroPageX, 6, false));
+    table[0xef] = Some(op(ISC, Absolute, 6, false));
+    table[0xff] = Some(op(ISC, AbsoluteX, 7, false));
+    table[0xfb] = Some(op(ISC, AbsoluteY, 7, false));
+    table[0xe3] = Some(op(ISC, IndexedIndirect, 8, false));
+    table[0xf3] = Some(op(ISC, IndirectIndexed, 8, false));
+    table[0x27] = Some(op(RLA, ZeroPage, 5, false));
+    table[0x37] = Some(op(RLA, ZeroPageX, 6, false));
+    table[0x2f] = Some(op(RLA, Absolute, 6, false));
+    table[0x3f] = Some(op(RLA, AbsoluteX, 7, false));
+    table[0x3b] = Some(op(RLA, AbsoluteY, 7, false));
+    table[0x23] = Some(op(RLA, IndexedIndirect, 8, false));
+    table[0x33] = Some(op(RLA, IndirectIndexed, 8, false));
+    table[0x67] = Some(op(RRA, ZeroPage, 5, false));
+    table[0x77] = Some(op(RRA, ZeroPageX, 6, false));
+    table[0x6f] = Some(op(RRA, Absolute, 6, false));
+    table[0x7f] = Some(op(RRA, AbsoluteX, 7, false));
+    table[0x7b] = Some(op(RRA, AbsoluteY, 7, false));
+    table[0x63] = Some(op(RRA, IndexedIndirect, 8, false));
+    table[0x73] = Some(op(RRA, IndirectIndexed, 8, false));
+    table[0x07] = Some(op(SLO, ZeroPage, 5, false));
+    table[0x17] = Some(op(SLO, ZeroPageX, 6, false));
+    table[0x0f] = Some(op(SLO, Absolute, 6, false));
+    table[0x1f] = Some(op(SLO, AbsoluteX, 7, false));
+    table[0x1b] = Some(op(SLO, AbsoluteY, 7, false));
+    table[0x03] = Some(op(SLO, IndexedIndirect, 8, false));
+    table[0x13] = Some(op(SLO, IndirectIndexed, 8, false));
+    table[0x47] = Some(op(SRE, ZeroPage, 5, false));
+    table[0x57] = Some(op(SRE, ZeroPageX, 6, false));
+    table[0x4f] = Some(op(SRE, Absolute, 6, false));
+    table[0x5f] = Some(op(SRE, AbsoluteX, 7, false));
+    table[0x5b] = Some(op(SRE, AbsoluteY, 7, false));
+    table[0x43] = Some(op(SRE, IndexedIndirect, 8, false));
+    table[0x53] = Some(op(SRE, IndirectIndexed, 8, false));
+    table[0x80] = Some(op(SKB, Immediate, 2, false));
+    table[0x82] = Some(op(SKB, Immediate, 2, false));
+    table[0x89] = Some(op(SKB, Immediate, 2, false));
+    table[0xc2] = Some(op(SKB, Immediate, 2, false));
+    table[0xe2] = Some(op(SKB, Immediate, 2, false));
+    table[0x0c] = Some(op(IGN, Absolute, 4, false));
+    table[0x1c] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0x3c] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0x5c] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0x7c] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0xdc] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0xfc] = Some(op(IGN, AbsoluteX, 4, true));
+    table[0x04] = Some(op(IGN, ZeroPage, 3, false));
+    table[0x44] = Some(op(IGN, ZeroPage, 3, false));
+    table[0x64] = Some(op(IGN, ZeroPage, 3, false));
+    table[0x14] = Some(op(IGN, ZeroPageX, 4, false));
+    table[0x34] = Some(op(IGN, ZeroPageX, 4, false));
+    table[0x54] = Some(op(IGN, ZeroPageX, 4, false));
+    table[0x74] = Some(op(IGN, ZeroPageX, 4, false));
+    table[0xd4] = Some(op(IGN, ZeroPageX, 4, false));
+    table[0xf4] = Some(op(IGN, ZeroPageX, 4, false));
+    // 不稳定的非法指令
+    table[0x8b] = Some(op(XAA, Immediate, 2, false));
+    table[0xab] = Some(op(LXA, Immediate, 2, false));
+    table[0x93] = Some(op(AHX, IndirectIndexed, 6, false));
+    table[0x9f] = Some(op(AHX, AbsoluteY, 5, false));
+    table[0x9e] = Some(op(SHX, AbsoluteY, 5, false));
+    table[0x9c] = Some(op(SHY, AbsoluteX, 5, false));
+    table[0x9b] = Some(op(TAS, AbsoluteY, 5, false));
+    table[0xbb] = Some(op(LAS, AbsoluteY, 4, true));
+    // JAM 使 CPU 停机，只能通过复位恢复
+    let jam_opcodes = [
+        0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xb2, 0xd2, 0xf2,
+    ];
+    let mut i = 0;
+    while i < jam_opcodes.len() {
+        table[jam_opcodes[i]] = Some(op(JAM, Implied, 2, false));
+        i += 1;
+    }
 
-        // 完整插入所有操作码
-        op_args_table.insert(0x69, (ADC, Immediate, 2, false));
-        op_args_table.insert(0x65, (ADC, ZeroPage, 3, false));
-        op_args_table.insert(0x75, (ADC, ZeroPageX, 4, false));
-        op_args_table.insert(0x6d, (ADC, Absolute, 4, false));
-        op_args_table.insert(0x7d, (ADC, AbsoluteX, 4, true));
-        op_args_table.insert(0x79, (ADC, AbsoluteY, 4, true));
-        op_args_table.insert(0x61, (ADC, IndexedIndirect, 6, false));
-        op_args_table.insert(0x71, (ADC, IndirectIndexed, 5, true));
-        op_args_table.insert(0x29, (AND, Immediate, 2, false));
-        op_args_table.insert(0x25, (AND, ZeroPage, 3, false));
-        op_args_table.insert(0x35, (AND, ZeroPageX, 4, false));
-        op_args_table.insert(0x2d, (AND, Absolute, 4, false));
-        op_args_table.insert(0x3d, (AND, AbsoluteX, 4, true));
-        op_args_table.insert(0x39, (AND, AbsoluteY, 4, true));
-        op_args_table.insert(0x21, (AND, IndexedIndirect, 6, false));
-        op_args_table.insert(0x31, (AND, IndirectIndexed, 5, true));
-        op_args_table.insert(0x0a, (ASL, Accumulator, 2, false));
-        op_args_table.insert(0x06, (ASL, ZeroPage, 5, false));
-        op_args_table.insert(0x16, (ASL, ZeroPageX, 6, false));
-        op_args_table.insert(0x0e, (ASL, Absolute, 6, false));
-        op_args_table.insert(0x1e, (ASL, AbsoluteX, 7, false));
-        op_args_table.insert(0x90, (BCC, Relative, 2, true));
-        op_args_table.insert(0xb0, (BCS, Relative, 2, true));
-        op_args_table.insert(0xf0, (BEQ, Relative, 2, true));
-        op_args_table.insert(0x24, (BIT, ZeroPage, 3, false));
-        op_args_table.insert(0x2c, (BIT, Absolute, 4, false));
-        op_args_table.insert(0x30, (BMI, Relative, 2, true));
-        op_args_table.insert(0xd0, (BNE, Relative, 2, true));
-        op_args_table.insert(0x10, (BPL, Relative, 2, true));
-        op_args_table.insert(0x00, (BRK, Implied, 7, false));
-        op_args_table.insert(0x50, (BVC, Relative, 2, true));
-        op_args_table.insert(0x70, (BVS, Relative, 2, true));
-        op_args_table.insert(0x18, (CLC, Implied, 2, false));
-        op_args_table.insert(0xd8, (CLD, Implied, 2, false));
-        op_args_table.insert(0x58, (CLI, Implied, 2, false));
-        op_args_table.insert(0xb8, (CLV, Implied, 2, false));
-        op_args_table.insert(0xc9, (CMP, Immediate, 2, false));
-        op_args_table.insert(0xc5, (CMP, ZeroPage, 3, false));
-        op_args_table.insert(0xd5, (CMP, ZeroPageX, 4, false));
-        op_args_table.insert(0xcd, (CMP, Absolute, 4, false));
-        op_args_table.insert(0xdd, (CMP, AbsoluteX, 4, true));
-        op_args_table.insert(0xd9, (CMP, AbsoluteY, 4, true));
-        op_args_table.insert(0xc1, (CMP, IndexedIndirect, 6, true));
-        op_args_table.insert(0xd1, (CMP, IndirectIndexed, 5, true));
-        op_args_table.insert(0xe0, (CPX, Immediate, 2, false));
-        op_args_table.insert(0xe4, (CPX, ZeroPage, 3, false));
-        op_args_table.insert(0xec, (CPX, Absolute, 4, false));
-        op_args_table.insert(0xc0, (CPY, Immediate, 2, false));
-        op_args_table.insert(0xc4, (CPY, ZeroPage, 3, false));
-        op_args_table.insert(0xcc, (CPY, Absolute, 4, false));
-        op_args_table.insert(0xc6, (DEC, ZeroPage, 5, false));
-        op_args_table.insert(0xd6, (DEC, ZeroPageX, 6, false));
-        op_args_table.insert(0xce, (DEC, Absolute, 6, false));
-        op_args_table.insert(0xde, (DEC, AbsoluteX, 7, false));
-        op_args_table.insert(0xca, (DEX, Implied, 2, false));
-        op_args_table.insert(0x88, (DEY, Implied, 2, false));
-        op_args_table.insert(0x49, (EOR, Immediate, 2, false));
-        op_args_table.insert(0x45, (EOR, ZeroPage, 3, false));
-        op_args_table.insert(0x55, (EOR, ZeroPageX, 4, false));
-        op_args_table.insert(0x4d, (EOR, Absolute, 4, false));
-        op_args_table.insert(0x5d, (EOR, AbsoluteX, 4, true));
-        op_args_table.insert(0x59, (EOR, AbsoluteY, 4, true));
-        op_args_table.insert(0x41, (EOR, IndexedIndirect, 6, false));
-        op_args_table.insert(0x51, (EOR, IndirectIndexed, 5, true));
-        op_args_table.insert(0xe6, (INC, ZeroPage, 5, false));
-        op_args_table.insert(0xf6, (INC, ZeroPageX, 6, false));
-        op_args_table.insert(0xee, (INC, Absolute, 6, false));
-        op_args_table.insert(0xfe, (INC, AbsoluteX, 7, false));
-        op_args_table.insert(0xe8, (INX, Implied, 2, false));
-        op_args_table.insert(0xc8, (INY, Implied, 2, false));
-        op_args_table.insert(0x4c, (JMP, Absolute, 3, false));
-        op_args_table.insert(0x6c, (JMP, Indirect, 5, false));
-        op_args_table.insert(0x20, (JSR, Absolute, 6, false));
-        op_args_table.insert(0xa9, (LDA, Immediate, 2, false));
-        op_args_table.insert(0xa5, (LDA, ZeroPage, 3, false));
-        op_args_table.insert(0xb5, (LDA, ZeroPageX, 4, false));
-        op_args_table.insert(0xad, (LDA, Absolute, 4, false));
-        op_args_table.insert(0xbd, (LDA, AbsoluteX, 4, true));
-        op_args_table.insert(0xb9, (LDA, AbsoluteY, 4, true));
-        op_args_table.insert(0xa1, (LDA, IndexedIndirect, 6, false));
-        op_args_table.insert(0xb1, (LDA, IndirectIndexed, 5, true));
-        op_args_table.insert(0xa2, (LDX, Immediate, 2, false));
-        op_args_table.insert(0xa6, (LDX, ZeroPage, 3, false));
-        op_args_table.insert(0xb6, (LDX, ZeroPageY, 4, false));
-        op_args_table.insert(0xae, (LDX, Absolute, 4, false));
-        op_args_table.insert(0xbe, (LDX, AbsoluteY, 4, true));
-        op_args_table.insert(0xa0, (LDY, Immediate, 2, false));
-        op_args_table.insert(0xa4, (LDY, ZeroPage, 3, false));
-        op_args_table.insert(0xb4, (LDY, ZeroPageX, 4, false));
-        op_args_table.insert(0xac, (LDY, Absolute, 4, false));
-        op_args_table.insert(0xbc, (LDY, AbsoluteX, 4, true));
-        op_args_table.insert(0x4a, (LSR, Accumulator, 2, false));
-        op_args_table.insert(0x46, (LSR, ZeroPage, 5, false));
-        op_args_table.insert(0x56, (LSR, ZeroPageX, 6, false));
-        op_args_table.insert(0x4e, (LSR, Absolute, 6, false));
-        op_args_table.insert(0x5e, (LSR, AbsoluteX, 7, false));
-        op_args_table.insert(0x1a, (NOP, Implied, 2, false));
-        op_args_table.insert(0x3a, (NOP, Implied, 2, false));
-        op_args_table.insert(0x5a, (NOP, Implied, 2, false));
-        op_args_table.insert(0x7a, (NOP, Implied, 2, false));
-        op_args_table.insert(0xda, (NOP, Implied, 2, false));
-        op_args_table.insert(0xea, (NOP, Implied, 2, false));
-        op_args_table.insert(0xfa, (NOP, Implied, 2, false));
-        op_args_table.insert(0x09, (ORA, Immediate, 2, false));
-        op_args_table.insert(0x05, (ORA, ZeroPage, 3, false));
-        op_args_table.insert(0x15, (ORA, ZeroPageX, 4, false));
-        op_args_table.insert(0x0d, (ORA, Absolute, 4, false));
-        op_args_table.insert(0x1d, (ORA, AbsoluteX, 4, true));
-        op_args_table.insert(0x19, (ORA, AbsoluteY, 4, true));
-        op_args_table.insert(0x01, (ORA, IndexedIndirect, 6, false));
-        op_args_table.insert(0x11, (ORA, IndirectIndexed, 5, true));
-        op_args_table.insert(0x48, (PHA, Implied, 3, false));
-        op_args_table.insert(0x08, (PHP, Implied, 3, false));
-        op_args_table.insert(0x68, (PLA, Implied, 4, false));
-        op_args_table.insert(0x28, (PLP, Implied, 4, false));
-        op_args_table.insert(0x2a, (ROL, Accumulator, 2, false));
-        op_args_table.insert(0x26, (ROL, ZeroPage, 5, false));
-        op_args_table.insert(0x36, (ROL, ZeroPageX, 6, false));
-        op_args_table.insert(0x2e, (ROL, Absolute, 6, false));
-        op_args_table.insert(0x3e, (ROL, AbsoluteX, 7, false));
-        op_args_table.insert(0x6a, (ROR, Accumulator, 2, false));
-        op_args_table.insert(0x66, (ROR, ZeroPage, 5, false));
-        op_args_table.insert(0x76, (ROR, ZeroPageX, 6, false));
-        op_args_table.insert(0x6e, (ROR, Absolute, 6, false));
-        op_args_table.insert(0x7e, (ROR, AbsoluteX, 7, false));
-        op_args_table.insert(0x40, (RTI, Implied, 6, false));
-        op_args_table.insert(0x60, (RTS, Implied, 6, false));
-        op_args_table.insert(0xeb, (SBC, Immediate, 2, false));
-        op_args_table.insert(0xe9, (SBC, Immediate, 2, false));
-        op_args_table.insert(0xe5, (SBC, ZeroPage, 3, false));
-        op_args_table.insert(0xf5, (SBC, ZeroPageX, 4, false));
-        op_args_table.insert(0xed, (SBC, Absolute, 4, false));
-        op_args_table.insert(0xfd, (SBC, AbsoluteX, 4, true));
-        op_args_table.insert(0xf9, (SBC, AbsoluteY, 4, true));
-        op_args_table.insert(0xe1, (SBC, IndexedIndirect, 6, false));
-        op_args_table.insert(0xf1, (SBC, IndirectIndexed, 5, true));
-        op_args_table.insert(0x38, (SEC, Implied, 2, false));
-        op_args_table.insert(0xf8, (SED, Implied, 2, false));
-        op_args_table.insert(0x78, (SEI, Implied, 2, false));
-        op_args_table.insert(0x85, (STA, ZeroPage, 3, false));
-        op_args_table.insert(0x95, (STA, ZeroPageX, 4, false));
-        op_args_table.insert(0x8d, (STA, Absolute, 4, false));
-        op_args_table.insert(0x9d, (STA, AbsoluteX, 5, false));
-        op_args_table.insert(0x99, (STA, AbsoluteY, 5, false));
-        op_args_table.insert(0x81, (STA, IndexedIndirect, 6, false));
-        op_args_table.insert(0x91, (STA, IndirectIndexed, 6, false));
-        op_args_table.insert(0x86, (STX, ZeroPage, 3, false));
-        op_args_table.insert(0x96, (STX, ZeroPageY, 4, false));
-        op_args_table.insert(0x8e, (STX, Absolute, 4, false));
-        op_args_table.insert(0x84, (STY, ZeroPage, 3, false));
-        op_args_table.insert(0x94, (STY, ZeroPageX, 4, false));
-        op_args_table.insert(0x8c, (STY, Absolute, 4, false));
-        op_args_table.insert(0xaa, (TAX, Implied, 2, false));
-        op_args_table.insert(0xa8, (TAY, Implied, 2, false));
-        op_args_table.insert(0xba, (TSX, Implied, 2, false));
-        op_args_table.insert(0x8a, (TXA, Implied, 2, false));
-        op_args_table.insert(0x9a, (TXS, Implied, 2, false));
-        op_args_table.insert(0x98, (TYA, Implied, 2, false));
-        op_args_table.insert(0x4b, (ALR, Immediate, 2, false));
-        op_args_table.insert(0x0b, (ANC, Immediate, 2, false));
-        op_args_table.insert(0x2b, (ANC, Immediate, 2, false));
-        op_args_table.insert(0x6b, (ARR, Immediate, 2, false));
-        op_args_table.insert(0xcb, (AXS, Immediate, 2, false));
-        op_args_table.insert(0xa7, (LAX, ZeroPage, 3, false));
-        op_args_table.insert(0xb7, (LAX, ZeroPageY, 4, false));
-        op_args_table.insert(0xaf, (LAX, Absolute, 4, false));
-        op_args_table.insert(0xbf, (LAX, AbsoluteY, 4, true));
-        op_args_table.insert(0xa3, (LAX, IndexedIndirect, 6, false));
-        op_args_table.insert(0xb3, (LAX, IndirectIndexed, 5, true));
-        op_args_table.insert(0x87, (SAX, ZeroPage, 3, false));
-        op_args_table.insert(0x97, (SAX, ZeroPageY, 4, false));
-        op_args_table.insert(0x8f, (SAX, Absolute, 4, false));
-        op_args_table.insert(0x83, (SAX, IndexedIndirect, 6, true));
-        op_args_table.insert(0xc7, (DCP, ZeroPage, 5, false));
-        op_args_table.insert(0xd7, (DCP, ZeroPageX, 6, false));
-        op_args_table.insert(0xcf, (DCP, Absolute, 6, false));
-        op_args_table.insert(0xdf, (DCP, AbsoluteX, 7, false));
-        op_args_table.insert(0xdb, (DCP, AbsoluteY, 7, false));
-        op_args_table.insert(0xc3, (DCP, IndexedIndirect, 8, false));
-        op_args_table.insert(0xd3, (DCP, IndirectIndexed, 8, false));
-        op_args_table.insert(0xe7, (ISC, ZeroPage, 5, false));
-        op_args_table.insert(0xf7, (ISC, ZeroPageX, 6, false));
-        op_args_table.insert(0xef, (ISC, Absolute, 6, false));
-        op_args_table.insert(0xff, (ISC, AbsoluteX, 7, false));
-        op_args_table.insert(0xfb, (ISC, AbsoluteY, 7, false));
-        op_args_table.insert(0xe3, (ISC, IndexedIndirect, 8, false));
-        op_args_table.insert(0xf3, (ISC, IndirectIndexed, 8, false));
-        op_args_table.insert(0x27, (RLA, ZeroPage, 5, false));
-        op_args_table.insert(0x37, (RLA, ZeroPageX, 6, false));
-        op_args_table.insert(0x2f, (RLA, Absolute, 6, false));
-        op_args_table.insert(0x3f, (RLA, AbsoluteX, 7, false));
-        op_args_table.insert(0x3b, (RLA, AbsoluteY, 7, false));
-        op_args_table.insert(0x23, (RLA, IndexedIndirect, 8, false));
-        op_args_table.insert(0x33, (RLA, IndirectIndexed, 8, false));
-        op_args_table.insert(0x67, (RRA, ZeroPage, 5, false));
-        op_args_table.insert(0x77, (RRA, ZeroPageX, 6, false));
-        op_args_table.insert(0x6f, (RRA, Absolute, 6, false));
-        op_args_table.insert(0x7f, (RRA, AbsoluteX, 7, false));
-        op_args_table.insert(0x7b, (RRA, AbsoluteY, 7, false));
-        op_args_table.insert(0x63, (RRA, IndexedIndirect, 8, false));
-        op_args_table.insert(0x73, (RRA, IndirectIndexed, 8, false));
-        op_args_table.insert(0x07, (SLO, ZeroPage, 5, false));
-        op_args_table.insert(0x17, (SLO, ZeroPageX, 6, false));
-        op_args_table.insert(0x0f, (SLO, Absolute, 6, false));
-        op_args_table.insert(0x1f, (SLO, AbsoluteX, 7, false));
-        op_args_table.insert(0x1b, (SLO, AbsoluteY, 7, false));
-        op_args_table.insert(0x03, (SLO, IndexedIndirect, 8, false));
-        op_args_table.insert(0x13, (SLO, IndirectIndexed, 8, false));
-        op_args_table.insert(0x47, (SRE, ZeroPage, 5, false));
-        op_args_table.insert(0x57, (SRE, ZeroPageX, 6, false));
-        op_args_table.insert(0x4f, (SRE, Absolute, 6, false));
-        op_args_table.insert(0x5f, (SRE, AbsoluteX, 7, false));
-        op_args_table.insert(0x5b, (SRE, AbsoluteY, 7, false));
-        op_args_table.insert(0x43, (SRE, IndexedIndirect, 8, false));
-        op_args_table.insert(0x53, (SRE, IndirectIndexed, 8, false));
-        op_args_table.insert(0x80, (SKB, Immediate, 2, false));
-        op_args_table.insert(0x82, (SKB, Immediate, 2, false));
-        op_args_table.insert(0x89, (SKB, Immediate, 2, false));
-        op_args_table.insert(0xc2, (SKB, Immediate, 2, false));
-        op_args_table.insert(0xe2, (SKB, Immediate, 2, false));
-        op_args_table.insert(0x0c, (IGN, Absolute, 4, false));
-        op_args_table.insert(0x1c, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0x3c, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0x5c, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0x7c, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0xdc, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0xfc, (IGN, AbsoluteX, 4, true));
-        op_args_table.insert(0x04, (IGN, ZeroPage, 3, false));
-        op_args_table.insert(0x44, (IGN, ZeroPage, 3, false));
-        op_args_table.insert(0x64, (IGN, ZeroPage, 3, false));
-        op_args_table.insert(0x14, (IGN, ZeroPageX, 4, false));
-        op_args_table.insert(0x34, (IGN, ZeroPageX, 4, false));
-        op_args_table.insert(0x54, (IGN, ZeroPageX, 4, false));
-        op_args_table.insert(0x74, (IGN, ZeroPageX, 4, false));
-        op_args_table.insert(0xd4, (IGN, ZeroPageX, 4, false));
-        op_args_table.insert(0xf4, (IGN, ZeroPageX, 4, false));
+    complete(table)
+};
 
-        let mut op_table: HashMap<u8, Op> = HashMap::new();
-        for (opcode, (instruction, mode, cycles, increase_cycle_when_cross_page)) in op_args_table {
-            op_table.insert(
-                opcode,
-                Op {
-                    instruction,
-                    mode,
-                    cycles,
-                    increase_cycle_when_cross_page,
-                },
-            );
+/// 65C02 的译码表：保留 NMOS 的官方指令，替换全部非官方操作码
+const CMOS_OP_TABLE: [Op; 256] = {
+    use AddressingMode::*;
+    use InstructionEnum::*;
+    let mut table: [Option<Op>; 256] = [None; 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        let nmos = NMOS_OP_TABLE[opcode];
+        if !is_unofficial(opcode as u8, nmos.instruction) {
+            table[opcode] = Some(nmos);
         }
+        opcode += 1;
+    }
 
-        Self { op_table }
+    // 修正了页边界错误的间接跳转多 1 个周期
+    table[0x6c] = Some(op(JMP, Indirect, 6, false));
+    // 移位指令的 abs,X 不跨页时省去修正周期，INC/DEC 仍为 7 个周期
+    table[0x1e] = Some(op(ASL, AbsoluteX, 6, true));
+    table[0x3e] = Some(op(ROL, AbsoluteX, 6, true));
+    table[0x5e] = Some(op(LSR, AbsoluteX, 6, true));
+    table[0x7e] = Some(op(ROR, AbsoluteX, 6, true));
+
+    table[0x80] = Some(op(BRA, Relative, 3, true));
+    table[0x64] = Some(op(STZ, ZeroPage, 3, false));
+    table[0x74] = Some(op(STZ, ZeroPageX, 4, false));
+    table[0x9c] = Some(op(STZ, Absolute, 4, false));
+    table[0x9e] = Some(op(STZ, AbsoluteX, 5, false));
+    table[0xda] = Some(op(PHX, Implied, 3, false));
+    table[0x5a] = Some(op(PHY, Implied, 3, false));
+    table[0xfa] = Some(op(PLX, Implied, 4, false));
+    table[0x7a] = Some(op(PLY, Implied, 4, false));
+    table[0x04] = Some(op(TSB, ZeroPage, 5, false));
+    table[0x0c] = Some(op(TSB, Absolute, 6, false));
+    table[0x14] = Some(op(TRB, ZeroPage, 5, false));
+    table[0x1c] = Some(op(TRB, Absolute, 6, false));
+    table[0x89] = Some(op(BIT, Immediate, 2, false));
+    table[0x34] = Some(op(BIT, ZeroPageX, 4, false));
+    table[0x3c] = Some(op(BIT, AbsoluteX, 4, true));
+    table[0x1a] = Some(op(INC, Accumulator, 2, false));
+    table[0x3a] = Some(op(DEC, Accumulator, 2, false));
+    table[0x7c] = Some(op(JMP, AbsoluteIndexedIndirect, 6, false));
+    table[0x12] = Some(op(ORA, ZeroPageIndirect, 5, false));
+    table[0x32] = Some(op(AND, ZeroPageIndirect, 5, false));
+    table[0x52] = Some(op(EOR, ZeroPageIndirect, 5, false));
+    table[0x72] = Some(op(ADC, ZeroPageIndirect, 5, false));
+    table[0x92] = Some(op(STA, ZeroPageIndirect, 5, false));
+    table[0xb2] = Some(op(LDA, ZeroPageIndirect, 5, false));
+    table[0xd2] = Some(op(CMP, ZeroPageIndirect, 5, false));
+    table[0xf2] = Some(op(SBC, ZeroPageIndirect, 5, false));
+
+    // RMBn/SMBn/BBRn/BBSn 的位号 n 在操作码的第 4-6 位
+    let rmb = [RMB0, RMB1, RMB2, RMB3, RMB4, RMB5, RMB6, RMB7];
+    let smb = [SMB0, SMB1, SMB2, SMB3, SMB4, SMB5, SMB6, SMB7];
+    let bbr = [BBR0, BBR1, BBR2, BBR3, BBR4, BBR5, BBR6, BBR7];
+    let bbs = [BBS0, BBS1, BBS2, BBS3, BBS4, BBS5, BBS6, BBS7];
+    let mut bit = 0;
+    while bit < 8 {
+        table[0x07 | bit << 4] = Some(op(rmb[bit], ZeroPage, 5, false));
+        table[0x87 | bit << 4] = Some(op(smb[bit], ZeroPage, 5, false));
+        table[0x0f | bit << 4] = Some(op(bbr[bit], ZeroPageRelative, 5, true));
+        table[0x8f | bit << 4] = Some(op(bbs[bit], ZeroPageRelative, 5, true));
+        bit += 1;
     }
 
-    fn get_op(&self, opcode: u8) -> Op {
-        self.op_table.get(&opcode).cloned().unwrap_or_else(|| {
-            panic!(
-                "Invalid opcode: {:#04x}. This opcode is not implemented or is illegal.",
-                opcode
-            )
-        })
+    // 其余未定义的操作码都是 NOP，长度和周期数各不相同
+    let mut high = 0;
+    while high < 16 {
+        table[high << 4 | 0x03] = Some(op(NOP, Implied, 1, false));
+        table[high << 4 | 0x0b] = Some(op(NOP, Implied, 1, false));
+        high += 1;
+    }
+    let immediate_nops = [0x02, 0x22, 0x42, 0x62, 0x82, 0xc2, 0xe2];
+    let mut i = 0;
+    while i < immediate_nops.len() {
+        table[immediate_nops[i]] = Some(op(NOP, Immediate, 2, false));
+        i += 1;
     }
+    table[0x44] = Some(op(NOP, ZeroPage, 3, false));
+    table[0x54] = Some(op(NOP, ZeroPageX, 4, false));
+    table[0xd4] = Some(op(NOP, ZeroPageX, 4, false));
+    table[0xf4] = Some(op(NOP, ZeroPageX, 4, false));
+    table[0xdc] = Some(op(NOP, Absolute, 4, false));
+    table[0xfc] = Some(op(NOP, Absolute, 4, false));
+    table[0x5c] = Some(op(NOP, Absolute, 8, false));
+
+    complete(table)
+};
+
+static OP_TABLE: [Op; 256] = NMOS_OP_TABLE;
+static OP_TABLE_65C02: [Op; 256] = CMOS_OP_TABLE;
+
+/// 每个操作码都必须有定义，遗漏会在编译期报错
+const fn complete(table: [Option<Op>; 256]) -> [Op; 256] {
+    let mut op_table = [op(InstructionEnum::JAM, AddressingMode::Implied, 2, false); 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        op_table[opcode] = match table[opcode] {
+            Some(entry) => entry,
+            None => panic!("opcode table is incomplete"),
+        };
+        opcode += 1;
+    }
+    op_table
 }
 
-// 全局单例的OpcodeManager
-static OPCODE_MANAGER: LazyLock<OpcodeManager> = LazyLock::new(OpcodeManager::new);
+/// 按 CPU 型号选择译码表，2A03 与 NMOS 6502 的操作码完全相同
+pub(crate) fn op_table(variant: CpuVariant) -> &'static [Op; 256] {
+    match variant {
+        CpuVariant::Ricoh2A03 | CpuVariant::Nmos6502 => &OP_TABLE,
+        CpuVariant::Cmos65C02 => &OP_TABLE_65C02,
+    }
+}
 
-pub fn get_op(opcode: u8) -> Op {
-    OPCODE_MANAGER.get_op(opcode)
+/// NMOS 6502 的非官方操作码，官方 NOP 只有 $EA，$EB 是 SBC #imm 的非官方别名
+pub(crate) const fn is_unofficial(opcode: u8, instruction: InstructionEnum) -> bool {
+    use InstructionEnum::*;
+    // 枚举中 ALR..=LAS 连续排列，都是非官方指令
+    let index = instruction as u8;
+    (matches!(instruction, NOP) && opcode != 0xEA)
+        || opcode == 0xEB
+        || (index >= ALR as u8 && index <= LAS as u8)
+        || matches!(instruction, JAM)
 }
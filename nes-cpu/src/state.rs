@@ -1,14 +1,13 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::LazyLock};
+use std::{cell::RefCell, rc::Rc};
 
-use log::debug;
-use nes_base::{BusAdapter, Interrupt};
+use nes_base::BusAdapter;
 
-use crate::common::{AddressingMode, InstructionEnum, is_page_crossed};
-use crate::opcode::Op;
+use crate::common::{AddressingMode, CpuVariant, InstructionEnum};
+use crate::opcode::{Op, op_table};
 
 // 状态标志位
 #[derive(Debug, Clone, Copy)]
-enum StatusFlag {
+pub(crate) enum StatusFlag {
     Carry,
     Zero,
     InterruptDisable,
@@ -32,13 +31,42 @@ pub struct Context {
     pub reg_pc: u16,
     pub reg_status: u8,
 
+    /// 挂起周期 (如 OAM DMA)，期间 CPU 不执行指令
     pub remaining_cycles: u32,
-    pub data_address: u16,
     pub op: Option<Op>,
+
+    // 指令执行过程中的内部状态
+    /// 当前指令已执行的周期数，0 表示位于指令边界
+    pub cycle: u8,
+    /// 有效地址
+    pub data_address: u16,
+    /// 零页指针
+    pub pointer: u8,
+    /// 数据锁存
+    pub operand: u8,
+    /// 变址或分支是否跨页
+    pub page_crossed: bool,
+    /// 开始访问数据的周期，0 表示仍在寻址
+    pub access_cycle: u8,
+
+    // 中断输入
+    /// 检测到 NMI 下降沿后锁存，直到进入响应序列
+    pub nmi_pending: bool,
+    /// 电平触发的 IRQ 线，由外设自行应答后释放
+    pub irq_line: bool,
+    /// 执行 JAM 后停机，只能复位
+    pub jammed: bool,
+    /// 已响应的 NMI 次数
+    pub nmi_count: u32,
+
+    // CPU 型号及其译码表、运算函数表，构造时选定
+    pub variant: CpuVariant,
+    pub op_table: &'static [Op; 256],
+    pub instruction_table: &'static [InstructionFn; INSTRUCTION_COUNT],
 }
 
 impl Context {
-    pub fn new() -> Self {
+    pub fn new(variant: CpuVariant) -> Self {
         Self {
             bus: None,
             reg_a: 0,
@@ -48,8 +76,20 @@ impl Context {
             reg_pc: 0xFFFC,
             reg_status: 0x24, // Unused 和 Break flags set
             remaining_cycles: 7,
-            data_address: 0,
             op: None,
+            cycle: 0,
+            data_address: 0,
+            pointer: 0,
+            operand: 0,
+            page_crossed: false,
+            access_cycle: 0,
+            nmi_pending: false,
+            irq_line: false,
+            jammed: false,
+            nmi_count: 0,
+            variant,
+            op_table: op_table(variant),
+            instruction_table: instruction_table(variant),
         }
     }
 }
@@ -108,7 +148,7 @@ impl Context {
         self.bus.as_ref().unwrap().borrow().read(addr)
     }
 
-    fn write_bus_8bit(&self, addr: u16, value: u8) {
+    pub fn write_bus_8bit(&self, addr: u16, value: u8) {
         if self.bus.is_none() {
             panic!("Bus is not attached to the context");
         }
@@ -122,658 +162,780 @@ impl Context {
         self.bus.as_ref().unwrap().borrow().read_u16(addr)
     }
 
-    // 栈操作
-    fn push_stack(&mut self, value: u8) {
+    /// 读取 PC 处的字节并递增 PC
+    pub fn fetch_pc(&mut self) -> u8 {
+        let value = self.read_bus_8bit(self.reg_pc);
+        self.reg_pc = self.reg_pc.wrapping_add(1);
+        value
+    }
+
+    // 栈操作，每次调用对应一个总线周期
+    pub fn push_stack(&mut self, value: u8) {
         self.write_bus_8bit(0x0100 + self.reg_sp as u16, value);
         self.reg_sp = self.reg_sp.wrapping_sub(1);
     }
 
-    fn pop_stack(&mut self) -> u8 {
+    pub fn pop_stack(&mut self) -> u8 {
         self.reg_sp = self.reg_sp.wrapping_add(1);
         self.read_bus_8bit(0x0100 + self.reg_sp as u16)
     }
 
-    fn push_stack_16bit(&mut self, value: u16) {
-        self.push_stack((value >> 8) as u8);
-        self.push_stack(value as u8);
-    }
-
-    fn pop_stack_16bit(&mut self) -> u16 {
-        let lo = self.pop_stack() as u16;
-        let hi = self.pop_stack() as u16;
-        (hi << 8) | lo
+    /// 读取栈顶 (不移动栈指针)
+    pub fn peek_stack(&self) -> u8 {
+        self.read_bus_8bit(0x0100 + self.reg_sp as u16)
     }
 
     // 状态寄存器操作
-    fn get_status_flag(&self, flag: StatusFlag) -> bool {
+    pub(crate) fn get_status_flag(&self, flag: StatusFlag) -> bool {
         get_status_flag(self.reg_status, flag)
     }
 
-    fn set_status_flag(&mut self, flag: StatusFlag, value: bool) {
+    pub(crate) fn set_status_flag(&mut self, flag: StatusFlag, value: bool) {
         self.reg_status = set_status_flag(self.reg_status, flag, value);
     }
 
-    pub fn get_op_mode(&self) -> AddressingMode {
-        self.op.unwrap().mode
+    pub(crate) fn set_zero_negative(&mut self, value: u8) {
+        self.set_status_flag(StatusFlag::Zero, value == 0);
+        self.set_status_flag(StatusFlag::Negative, get_bit(value, 7));
     }
-}
 
-// 分支成功辅助函数
-fn branch_success(ctx: &mut Context) {
-    if is_page_crossed(ctx.data_address, ctx.reg_pc + 1) {
-        ctx.remaining_cycles += 2;
-    } else {
-        ctx.remaining_cycles += 1;
+    /// 从栈中恢复状态寄存器，B 与 Unused 位保持不变 (PLP/RTI)
+    pub fn restore_status(&mut self, status: u8) {
+        let status = set_status_flag(
+            status,
+            StatusFlag::BreakCommand,
+            self.get_status_flag(StatusFlag::BreakCommand),
+        );
+        self.reg_status = set_status_flag(
+            status,
+            StatusFlag::Unused,
+            self.get_status_flag(StatusFlag::Unused),
+        );
+    }
+
+    /// 当前周期结束时是否有需要响应的中断，IRQ 受 I 标志屏蔽
+    pub fn interrupt_requested(&self) -> bool {
+        self.nmi_pending || (self.irq_line && !self.get_status_flag(StatusFlag::InterruptDisable))
     }
-    ctx.reg_pc = ctx.data_address;
+
+    /// 分支指令的跳转条件
+    pub fn branch_taken(&self, instruction: InstructionEnum) -> bool {
+        match instruction {
+            InstructionEnum::BCC => !self.get_status_flag(StatusFlag::Carry),
+            InstructionEnum::BCS => self.get_status_flag(StatusFlag::Carry),
+            InstructionEnum::BEQ => self.get_status_flag(StatusFlag::Zero),
+            InstructionEnum::BMI => self.get_status_flag(StatusFlag::Negative),
+            InstructionEnum::BNE => !self.get_status_flag(StatusFlag::Zero),
+            InstructionEnum::BPL => !self.get_status_flag(StatusFlag::Negative),
+            InstructionEnum::BVC => !self.get_status_flag(StatusFlag::Overflow),
+            InstructionEnum::BVS => self.get_status_flag(StatusFlag::Overflow),
+            InstructionEnum::BRA => true,
+            _ => false,
+        }
+    }
+}
+
+// 指令的运算部分，总线访问由 cycle 模块按周期完成
+// 读指令: value 为读到的数据；写指令: 返回要写入的数据；
+// 读改写指令: 返回修改后的数据；隐含寻址指令忽略 value
+
+fn instruction_adc(ctx: &mut Context, value: u8) -> u8 {
+    add_binary(ctx, value);
+    value
 }
 
-fn instruction_adc(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let tmp = ctx.reg_a as u16 + fetched as u16 + ctx.get_status_flag(StatusFlag::Carry) as u16;
+fn add_binary(ctx: &mut Context, value: u8) {
+    let tmp = ctx.reg_a as u16 + value as u16 + ctx.get_status_flag(StatusFlag::Carry) as u16;
 
     ctx.set_status_flag(
         StatusFlag::Overflow,
-        ((tmp as u8 ^ ctx.reg_a) & 0x80 != 0) && ((fetched ^ tmp as u8) & 0x80 != 0),
+        ((tmp as u8 ^ ctx.reg_a) & 0x80 != 0) && ((value ^ tmp as u8) & 0x80 != 0),
     );
     ctx.set_status_flag(StatusFlag::Carry, tmp > 0xFF);
-    ctx.set_status_flag(StatusFlag::Zero, (tmp as u8) == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp as u8, 7));
-
     ctx.reg_a = tmp as u8;
+    ctx.set_zero_negative(ctx.reg_a);
 }
 
-fn instruction_and(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_a &= fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_and(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a &= value;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_asl(ctx: &mut Context) {
-    let tmp = if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a
-    } else {
-        ctx.read_bus_8bit(ctx.data_address)
-    };
-    ctx.set_status_flag(StatusFlag::Carry, get_bit(tmp, 7));
-    let tmp = tmp << 1;
-    if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a = tmp;
-    } else {
-        ctx.write_bus_8bit(ctx.data_address, tmp);
-    }
-    ctx.set_status_flag(StatusFlag::Zero, tmp == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_asl(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(value, 7));
+    let result = value << 1;
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_bit(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let test = fetched & ctx.reg_a;
-    ctx.set_status_flag(StatusFlag::Zero, test == 0);
-    ctx.set_status_flag(StatusFlag::Overflow, get_bit(fetched, 6));
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(fetched, 7));
+fn instruction_bit(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Zero, value & ctx.reg_a == 0);
+    ctx.set_status_flag(StatusFlag::Overflow, get_bit(value, 6));
+    ctx.set_status_flag(StatusFlag::Negative, get_bit(value, 7));
+    value
 }
 
-fn instruction_bcc(ctx: &mut Context) {
-    if !ctx.get_status_flag(StatusFlag::Carry) {
-        branch_success(ctx);
-    }
+fn instruction_clc(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Carry, false);
+    value
 }
 
-fn instruction_bcs(ctx: &mut Context) {
-    if ctx.get_status_flag(StatusFlag::Carry) {
-        branch_success(ctx);
-    }
+fn instruction_cld(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::DecimalMode, false);
+    value
 }
 
-fn instruction_beq(ctx: &mut Context) {
-    if ctx.get_status_flag(StatusFlag::Zero) {
-        branch_success(ctx);
-    }
+fn instruction_cli(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::InterruptDisable, false);
+    value
 }
 
-fn instruction_bmi(ctx: &mut Context) {
-    if ctx.get_status_flag(StatusFlag::Negative) {
-        branch_success(ctx);
-    }
+fn instruction_clv(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Overflow, false);
+    value
 }
 
-fn instruction_bne(ctx: &mut Context) {
-    if !ctx.get_status_flag(StatusFlag::Zero) {
-        branch_success(ctx);
-    }
+fn compare(ctx: &mut Context, register: u8, value: u8) {
+    ctx.set_status_flag(StatusFlag::Carry, register >= value);
+    ctx.set_zero_negative(register.wrapping_sub(value));
 }
 
-fn instruction_bpl(ctx: &mut Context) {
-    if !ctx.get_status_flag(StatusFlag::Negative) {
-        branch_success(ctx);
-    }
+fn instruction_cmp(ctx: &mut Context, value: u8) -> u8 {
+    compare(ctx, ctx.reg_a, value);
+    value
 }
 
-fn instruction_bvc(ctx: &mut Context) {
-    if !ctx.get_status_flag(StatusFlag::Overflow) {
-        branch_success(ctx);
-    }
+fn instruction_cpx(ctx: &mut Context, value: u8) -> u8 {
+    compare(ctx, ctx.reg_x, value);
+    value
 }
 
-fn instruction_bvs(ctx: &mut Context) {
-    if ctx.get_status_flag(StatusFlag::Overflow) {
-        branch_success(ctx);
-    }
+fn instruction_cpy(ctx: &mut Context, value: u8) -> u8 {
+    compare(ctx, ctx.reg_y, value);
+    value
 }
 
-fn instruction_brk(ctx: &mut Context) {
-    ctx.push_stack_16bit(ctx.reg_pc + 1);
-    ctx.push_stack(ctx.reg_status);
-    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
-    ctx.set_status_flag(StatusFlag::BreakCommand, true);
-    ctx.reg_pc = ctx.read_bus_16bit(0xFFFE);
+fn instruction_dec(ctx: &mut Context, value: u8) -> u8 {
+    let result = value.wrapping_sub(1);
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_clc(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::Carry, false);
+fn instruction_dex(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = ctx.reg_x.wrapping_sub(1);
+    ctx.set_zero_negative(ctx.reg_x);
+    value
 }
 
-fn instruction_cld(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::DecimalMode, false);
+fn instruction_dey(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_y = ctx.reg_y.wrapping_sub(1);
+    ctx.set_zero_negative(ctx.reg_y);
+    value
 }
 
-fn instruction_cli(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::InterruptDisable, false);
+fn instruction_eor(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a ^= value;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_clv(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::Overflow, false);
+fn instruction_inc(ctx: &mut Context, value: u8) -> u8 {
+    let result = value.wrapping_add(1);
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_cmp(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let tmp = ctx.reg_a.wrapping_sub(fetched);
-    ctx.set_status_flag(StatusFlag::Carry, ctx.reg_a >= fetched);
-    ctx.set_status_flag(StatusFlag::Zero, tmp == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_inx(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = ctx.reg_x.wrapping_add(1);
+    ctx.set_zero_negative(ctx.reg_x);
+    value
 }
 
-fn instruction_cpx(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let tmp = ctx.reg_x.wrapping_sub(fetched);
-    ctx.set_status_flag(StatusFlag::Carry, ctx.reg_x >= fetched);
-    ctx.set_status_flag(StatusFlag::Zero, tmp == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_iny(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_y = ctx.reg_y.wrapping_add(1);
+    ctx.set_zero_negative(ctx.reg_y);
+    value
 }
 
-fn instruction_cpy(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let tmp = ctx.reg_y.wrapping_sub(fetched);
-    ctx.set_status_flag(StatusFlag::Carry, ctx.reg_y >= fetched);
-    ctx.set_status_flag(StatusFlag::Zero, tmp == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_lda(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a = value;
+    ctx.set_zero_negative(value);
+    value
 }
 
-fn instruction_dec(ctx: &mut Context) {
-    let mut fetched = ctx.read_bus_8bit(ctx.data_address);
-    fetched = fetched.wrapping_sub(1);
-    ctx.write_bus_8bit(ctx.data_address, fetched);
-    ctx.set_status_flag(StatusFlag::Zero, fetched == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(fetched, 7));
+fn instruction_ldx(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = value;
+    ctx.set_zero_negative(value);
+    value
 }
 
-fn instruction_dex(ctx: &mut Context) {
-    ctx.reg_x = ctx.reg_x.wrapping_sub(1);
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_ldy(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_y = value;
+    ctx.set_zero_negative(value);
+    value
 }
 
-fn instruction_dey(ctx: &mut Context) {
-    ctx.reg_y = ctx.reg_y.wrapping_sub(1);
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_y == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_y, 7));
+/// 逻辑右移指令，将目标操作数右移，并将最低位移入进位标志位。
+fn instruction_lsr(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(value, 0));
+    let result = value >> 1;
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_eor(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_a ^= fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_ora(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a |= value;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_inc(ctx: &mut Context) {
-    let mut fetched = ctx.read_bus_8bit(ctx.data_address);
-    fetched = fetched.wrapping_add(1);
-    ctx.write_bus_8bit(ctx.data_address, fetched);
-    ctx.set_status_flag(StatusFlag::Zero, fetched == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(fetched, 7));
+fn instruction_rol(ctx: &mut Context, value: u8) -> u8 {
+    let old_carry = ctx.get_status_flag(StatusFlag::Carry);
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(value, 7));
+    let result = (value << 1) | old_carry as u8;
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_inx(ctx: &mut Context) {
-    ctx.reg_x = ctx.reg_x.wrapping_add(1);
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_ror(ctx: &mut Context, value: u8) -> u8 {
+    let old_carry = ctx.get_status_flag(StatusFlag::Carry);
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(value, 0));
+    let result = (value >> 1) | ((old_carry as u8) << 7);
+    ctx.set_zero_negative(result);
+    result
 }
 
-fn instruction_iny(ctx: &mut Context) {
-    ctx.reg_y = ctx.reg_y.wrapping_add(1);
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_y == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_y, 7));
+fn instruction_sbc(ctx: &mut Context, value: u8) -> u8 {
+    subtract_binary(ctx, value);
+    value
 }
 
-fn instruction_jmp(ctx: &mut Context) {
-    ctx.reg_pc = ctx.data_address;
+fn subtract_binary(ctx: &mut Context, value: u8) {
+    let tmp = ctx.reg_a as i16 - value as i16 - (1 - ctx.get_status_flag(StatusFlag::Carry) as i16);
+    ctx.set_status_flag(
+        StatusFlag::Overflow,
+        ((tmp as u8 ^ ctx.reg_a) & 0x80 != 0) && ((ctx.reg_a ^ value) & 0x80 != 0),
+    );
+    ctx.set_status_flag(StatusFlag::Carry, tmp >= 0);
+    ctx.reg_a = tmp as u8;
+    ctx.set_zero_negative(ctx.reg_a);
 }
 
-fn instruction_jsr(ctx: &mut Context) {
-    ctx.push_stack_16bit(ctx.reg_pc - 1);
-    ctx.reg_pc = ctx.data_address;
+fn instruction_sec(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Carry, true);
+    value
 }
 
-fn instruction_lda(ctx: &mut Context) {
-    debug!("LDA: mode={:?}", ctx.get_op_mode());
-    debug!("LDA: data_address={:#04x}", ctx.data_address);
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    debug!("LDA: fetched={:#04x}", fetched);
-    ctx.reg_a = fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_sed(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::DecimalMode, true);
+    value
 }
 
-fn instruction_ldx(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_x = fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_sei(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
+    value
 }
 
-fn instruction_ldy(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_y = fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_y == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_y, 7));
+fn instruction_sta(ctx: &mut Context, _: u8) -> u8 {
+    ctx.reg_a
 }
 
-/// 逻辑右移指令，将目标操作数右移，并将最低位移入进位标志位。
-fn instruction_lsr(ctx: &mut Context) {
-    debug!("LSR: mode={:?}", ctx.get_op_mode());
-    let tmp = if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a
-    } else {
-        ctx.read_bus_8bit(ctx.data_address)
-    };
-    debug!("LSR: tmp={:#04x}", tmp);
-    ctx.set_status_flag(StatusFlag::Carry, get_bit(tmp, 0));
-    let tmp = tmp >> 1;
-    if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a = tmp;
-    } else {
-        ctx.write_bus_8bit(ctx.data_address, tmp);
-    }
-    ctx.set_status_flag(StatusFlag::Zero, tmp == 0);
-    ctx.set_status_flag(StatusFlag::Negative, false);
+fn instruction_stx(ctx: &mut Context, _: u8) -> u8 {
+    ctx.reg_x
 }
 
-fn instruction_ora(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_a |= fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_sty(ctx: &mut Context, _: u8) -> u8 {
+    ctx.reg_y
 }
 
-fn instruction_pha(ctx: &mut Context) {
-    ctx.push_stack(ctx.reg_a);
+fn instruction_tax(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = ctx.reg_a;
+    ctx.set_zero_negative(ctx.reg_x);
+    value
 }
 
-fn instruction_php(ctx: &mut Context) {
-    ctx.push_stack(ctx.reg_status | 0x30);
+fn instruction_tay(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_y = ctx.reg_a;
+    ctx.set_zero_negative(ctx.reg_y);
+    value
 }
 
-fn instruction_pla(ctx: &mut Context) {
-    ctx.reg_a = ctx.pop_stack();
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_tsx(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = ctx.reg_sp;
+    ctx.set_zero_negative(ctx.reg_x);
+    value
 }
 
-fn instruction_plp(ctx: &mut Context) {
-    let status = ctx.pop_stack();
-    let status = set_status_flag(
-        status,
-        StatusFlag::BreakCommand,
-        ctx.get_status_flag(StatusFlag::BreakCommand),
-    );
-    let status = set_status_flag(
-        status,
-        StatusFlag::Unused,
-        ctx.get_status_flag(StatusFlag::Unused),
-    );
-    ctx.reg_status = status;
+fn instruction_txa(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a = ctx.reg_x;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_rol(ctx: &mut Context) {
-    let tmp = if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a
-    } else {
-        ctx.read_bus_8bit(ctx.data_address)
-    };
-    let old_carry = ctx.get_status_flag(StatusFlag::Carry);
-    ctx.set_status_flag(StatusFlag::Carry, get_bit(tmp, 7));
-    let tmp = (tmp << 1) | old_carry as u8;
-    if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a = tmp;
-        ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    } else {
-        ctx.write_bus_8bit(ctx.data_address, tmp);
-    }
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_txs(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_sp = ctx.reg_x;
+    value
 }
 
-fn instruction_ror(ctx: &mut Context) {
-    let tmp = if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a
-    } else {
-        ctx.read_bus_8bit(ctx.data_address)
-    };
-    let old_carry = ctx.get_status_flag(StatusFlag::Carry);
-    ctx.set_status_flag(StatusFlag::Carry, get_bit(tmp, 0));
-    let tmp = (tmp >> 1) | ((old_carry as u8) << 7);
-    if ctx.get_op_mode() == AddressingMode::Accumulator {
-        ctx.reg_a = tmp;
-        ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    } else {
-        ctx.write_bus_8bit(ctx.data_address, tmp);
-    }
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp, 7));
+fn instruction_tya(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a = ctx.reg_y;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_rti(ctx: &mut Context) {
-    let status = ctx.pop_stack();
-    let status = set_status_flag(
-        status,
-        StatusFlag::BreakCommand,
-        ctx.get_status_flag(StatusFlag::BreakCommand),
-    );
-    let status = set_status_flag(
-        status,
-        StatusFlag::Unused,
-        ctx.get_status_flag(StatusFlag::Unused),
-    );
-    ctx.reg_status = status;
-    ctx.reg_pc = ctx.pop_stack_16bit();
+// 非法/复合指令
+fn instruction_alr(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a &= value;
+    ctx.reg_a = instruction_lsr(ctx, ctx.reg_a);
+    value
 }
 
-fn instruction_rts(ctx: &mut Context) {
-    ctx.reg_pc = ctx.pop_stack_16bit() + 1;
+fn instruction_anc(ctx: &mut Context, value: u8) -> u8 {
+    instruction_and(ctx, value);
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(ctx.reg_a, 7));
+    value
 }
 
-fn instruction_sbc(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    let tmp =
-        ctx.reg_a as i16 - fetched as i16 - (1 - ctx.get_status_flag(StatusFlag::Carry) as i16);
+/// AND 后循环右移 A，C 取结果第 6 位，V 取第 6 位与第 5 位的异或
+fn instruction_arr(ctx: &mut Context, value: u8) -> u8 {
+    let old_carry = ctx.get_status_flag(StatusFlag::Carry);
+    ctx.reg_a = ((ctx.reg_a & value) >> 1) | ((old_carry as u8) << 7);
+    ctx.set_zero_negative(ctx.reg_a);
+    ctx.set_status_flag(StatusFlag::Carry, get_bit(ctx.reg_a, 6));
     ctx.set_status_flag(
         StatusFlag::Overflow,
-        ((tmp as u8 ^ ctx.reg_a) & 0x80 != 0) && ((ctx.reg_a ^ fetched) & 0x80 != 0),
+        get_bit(ctx.reg_a, 6) ^ get_bit(ctx.reg_a, 5),
     );
-    ctx.set_status_flag(StatusFlag::Carry, tmp >= 0);
-    ctx.set_status_flag(StatusFlag::Zero, (tmp as u8) == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(tmp as u8, 7));
-    ctx.reg_a = tmp as u8;
+    value
 }
 
-fn instruction_sec(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::Carry, true);
+/// X = (A & X) - value，进位与 CMP 相同
+fn instruction_axs(ctx: &mut Context, value: u8) -> u8 {
+    let tmp = ctx.reg_a & ctx.reg_x;
+    compare(ctx, tmp, value);
+    ctx.reg_x = tmp.wrapping_sub(value);
+    value
 }
 
-fn instruction_sed(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::DecimalMode, true);
+fn instruction_lax(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_x = value;
+    instruction_lda(ctx, value)
 }
 
-fn instruction_sei(ctx: &mut Context) {
-    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
+fn instruction_sax(ctx: &mut Context, _: u8) -> u8 {
+    ctx.reg_x & ctx.reg_a
 }
 
-fn instruction_sta(ctx: &mut Context) {
-    ctx.write_bus_8bit(ctx.data_address, ctx.reg_a);
+fn instruction_dcp(ctx: &mut Context, value: u8) -> u8 {
+    let result = value.wrapping_sub(1);
+    instruction_cmp(ctx, result);
+    result
 }
 
-fn instruction_stx(ctx: &mut Context) {
-    ctx.write_bus_8bit(ctx.data_address, ctx.reg_x);
+fn instruction_isc(ctx: &mut Context, value: u8) -> u8 {
+    let result = value.wrapping_add(1);
+    instruction_sbc(ctx, result);
+    result
 }
 
-fn instruction_sty(ctx: &mut Context) {
-    ctx.write_bus_8bit(ctx.data_address, ctx.reg_y);
+fn instruction_rla(ctx: &mut Context, value: u8) -> u8 {
+    let result = instruction_rol(ctx, value);
+    instruction_and(ctx, result);
+    result
 }
 
-fn instruction_tax(ctx: &mut Context) {
-    ctx.reg_x = ctx.reg_a;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_rra(ctx: &mut Context, value: u8) -> u8 {
+    let result = instruction_ror(ctx, value);
+    instruction_adc(ctx, result);
+    result
 }
 
-fn instruction_tay(ctx: &mut Context) {
-    ctx.reg_y = ctx.reg_a;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_y == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_y, 7));
+fn instruction_slo(ctx: &mut Context, value: u8) -> u8 {
+    let result = instruction_asl(ctx, value);
+    instruction_ora(ctx, result);
+    result
 }
 
-fn instruction_tsx(ctx: &mut Context) {
-    ctx.reg_x = ctx.reg_sp;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_sre(ctx: &mut Context, value: u8) -> u8 {
+    let result = instruction_lsr(ctx, value);
+    instruction_eor(ctx, result);
+    result
 }
 
-fn instruction_txa(ctx: &mut Context) {
-    ctx.reg_a = ctx.reg_x;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
-}
+// 不稳定的非法指令，行为参考 NES (2A03) 上的实测结果
+// see: https://www.nesdev.org/wiki/CPU_unofficial_opcodes
 
-fn instruction_txs(ctx: &mut Context) {
-    ctx.reg_sp = ctx.reg_x;
-}
+/// XAA/LXA 中与 A 进行或运算的常数，随芯片和温度变化，这里取常见的 0xEE
+const UNSTABLE_MAGIC: u8 = 0xEE;
 
-fn instruction_tya(ctx: &mut Context) {
-    ctx.reg_a = ctx.reg_y;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_xaa(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a = (ctx.reg_a | UNSTABLE_MAGIC) & ctx.reg_x & value;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-// 非法/复合指令
-fn instruction_alr(ctx: &mut Context) {
-    instruction_and(ctx);
-    instruction_lsr(ctx);
+fn instruction_lxa(ctx: &mut Context, value: u8) -> u8 {
+    ctx.reg_a = (ctx.reg_a | UNSTABLE_MAGIC) & value;
+    ctx.reg_x = ctx.reg_a;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
+}
+
+fn instruction_las(ctx: &mut Context, value: u8) -> u8 {
+    let result = value & ctx.reg_sp;
+    ctx.reg_a = result;
+    ctx.reg_x = result;
+    ctx.reg_sp = result;
+    ctx.set_zero_negative(result);
+    value
+}
+
+/// SHA/SHX/SHY/TAS 写入 register & (基址高字节 + 1)，
+/// 变址跨页时写入的值同时替换目标地址的高字节
+fn store_high_and(ctx: &mut Context, register: u8) -> u8 {
+    let high = (ctx.data_address >> 8) as u8;
+    let base_high = high.wrapping_sub(ctx.page_crossed as u8);
+    let value = register & base_high.wrapping_add(1);
+    if ctx.page_crossed {
+        ctx.data_address = (value as u16) << 8 | (ctx.data_address & 0x00FF);
+    }
+    value
 }
 
-fn instruction_anc(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_a &= fetched;
-    ctx.set_status_flag(StatusFlag::Carry, get_bit(ctx.reg_a, 7));
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_ahx(ctx: &mut Context, _: u8) -> u8 {
+    store_high_and(ctx, ctx.reg_a & ctx.reg_x)
 }
 
-fn instruction_arr(ctx: &mut Context) {
-    instruction_and(ctx);
-    instruction_ror(ctx);
+fn instruction_shx(ctx: &mut Context, _: u8) -> u8 {
+    store_high_and(ctx, ctx.reg_x)
 }
 
-fn instruction_axs(ctx: &mut Context) {
-    ctx.reg_x &= ctx.reg_a;
-    ctx.set_status_flag(StatusFlag::Carry, false);
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_x == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_x, 7));
+fn instruction_shy(ctx: &mut Context, _: u8) -> u8 {
+    store_high_and(ctx, ctx.reg_y)
 }
 
-fn instruction_lax(ctx: &mut Context) {
-    let fetched = ctx.read_bus_8bit(ctx.data_address);
-    ctx.reg_x = fetched;
-    ctx.reg_a = fetched;
-    ctx.set_status_flag(StatusFlag::Zero, ctx.reg_a == 0);
-    ctx.set_status_flag(StatusFlag::Negative, get_bit(ctx.reg_a, 7));
+fn instruction_tas(ctx: &mut Context, _: u8) -> u8 {
+    ctx.reg_sp = ctx.reg_a & ctx.reg_x;
+    store_high_and(ctx, ctx.reg_sp)
 }
 
-fn instruction_sax(ctx: &mut Context) {
-    ctx.write_bus_8bit(ctx.data_address, ctx.reg_x & ctx.reg_a);
+fn instruction_jam(ctx: &mut Context, value: u8) -> u8 {
+    ctx.jammed = true;
+    value
 }
 
-fn instruction_dcp(ctx: &mut Context) {
-    instruction_dec(ctx);
-    instruction_cmp(ctx);
+// 十进制运算，2A03 去掉了十进制电路，只有 NMOS 6502 和 65C02 使用
+// see: http://www.6502.org/tutorials/decimal_mode.html
+
+/// 十进制加法，设置 C/V，返回 (结果, 修正高半字节前的中间结果)，N/Z 按型号另行设置
+fn add_decimal(ctx: &mut Context, value: u8) -> (u8, u8) {
+    let (a, v) = (ctx.reg_a as i16, value as i16);
+    let mut low = (a & 0x0F) + (v & 0x0F) + ctx.get_status_flag(StatusFlag::Carry) as i16;
+    if low >= 0x0A {
+        low = ((low + 0x06) & 0x0F) + 0x10;
+    }
+    let mut sum = (a & 0xF0) + (v & 0xF0) + low;
+    // V 取中间结果按有符号数计算是否溢出
+    let signed = (a & 0xF0) as u8 as i8 as i16 + (v & 0xF0) as u8 as i8 as i16 + low;
+    ctx.set_status_flag(StatusFlag::Overflow, !(-128..=127).contains(&signed));
+    let intermediate = sum as u8;
+    if sum >= 0xA0 {
+        sum += 0x60;
+    }
+    ctx.set_status_flag(StatusFlag::Carry, sum >= 0x100);
+    (sum as u8, intermediate)
 }
 
-fn instruction_isc(ctx: &mut Context) {
-    instruction_inc(ctx);
-    instruction_sbc(ctx);
+/// NMOS 6502 的 ADC：N 取自中间结果，Z 取自二进制加法的结果
+fn instruction_adc_nmos(ctx: &mut Context, value: u8) -> u8 {
+    if !ctx.get_status_flag(StatusFlag::DecimalMode) {
+        return instruction_adc(ctx, value);
+    }
+    let carry = ctx.get_status_flag(StatusFlag::Carry) as u8;
+    let binary = ctx.reg_a.wrapping_add(value).wrapping_add(carry);
+    let (result, intermediate) = add_decimal(ctx, value);
+    ctx.reg_a = result;
+    ctx.set_status_flag(StatusFlag::Zero, binary == 0);
+    ctx.set_status_flag(StatusFlag::Negative, get_bit(intermediate, 7));
+    value
+}
+
+/// 65C02 的 ADC：N/Z 取自十进制结果
+fn instruction_adc_cmos(ctx: &mut Context, value: u8) -> u8 {
+    if !ctx.get_status_flag(StatusFlag::DecimalMode) {
+        return instruction_adc(ctx, value);
+    }
+    let (result, _) = add_decimal(ctx, value);
+    ctx.reg_a = result;
+    ctx.set_zero_negative(result);
+    value
 }
 
-fn instruction_rla(ctx: &mut Context) {
-    instruction_rol(ctx);
-    instruction_and(ctx);
+/// NMOS 6502 的 SBC：标志位全部与二进制减法相同
+fn instruction_sbc_nmos(ctx: &mut Context, value: u8) -> u8 {
+    if !ctx.get_status_flag(StatusFlag::DecimalMode) {
+        return instruction_sbc(ctx, value);
+    }
+    let (a, v) = (ctx.reg_a as i16, value as i16);
+    let borrow = 1 - ctx.get_status_flag(StatusFlag::Carry) as i16;
+    let mut low = (a & 0x0F) - (v & 0x0F) - borrow;
+    if low < 0 {
+        low = ((low - 0x06) & 0x0F) - 0x10;
+    }
+    let mut result = (a & 0xF0) - (v & 0xF0) + low;
+    if result < 0 {
+        result -= 0x60;
+    }
+    subtract_binary(ctx, value);
+    ctx.reg_a = result as u8;
+    value
 }
 
-fn instruction_rra(ctx: &mut Context) {
-    instruction_ror(ctx);
-    instruction_adc(ctx);
+/// 65C02 的 SBC：C/V 与二进制减法相同，N/Z 取自十进制结果
+fn instruction_sbc_cmos(ctx: &mut Context, value: u8) -> u8 {
+    if !ctx.get_status_flag(StatusFlag::DecimalMode) {
+        return instruction_sbc(ctx, value);
+    }
+    let (a, v) = (ctx.reg_a as i16, value as i16);
+    let borrow = 1 - ctx.get_status_flag(StatusFlag::Carry) as i16;
+    let low = (a & 0x0F) - (v & 0x0F) - borrow;
+    let mut result = a - v - borrow;
+    if result < 0 {
+        result -= 0x60;
+    }
+    if low < 0 {
+        result -= 0x06;
+    }
+    subtract_binary(ctx, value);
+    ctx.reg_a = result as u8;
+    ctx.set_zero_negative(ctx.reg_a);
+    value
 }
 
-fn instruction_slo(ctx: &mut Context) {
-    instruction_asl(ctx);
-    instruction_ora(ctx);
+fn instruction_isc_nmos(ctx: &mut Context, value: u8) -> u8 {
+    let result = value.wrapping_add(1);
+    instruction_sbc_nmos(ctx, result);
+    result
 }
 
-fn instruction_sre(ctx: &mut Context) {
-    instruction_lsr(ctx);
-    instruction_eor(ctx);
+fn instruction_rra_nmos(ctx: &mut Context, value: u8) -> u8 {
+    let result = instruction_ror(ctx, value);
+    instruction_adc_nmos(ctx, result);
+    result
 }
 
-// NOP/IGN/SKB
-fn instruction_nop(_: &mut Context) {}
-fn instruction_skb(_: &mut Context) {}
-fn instruction_ign(_: &mut Context) {}
-
-struct InstructionManager {
-    instructions: HashMap<InstructionEnum, fn(&mut Context)>,
-}
-
-impl InstructionManager {
-    fn new() -> Self {
-        let mut m: HashMap<InstructionEnum, fn(&mut Context)> = HashMap::new();
-
-        m.insert(InstructionEnum::ADC, instruction_adc);
-        m.insert(InstructionEnum::AND, instruction_and);
-        m.insert(InstructionEnum::ASL, instruction_asl);
-        m.insert(InstructionEnum::BIT, instruction_bit);
-        m.insert(InstructionEnum::BCC, instruction_bcc);
-        m.insert(InstructionEnum::BCS, instruction_bcs);
-        m.insert(InstructionEnum::BEQ, instruction_beq);
-        m.insert(InstructionEnum::BMI, instruction_bmi);
-        m.insert(InstructionEnum::BNE, instruction_bne);
-        m.insert(InstructionEnum::BPL, instruction_bpl);
-        m.insert(InstructionEnum::BVC, instruction_bvc);
-        m.insert(InstructionEnum::BVS, instruction_bvs);
-        m.insert(InstructionEnum::BRK, instruction_brk);
-        m.insert(InstructionEnum::CLC, instruction_clc);
-        m.insert(InstructionEnum::CLD, instruction_cld);
-        m.insert(InstructionEnum::CLI, instruction_cli);
-        m.insert(InstructionEnum::CLV, instruction_clv);
-        m.insert(InstructionEnum::CMP, instruction_cmp);
-        m.insert(InstructionEnum::CPX, instruction_cpx);
-        m.insert(InstructionEnum::CPY, instruction_cpy);
-        m.insert(InstructionEnum::DEC, instruction_dec);
-        m.insert(InstructionEnum::DEX, instruction_dex);
-        m.insert(InstructionEnum::DEY, instruction_dey);
-        m.insert(InstructionEnum::EOR, instruction_eor);
-        m.insert(InstructionEnum::INC, instruction_inc);
-        m.insert(InstructionEnum::INX, instruction_inx);
-        m.insert(InstructionEnum::INY, instruction_iny);
-        m.insert(InstructionEnum::JMP, instruction_jmp);
-        m.insert(InstructionEnum::JSR, instruction_jsr);
-        m.insert(InstructionEnum::LDA, instruction_lda);
-        m.insert(InstructionEnum::LDX, instruction_ldx);
-        m.insert(InstructionEnum::LDY, instruction_ldy);
-        m.insert(InstructionEnum::LSR, instruction_lsr);
-        m.insert(InstructionEnum::ORA, instruction_ora);
-        m.insert(InstructionEnum::PHA, instruction_pha);
-        m.insert(InstructionEnum::PHP, instruction_php);
-        m.insert(InstructionEnum::PLA, instruction_pla);
-        m.insert(InstructionEnum::PLP, instruction_plp);
-        m.insert(InstructionEnum::ROL, instruction_rol);
-        m.insert(InstructionEnum::ROR, instruction_ror);
-        m.insert(InstructionEnum::RTI, instruction_rti);
-        m.insert(InstructionEnum::RTS, instruction_rts);
-        m.insert(InstructionEnum::SBC, instruction_sbc);
-        m.insert(InstructionEnum::SEC, instruction_sec);
-        m.insert(InstructionEnum::SED, instruction_sed);
-        m.insert(InstructionEnum::SEI, instruction_sei);
-        m.insert(InstructionEnum::STA, instruction_sta);
-        m.insert(InstructionEnum::STX, instruction_stx);
-        m.insert(InstructionEnum::STY, instruction_sty);
-        m.insert(InstructionEnum::TAX, instruction_tax);
-        m.insert(InstructionEnum::TAY, instruction_tay);
-        m.insert(InstructionEnum::TSX, instruction_tsx);
-        m.insert(InstructionEnum::TXA, instruction_txa);
-        m.insert(InstructionEnum::TXS, instruction_txs);
-        m.insert(InstructionEnum::TYA, instruction_tya);
-        m.insert(InstructionEnum::ALR, instruction_alr);
-        m.insert(InstructionEnum::ANC, instruction_anc);
-        m.insert(InstructionEnum::ARR, instruction_arr);
-        m.insert(InstructionEnum::AXS, instruction_axs);
-        m.insert(InstructionEnum::LAX, instruction_lax);
-        m.insert(InstructionEnum::SAX, instruction_sax);
-        m.insert(InstructionEnum::DCP, instruction_dcp);
-        m.insert(InstructionEnum::ISC, instruction_isc);
-        m.insert(InstructionEnum::RLA, instruction_rla);
-        m.insert(InstructionEnum::RRA, instruction_rra);
-        m.insert(InstructionEnum::SLO, instruction_slo);
-        m.insert(InstructionEnum::SRE, instruction_sre);
-        m.insert(InstructionEnum::NOP, instruction_nop);
-        m.insert(InstructionEnum::SKB, instruction_skb);
-        m.insert(InstructionEnum::IGN, instruction_ign);
-
-        Self { instructions: m }
+// 65C02 新增指令，BRA/PHX/PHY/PLX/PLY 没有运算部分
+
+/// 65C02 的 BIT #imm 只影响 Z
+fn instruction_bit_cmos(ctx: &mut Context, value: u8) -> u8 {
+    if ctx
+        .op
+        .is_some_and(|op| op.mode == AddressingMode::Immediate)
+    {
+        ctx.set_status_flag(StatusFlag::Zero, value & ctx.reg_a == 0);
+        return value;
     }
+    instruction_bit(ctx, value)
 }
 
-static INSTRUCTION_MANAGER: LazyLock<InstructionManager> = LazyLock::new(InstructionManager::new);
+fn instruction_stz(_: &mut Context, _: u8) -> u8 {
+    0
+}
 
-pub fn execute_instruction(ctx: &mut Context, instruction: InstructionEnum) {
-    if let Some(&func) = INSTRUCTION_MANAGER.instructions.get(&instruction) {
-        func(ctx);
-    } else {
-        panic!("Unknown instruction: {:?}", instruction);
-    }
+fn instruction_tsb(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Zero, value & ctx.reg_a == 0);
+    value | ctx.reg_a
 }
 
-fn interrupt_nmi(ctx: &mut Context) {
-    ctx.push_stack_16bit(ctx.reg_pc);
-    ctx.push_stack(ctx.reg_status | 0x30);
-    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
-    ctx.reg_pc = ctx.read_bus_16bit(0xFFFA);
+fn instruction_trb(ctx: &mut Context, value: u8) -> u8 {
+    ctx.set_status_flag(StatusFlag::Zero, value & ctx.reg_a == 0);
+    value & !ctx.reg_a
+}
 
-    ctx.remaining_cycles = 7;
+fn instruction_rmb<const BIT: u8>(_: &mut Context, value: u8) -> u8 {
+    value & !(1 << BIT)
 }
 
-fn interrupt_reset(ctx: &mut Context) {
-    ctx.reg_sp = 0xFD;
-    ctx.reg_status = 0x24; // Unused and Break flags set
-    ctx.reg_pc = ctx.read_bus_16bit(0xFFFC);
+fn instruction_smb<const BIT: u8>(_: &mut Context, value: u8) -> u8 {
+    value | (1 << BIT)
+}
 
-    ctx.remaining_cycles = 7;
+/// BBRn/BBSn 返回是否跳转，分支由 cycle 模块完成
+fn instruction_bbr<const BIT: u8>(_: &mut Context, value: u8) -> u8 {
+    !get_bit(value, BIT) as u8
+}
+
+fn instruction_bbs<const BIT: u8>(_: &mut Context, value: u8) -> u8 {
+    get_bit(value, BIT) as u8
 }
 
-fn interrupt_irq(ctx: &mut Context) {
-    if ctx.get_status_flag(StatusFlag::InterruptDisable) {
-        return;
+// NOP/IGN/SKB
+fn instruction_nop(_: &mut Context, value: u8) -> u8 {
+    value
+}
+
+/// 指令的运算函数，分支、跳转和栈指令没有运算部分，由 cycle 模块直接处理
+pub(crate) type InstructionFn = fn(&mut Context, u8) -> u8;
+
+/// 运算函数表的大小，JAM 是 InstructionEnum 的最后一个成员
+pub(crate) const INSTRUCTION_COUNT: usize = InstructionEnum::JAM as usize + 1;
+
+fn instruction_unknown(ctx: &mut Context, _: u8) -> u8 {
+    panic!("Unknown instruction: {:?}", ctx.op.map(|op| op.instruction));
+}
+
+/// 2A03 以 InstructionEnum 为下标的运算函数表，编译期生成
+const NES_INSTRUCTIONS: [InstructionFn; INSTRUCTION_COUNT] = {
+    use InstructionEnum::*;
+    let mut table: [InstructionFn; INSTRUCTION_COUNT] = [instruction_unknown; INSTRUCTION_COUNT];
+    table[ADC as usize] = instruction_adc;
+    table[AND as usize] = instruction_and;
+    table[ASL as usize] = instruction_asl;
+    table[BIT as usize] = instruction_bit;
+    table[CLC as usize] = instruction_clc;
+    table[CLD as usize] = instruction_cld;
+    table[CLI as usize] = instruction_cli;
+    table[CLV as usize] = instruction_clv;
+    table[CMP as usize] = instruction_cmp;
+    table[CPX as usize] = instruction_cpx;
+    table[CPY as usize] = instruction_cpy;
+    table[DEC as usize] = instruction_dec;
+    table[DEX as usize] = instruction_dex;
+    table[DEY as usize] = instruction_dey;
+    table[EOR as usize] = instruction_eor;
+    table[INC as usize] = instruction_inc;
+    table[INX as usize] = instruction_inx;
+    table[INY as usize] = instruction_iny;
+    table[LDA as usize] = instruction_lda;
+    table[LDX as usize] = instruction_ldx;
+    table[LDY as usize] = instruction_ldy;
+    table[LSR as usize] = instruction_lsr;
+    table[ORA as usize] = instruction_ora;
+    table[ROL as usize] = instruction_rol;
+    table[ROR as usize] = instruction_ror;
+    table[SBC as usize] = instruction_sbc;
+    table[SEC as usize] = instruction_sec;
+    table[SED as usize] = instruction_sed;
+    table[SEI as usize] = instruction_sei;
+    table[STA as usize] = instruction_sta;
+    table[STX as usize] = instruction_stx;
+    table[STY as usize] = instruction_sty;
+    table[TAX as usize] = instruction_tax;
+    table[TAY as usize] = instruction_tay;
+    table[TSX as usize] = instruction_tsx;
+    table[TXA as usize] = instruction_txa;
+    table[TXS as usize] = instruction_txs;
+    table[TYA as usize] = instruction_tya;
+    table[ALR as usize] = instruction_alr;
+    table[ANC as usize] = instruction_anc;
+    table[ARR as usize] = instruction_arr;
+    table[AXS as usize] = instruction_axs;
+    table[LAX as usize] = instruction_lax;
+    table[SAX as usize] = instruction_sax;
+    table[DCP as usize] = instruction_dcp;
+    table[ISC as usize] = instruction_isc;
+    table[RLA as usize] = instruction_rla;
+    table[RRA as usize] = instruction_rra;
+    table[SLO as usize] = instruction_slo;
+    table[SRE as usize] = instruction_sre;
+    table[NOP as usize] = instruction_nop;
+    table[SKB as usize] = instruction_nop;
+    table[IGN as usize] = instruction_nop;
+    table[XAA as usize] = instruction_xaa;
+    table[LXA as usize] = instruction_lxa;
+    table[AHX as usize] = instruction_ahx;
+    table[SHX as usize] = instruction_shx;
+    table[SHY as usize] = instruction_shy;
+    table[TAS as usize] = instruction_tas;
+    table[LAS as usize] = instruction_las;
+    table[JAM as usize] = instruction_jam;
+    table
+};
+
+/// NMOS 6502 在 2A03 的基础上支持十进制运算
+const NMOS_INSTRUCTIONS: [InstructionFn; INSTRUCTION_COUNT] = {
+    use InstructionEnum::*;
+    let mut table = NES_INSTRUCTIONS;
+    table[ADC as usize] = instruction_adc_nmos;
+    table[SBC as usize] = instruction_sbc_nmos;
+    table[ISC as usize] = instruction_isc_nmos;
+    table[RRA as usize] = instruction_rra_nmos;
+    table
+};
+
+/// 65C02 的运算函数表，非官方指令不会被译码到
+const CMOS_INSTRUCTIONS: [InstructionFn; INSTRUCTION_COUNT] = {
+    use InstructionEnum::*;
+    let mut table = NES_INSTRUCTIONS;
+    table[ADC as usize] = instruction_adc_cmos;
+    table[SBC as usize] = instruction_sbc_cmos;
+    table[BIT as usize] = instruction_bit_cmos;
+    table[STZ as usize] = instruction_stz;
+    table[TSB as usize] = instruction_tsb;
+    table[TRB as usize] = instruction_trb;
+    table[RMB0 as usize] = instruction_rmb::<0>;
+    table[SMB0 as usize] = instruction_smb::<0>;
+    table[BBR0 as usize] = instruction_bbr::<0>;
+    table[BBS0 as usize] = instruction_bbs::<0>;
+    table[RMB1 as usize] = instruction_rmb::<1>;
+    table[SMB1 as usize] = instruction_smb::<1>;
+    table[BBR1 as usize] = instruction_bbr::<1>;
+    table[BBS1 as usize] = instruction_bbs::<1>;
+    table[RMB2 as usize] = instruction_rmb::<2>;
+    table[SMB2 as usize] = instruction_smb::<2>;
+    table[BBR2 as usize] = instruction_bbr::<2>;
+    table[BBS2 as usize] = instruction_bbs::<2>;
+    table[RMB3 as usize] = instruction_rmb::<3>;
+    table[SMB3 as usize] = instruction_smb::<3>;
+    table[BBR3 as usize] = instruction_bbr::<3>;
+    table[BBS3 as usize] = instruction_bbs::<3>;
+    table[RMB4 as usize] = instruction_rmb::<4>;
+    table[SMB4 as usize] = instruction_smb::<4>;
+    table[BBR4 as usize] = instruction_bbr::<4>;
+    table[BBS4 as usize] = instruction_bbs::<4>;
+    table[RMB5 as usize] = instruction_rmb::<5>;
+    table[SMB5 as usize] = instruction_smb::<5>;
+    table[BBR5 as usize] = instruction_bbr::<5>;
+    table[BBS5 as usize] = instruction_bbs::<5>;
+    table[RMB6 as usize] = instruction_rmb::<6>;
+    table[SMB6 as usize] = instruction_smb::<6>;
+    table[BBR6 as usize] = instruction_bbr::<6>;
+    table[BBS6 as usize] = instruction_bbs::<6>;
+    table[RMB7 as usize] = instruction_rmb::<7>;
+    table[SMB7 as usize] = instruction_smb::<7>;
+    table[BBR7 as usize] = instruction_bbr::<7>;
+    table[BBS7 as usize] = instruction_bbs::<7>;
+    table
+};
+
+static INSTRUCTION_TABLE: [InstructionFn; INSTRUCTION_COUNT] = NES_INSTRUCTIONS;
+static INSTRUCTION_TABLE_6502: [InstructionFn; INSTRUCTION_COUNT] = NMOS_INSTRUCTIONS;
+static INSTRUCTION_TABLE_65C02: [InstructionFn; INSTRUCTION_COUNT] = CMOS_INSTRUCTIONS;
+
+/// 按 CPU 型号选择运算函数表，构造时选定，执行时不再判断型号
+fn instruction_table(variant: CpuVariant) -> &'static [InstructionFn; INSTRUCTION_COUNT] {
+    match variant {
+        CpuVariant::Ricoh2A03 => &INSTRUCTION_TABLE,
+        CpuVariant::Nmos6502 => &INSTRUCTION_TABLE_6502,
+        CpuVariant::Cmos65C02 => &INSTRUCTION_TABLE_65C02,
     }
-    ctx.push_stack_16bit(ctx.reg_pc);
-    ctx.push_stack(ctx.reg_status | 0x30);
-    ctx.set_status_flag(StatusFlag::InterruptDisable, true);
-    ctx.reg_pc = ctx.read_bus_16bit(0xFFFE);
+}
 
-    ctx.remaining_cycles = 7;
+/// 执行指令的运算部分，返回写指令/读改写指令要写回的数据
+pub fn execute_instruction(ctx: &mut Context, instruction: InstructionEnum, value: u8) -> u8 {
+    let func = ctx.instruction_table[instruction as usize];
+    func(ctx, value)
 }
 
-pub fn execute_interrupt(ctx: &mut Context, signal: Interrupt) {
-    match signal {
-        Interrupt::Nmi => interrupt_nmi(ctx),
-        Interrupt::Reset => interrupt_reset(ctx),
-        Interrupt::Irq => interrupt_irq(ctx),
-    }
+/// 复位：直接装载复位向量，随后挂起 7 个周期
+pub fn execute_reset(ctx: &mut Context) {
+    ctx.reg_sp = 0xFD;
+    ctx.reg_status = 0x24; // Unused and Break flags set
+    ctx.reg_pc = ctx.read_bus_16bit(0xFFFC);
+    ctx.remaining_cycles = 7;
+    ctx.cycle = 0;
+    ctx.op = None;
+    ctx.nmi_pending = false;
+    ctx.jammed = false;
 }
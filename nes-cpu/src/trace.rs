@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use nes_base::CpuState;
+
+use crate::disasm::Disassembly;
+
+/// PPU 每条扫描线的点数
+const DOTS_PER_SCANLINE: u32 = 341;
+/// 每帧扫描线数 (NTSC)
+const SCANLINES_PER_FRAME: u32 = 262;
+
+/// 返回当前 PPU 的 (扫描线, 点)
+pub type PpuPositionSource = Box<dyn Fn() -> (u16, u16)>;
+
+/// 执行跟踪器，每条指令执行前写入一行 Nintendulator/nestest 格式的日志
+pub struct Tracer {
+    out: Box<dyn Write>,
+    ppu_position: Option<PpuPositionSource>,
+}
+
+impl Tracer {
+    pub fn new(out: impl Write + 'static) -> Self {
+        Self {
+            out: Box::new(out),
+            ppu_position: None,
+        }
+    }
+
+    /// 指定 PPU 位置的来源；未指定时按 1 CPU 周期 = 3 PPU 点由 CPU 周期推算
+    pub fn with_ppu_position(mut self, source: impl Fn() -> (u16, u16) + 'static) -> Self {
+        self.ppu_position = Some(Box::new(source));
+        self
+    }
+
+    pub(crate) fn trace(&mut self, dis: &Disassembly, state: &CpuState) -> std::io::Result<()> {
+        let (scanline, dot) = match &self.ppu_position {
+            Some(source) => source(),
+            None => ppu_position_from_cycles(state.total_cycles),
+        };
+        writeln!(self.out, "{}", format_trace_line(dis, state, scanline, dot))
+    }
+}
+
+fn ppu_position_from_cycles(cpu_cycles: u32) -> (u16, u16) {
+    let dots = cpu_cycles * 3;
+    let scanline = (dots / DOTS_PER_SCANLINE) % SCANLINES_PER_FRAME;
+    (scanline as u16, (dots % DOTS_PER_SCANLINE) as u16)
+}
+
+/// 按 nestest.log 的列宽格式化一行跟踪日志 (不含换行)
+///
+/// `C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7`
+pub fn format_trace_line(dis: &Disassembly, state: &CpuState, scanline: u16, dot: u16) -> String {
+    // 官方指令的助记符前留一个空格，非官方指令在该位置显示 `*`
+    let text = if dis.unofficial {
+        dis.to_string()
+    } else {
+        format!(" {dis}")
+    };
+    let reg_status: u8 = state.reg_status.into();
+    format!(
+        "{:04X}  {:<8} {:<33}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        dis.address,
+        dis.bytes_hex(),
+        text,
+        state.reg_a,
+        state.reg_x,
+        state.reg_y,
+        reg_status,
+        state.reg_sp,
+        scanline,
+        dot,
+        state.total_cycles
+    )
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use nes_base::CpuState;
+
+use crate::DebuggerError;
+
+/// 条件表达式中可以引用的寄存器与状态位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    P,
+    /// 状态寄存器中的某一位，取值 0 或 1
+    Flag(u8),
+    Value(u16),
+}
+
+impl Operand {
+    fn parse(token: &str) -> Option<Self> {
+        let operand = match token.to_ascii_uppercase().as_str() {
+            "A" => Operand::A,
+            "X" => Operand::X,
+            "Y" => Operand::Y,
+            "SP" | "S" => Operand::Sp,
+            "PC" => Operand::Pc,
+            "P" => Operand::P,
+            "C" => Operand::Flag(0),
+            "Z" => Operand::Flag(1),
+            "I" => Operand::Flag(2),
+            "D" => Operand::Flag(3),
+            "V" => Operand::Flag(6),
+            "N" => Operand::Flag(7),
+            _ => return parse_number(token).map(Operand::Value),
+        };
+        Some(operand)
+    }
+
+    fn eval(self, state: &CpuState) -> u16 {
+        match self {
+            Operand::A => state.reg_a as u16,
+            Operand::X => state.reg_x as u16,
+            Operand::Y => state.reg_y as u16,
+            Operand::Sp => state.reg_sp as u16,
+            Operand::Pc => state.reg_pc,
+            Operand::P => u8::from(state.reg_status) as u16,
+            Operand::Flag(bit) => ((u8::from(state.reg_status) >> bit) & 1) as u16,
+            Operand::Value(value) => value,
+        }
+    }
+}
+
+/// 数字支持 `$FF`、`0xFF` 与十进制
+fn parse_number(token: &str) -> Option<u16> {
+    if let Some(hex) = token.strip_prefix('$') {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(hex) = token
+        .strip_prefix("0x")
+        .or_else(|| token.strip_prefix("0X"))
+    {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        token.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparator {
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "==" => Comparator::Eq,
+            "!=" => Comparator::Ne,
+            "<" => Comparator::Lt,
+            "<=" => Comparator::Le,
+            ">" => Comparator::Gt,
+            ">=" => Comparator::Ge,
+            _ => return None,
+        })
+    }
+
+    fn compare(self, lhs: u16, rhs: u16) -> bool {
+        match self {
+            Comparator::Eq => lhs == rhs,
+            Comparator::Ne => lhs != rhs,
+            Comparator::Lt => lhs < rhs,
+            Comparator::Le => lhs <= rhs,
+            Comparator::Gt => lhs > rhs,
+            Comparator::Ge => lhs >= rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparison {
+    lhs: Operand,
+    comparator: Comparator,
+    rhs: Operand,
+}
+
+/// 寄存器条件表达式，例如 `A == $10 && X >= 3 || C == 1`
+///
+/// `&&` 优先级高于 `||`，不支持括号
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    source: String,
+    /// 析取范式：任意一组中的比较全部成立时条件成立
+    any_of: Vec<Vec<Comparison>>,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, DebuggerError> {
+        let invalid = || DebuggerError::InvalidCondition(source.to_string());
+        let any_of = source
+            .split("||")
+            .map(|group| {
+                group
+                    .split("&&")
+                    .map(|term| parse_comparison(term).ok_or_else(invalid))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            source: source.trim().to_string(),
+            any_of,
+        })
+    }
+
+    pub fn eval(&self, state: &CpuState) -> bool {
+        self.any_of.iter().any(|group| {
+            group
+                .iter()
+                .all(|c| c.comparator.compare(c.lhs.eval(state), c.rhs.eval(state)))
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn parse_comparison(term: &str) -> Option<Comparison> {
+    // 先匹配两个字符的比较符，避免把 `<=` 拆成 `<`
+    let (pos, len) = ["==", "!=", "<=", ">=", "<", ">"]
+        .iter()
+        .find_map(|op| term.find(op).map(|pos| (pos, op.len())))?;
+    let lhs = Operand::parse(term[..pos].trim())?;
+    let comparator = Comparator::parse(&term[pos..pos + len])?;
+    let rhs = Operand::parse(term[pos + len..].trim())?;
+    Some(Comparison {
+        lhs,
+        comparator,
+        rhs,
+    })
+}
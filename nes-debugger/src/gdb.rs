@@ -0,0 +1,463 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use log::{debug, info};
+use nes_base::CpuState;
+
+use crate::{Access, BusKind, Debugger, StopReason, Watchpoint};
+
+/// 继续运行时每次执行的周期数，两次之间检查客户端的中断请求
+const RUN_SLICE_CYCLES: u64 = 29_781;
+/// 中断请求 (Ctrl-C)
+const INTERRUPT: u8 = 0x03;
+const SIGINT: u8 = 2;
+/// CPU 执行 JAM 停机时报告为非法指令
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+/// 寄存器按 A, X, Y, P, SP, PC 的顺序排列，PC 为 16 位小端
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.nes.6502.core">
+    <reg name="a" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="x" bitsize="8" type="uint8"/>
+    <reg name="y" bitsize="8" type="uint8"/>
+    <reg name="p" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="8" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// GDB Remote Serial Protocol 服务端
+///
+/// 一次服务一个客户端，支持寄存器/内存读写、`Z0`/`Z1` 断点、`Z2`-`Z4` 观察点以及继续/单步
+pub struct GdbServer {
+    debugger: Debugger,
+}
+
+/// 处理一个数据包后的动作
+enum Reply {
+    Packet(String),
+    /// 客户端要求断开 (D/k)
+    Close(Option<String>),
+}
+
+impl GdbServer {
+    pub fn new(debugger: Debugger) -> Self {
+        Self { debugger }
+    }
+
+    pub fn debugger(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    pub fn into_debugger(self) -> Debugger {
+        self.debugger
+    }
+
+    /// 在 addr 上监听并服务第一个连接，客户端断开后返回
+    pub fn listen(&mut self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        info!("GDB server listening on {}", listener.local_addr()?);
+        let (stream, peer) = listener.accept()?;
+        info!("GDB client connected from {}", peer);
+        self.serve(stream)
+    }
+
+    /// 在已建立的连接上处理数据包，直到客户端断开
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        let mut conn = Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+        let cycle_limit = self.debugger.cycle_limit();
+        self.debugger.set_cycle_limit(RUN_SLICE_CYCLES);
+
+        let result = self.serve_packets(&mut conn);
+        self.debugger.set_cycle_limit(cycle_limit);
+        result
+    }
+
+    fn serve_packets(&mut self, conn: &mut Connection) -> io::Result<()> {
+        while let Some(packet) = conn.read_packet()? {
+            debug!("gdb <- {}", packet);
+            match self.handle(&packet, conn)? {
+                Reply::Packet(reply) => conn.write_packet(&reply)?,
+                Reply::Close(reply) => {
+                    if let Some(reply) = reply {
+                        conn.write_packet(&reply)?;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: &str, conn: &mut Connection) -> io::Result<Reply> {
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "?" => self.stop_reply(&StopReason::Step),
+            "g" => self.read_registers(),
+            "G" => self.write_registers(args),
+            "p" => self.read_register(args),
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "c" => return self.resume(conn).map(Reply::Packet),
+            "s" => {
+                let reason = self.debugger.step_into();
+                self.stop_reply(&reason)
+            }
+            "Z" => self.set_point(args, true),
+            "z" => self.set_point(args, false),
+            "H" | "T" => "OK".to_string(),
+            "D" => return Ok(Reply::Close(Some("OK".to_string()))),
+            "k" => return Ok(Reply::Close(None)),
+            "q" => query(args),
+            // 其它命令回复空包，表示不支持
+            _ => String::new(),
+        };
+        Ok(Reply::Packet(reply))
+    }
+
+    /// 分段运行，期间客户端发送 Ctrl-C 时停止
+    fn resume(&mut self, conn: &mut Connection) -> io::Result<String> {
+        loop {
+            match self.debugger.run() {
+                StopReason::CycleLimit => {
+                    if conn.poll_interrupt()? {
+                        return Ok(format!("S{SIGINT:02x}"));
+                    }
+                }
+                reason => return Ok(self.stop_reply(&reason)),
+            }
+        }
+    }
+
+    /// 停止原因对应的停止应答，观察点按其类型报告为 watch/rwatch/awatch
+    fn stop_reply(&self, reason: &StopReason) -> String {
+        let hit = match reason {
+            StopReason::Watchpoint(hit) => hit,
+            StopReason::Jam => return format!("S{SIGILL:02x}"),
+            _ => return format!("S{SIGTRAP:02x}"),
+        };
+        let access = self
+            .debugger
+            .watchpoints()
+            .get(hit.index)
+            .map_or(hit.access, |w| w.access);
+        let kind = match access {
+            Access::Write => "watch",
+            Access::Read => "rwatch",
+            Access::ReadWrite => "awatch",
+        };
+        format!("T{SIGTRAP:02x}{kind}:{:04x};", hit.address)
+    }
+
+    fn read_registers(&self) -> String {
+        encode_registers(&self.debugger.cpu_state())
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        let Some(bytes) = decode_hex(args).filter(|bytes| bytes.len() >= 7) else {
+            return "E01".to_string();
+        };
+        let mut state = self.debugger.cpu_state();
+        state.reg_a = bytes[0];
+        state.reg_x = bytes[1];
+        state.reg_y = bytes[2];
+        state.reg_status = bytes[3].into();
+        state.reg_sp = bytes[4];
+        state.reg_pc = u16::from_le_bytes([bytes[5], bytes[6]]);
+        self.debugger.set_cpu_registers(&state);
+        "OK".to_string()
+    }
+
+    fn read_register(&self, args: &str) -> String {
+        let state = self.debugger.cpu_state();
+        let value = match usize::from_str_radix(args, 16) {
+            Ok(0) => state.reg_a as u16,
+            Ok(1) => state.reg_x as u16,
+            Ok(2) => state.reg_y as u16,
+            Ok(3) => u8::from(state.reg_status) as u16,
+            Ok(4) => state.reg_sp as u16,
+            Ok(5) => return encode_hex(&state.reg_pc.to_le_bytes()),
+            _ => return "E01".to_string(),
+        };
+        format!("{value:02x}")
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let Some((index, value)) = args.split_once('=') else {
+            return "E01".to_string();
+        };
+        let (Ok(index), Some(value)) = (usize::from_str_radix(index, 16), decode_hex(value)) else {
+            return "E01".to_string();
+        };
+        let mut state = self.debugger.cpu_state();
+        let byte = value.first().copied().unwrap_or(0);
+        match index {
+            0 => state.reg_a = byte,
+            1 => state.reg_x = byte,
+            2 => state.reg_y = byte,
+            3 => state.reg_status = byte.into(),
+            4 => state.reg_sp = byte,
+            5 if value.len() == 2 => state.reg_pc = u16::from_le_bytes([value[0], value[1]]),
+            _ => return "E01".to_string(),
+        }
+        self.debugger.set_cpu_registers(&state);
+        "OK".to_string()
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        let Some((address, len)) = parse_address_length(args) else {
+            return "E01".to_string();
+        };
+        let bytes: Vec<u8> = (0..len)
+            .map(|i| self.debugger.peek(address.wrapping_add(i)))
+            .collect();
+        encode_hex(&bytes)
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let Some((range, data)) = args.split_once(':') else {
+            return "E01".to_string();
+        };
+        let (Some((address, len)), Some(bytes)) = (parse_address_length(range), decode_hex(data))
+        else {
+            return "E01".to_string();
+        };
+        if bytes.len() != len as usize {
+            return "E01".to_string();
+        }
+        if !(0..len).all(|i| Debugger::can_poke(address.wrapping_add(i))) {
+            return "E01".to_string();
+        }
+        for (i, byte) in bytes.into_iter().enumerate() {
+            self.debugger.poke(address.wrapping_add(i as u16), byte);
+        }
+        "OK".to_string()
+    }
+
+    /// Z/z 包：类型 0/1 为断点，2/3/4 为写/读/读写观察点
+    fn set_point(&mut self, args: &str, insert: bool) -> String {
+        let mut parts = args.split(',');
+        let (Some(kind), Some(address), Some(len)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return "E01".to_string();
+        };
+        let (Ok(address), Ok(len)) = (
+            u16::from_str_radix(address, 16),
+            u16::from_str_radix(len, 16),
+        ) else {
+            return "E01".to_string();
+        };
+
+        let access = match kind {
+            "0" | "1" => {
+                if insert {
+                    // 无条件断点不会解析失败
+                    let _ = self.debugger.add_breakpoint(address, None);
+                } else {
+                    self.debugger.remove_breakpoint(address);
+                }
+                return "OK".to_string();
+            }
+            "2" => Access::Write,
+            "3" => Access::Read,
+            "4" => Access::ReadWrite,
+            _ => return String::new(),
+        };
+
+        let end = address.saturating_add(len.max(1) - 1);
+        let watchpoint = Watchpoint::new(BusKind::Cpu, address..=end, access);
+        if insert {
+            self.debugger.add_watchpoint(watchpoint);
+        } else if let Some(index) = self
+            .debugger
+            .watchpoints()
+            .iter()
+            .position(|w| *w == watchpoint)
+        {
+            self.debugger.remove_watchpoint(index);
+        }
+        "OK".to_string()
+    }
+}
+
+fn query(args: &str) -> String {
+    if args.starts_with("Supported") {
+        "PacketSize=1000;qXfer:features:read+".to_string()
+    } else if args == "Attached" {
+        "1".to_string()
+    } else if args == "C" {
+        "QC1".to_string()
+    } else if args == "fThreadInfo" {
+        "m1".to_string()
+    } else if args == "sThreadInfo" {
+        "l".to_string()
+    } else if let Some(range) = args.strip_prefix("Xfer:features:read:target.xml:") {
+        let Some((offset, len)) = range.split_once(',') else {
+            return "E01".to_string();
+        };
+        let (Ok(offset), Ok(len)) = (
+            usize::from_str_radix(offset, 16),
+            usize::from_str_radix(len, 16),
+        ) else {
+            return "E01".to_string();
+        };
+        let start = offset.min(TARGET_XML.len());
+        let end = start.saturating_add(len).min(TARGET_XML.len());
+        let prefix = if end == TARGET_XML.len() { 'l' } else { 'm' };
+        format!("{prefix}{}", &TARGET_XML[start..end])
+    } else {
+        String::new()
+    }
+}
+
+fn encode_registers(state: &CpuState) -> String {
+    let [pc_low, pc_high] = state.reg_pc.to_le_bytes();
+    encode_hex(&[
+        state.reg_a,
+        state.reg_x,
+        state.reg_y,
+        state.reg_status.into(),
+        state.reg_sp,
+        pc_low,
+        pc_high,
+    ])
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() & 1 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_address_length(args: &str) -> Option<(u16, u16)> {
+    let (address, len) = args.split_once(',')?;
+    let address = u32::from_str_radix(address, 16).ok()?;
+    let len = u16::from_str_radix(len, 16).ok()?;
+    // gdb 可能使用更宽的地址，只取低 16 位
+    Some((address as u16, len))
+}
+
+/// 一个客户端连接，负责数据包的收发与校验
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// 读取下一个数据包并应答 `+`/`-`，连接关闭时返回 None
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // 跳过 ack 与数据包之间的字节
+            match self.read_byte()? {
+                None => return Ok(None),
+                Some(b'$') => {}
+                Some(_) => continue,
+            }
+
+            let mut data = Vec::new();
+            let mut sum: u8 = 0;
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => {
+                        sum = sum.wrapping_add(byte);
+                        data.push(byte);
+                    }
+                }
+            }
+            let mut checksum = [0; 2];
+            self.reader.read_exact(&mut checksum)?;
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|text| u8::from_str_radix(text, 16).ok());
+
+            if expected != Some(sum) {
+                self.writer.write_all(b"-")?;
+                continue;
+            }
+            self.writer.write_all(b"+")?;
+            return Ok(Some(String::from_utf8_lossy(&unescape(&data)).into_owned()));
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        debug!("gdb -> {}", data);
+        let sum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.writer, "${data}#{sum:02x}")?;
+        self.writer.flush()
+    }
+
+    /// 检查客户端是否发送了 Ctrl-C，不阻塞，客户端已断开时返回错误
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        if self.reader.buffer().is_empty() {
+            self.reader.get_ref().set_nonblocking(true)?;
+            let result = self.reader.fill_buf().map(|_| ());
+            self.reader.get_ref().set_nonblocking(false)?;
+            match result {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+                other => other?,
+            }
+            // 有数据可读却读到 0 字节，说明连接已关闭
+            if self.reader.buffer().is_empty() {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "gdb client disconnected",
+                ));
+            }
+        }
+        // 丢弃客户端对上一个应答的 ack
+        let acks = self
+            .reader
+            .buffer()
+            .iter()
+            .take_while(|&&b| b == b'+' || b == b'-')
+            .count();
+        self.reader.consume(acks);
+        let interrupted = self.reader.buffer().first() == Some(&INTERRUPT);
+        if interrupted {
+            self.reader.consume(1);
+        }
+        Ok(interrupted)
+    }
+}
+
+/// `}` 之后的字节与 0x20 异或
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'}' => out.extend(bytes.next().map(|b| b ^ 0x20)),
+            _ => out.push(byte),
+        }
+    }
+    out
+}
@@ -0,0 +1,331 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use nes_base::{BusAdapter, CpuState};
+use nes_board::BoardImpl;
+use watch::{WatchBus, WatchState};
+
+mod condition;
+mod gdb;
+mod watch;
+
+pub use condition::Condition;
+pub use gdb::GdbServer;
+pub use watch::{Access, BusKind, WatchHit, Watchpoint};
+
+/// JSR 操作码，单步跳过时执行完整个子程序
+const OPCODE_JSR: u8 = 0x20;
+const OPCODE_RTS: u8 = 0x60;
+const OPCODE_RTI: u8 = 0x40;
+/// 每帧扫描线数 (NTSC)
+const SCANLINES_PER_FRAME: u16 = 262;
+/// 默认的单次运行周期上限，约 1 秒的 NTSC CPU 周期
+const DEFAULT_CYCLE_LIMIT: u64 = 1_789_773;
+
+/// 调试器错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebuggerError {
+    /// 无法解析的条件表达式
+    InvalidCondition(String),
+}
+
+impl fmt::Display for DebuggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebuggerError::InvalidCondition(source) => {
+                write!(f, "invalid condition expression: {}", source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DebuggerError {}
+
+/// 调试器停止运行的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// 单步操作完成
+    Step,
+    /// 命中 PC 断点
+    Breakpoint(u16),
+    /// 命中读写观察点
+    Watchpoint(WatchHit),
+    /// 到达指定扫描线
+    Scanline(u16),
+    /// 进入 NMI 处理程序
+    Nmi,
+    /// 超出运行周期上限仍未停止
+    CycleLimit,
+    /// CPU 执行 JAM 指令后停机
+    Jam,
+}
+
+/// 刚执行完的一条指令，供停止条件判断
+struct Executed {
+    opcode: u8,
+    /// 执行前后 PPU 所在的扫描线
+    scanlines: (u16, u16),
+}
+
+impl Executed {
+    /// 执行过程中是否进入了 scanline，OAM DMA 等长指令可能跨越多条扫描线
+    fn entered_scanline(&self, scanline: u16) -> bool {
+        let (before, after) = self.scanlines;
+        let distance = |to: u16| (to + SCANLINES_PER_FRAME - before) % SCANLINES_PER_FRAME;
+        (1..=distance(after)).contains(&distance(scanline))
+    }
+}
+
+/// 调试器核心，接管主板并在指令边界检查断点与观察点
+///
+/// 观察点通过包装 CPU/PPU 所连接的总线实现，不使用调试器时主板的读写路径没有额外开销
+pub struct Debugger {
+    board: BoardImpl,
+    breakpoints: HashMap<u16, Option<Condition>>,
+    watch: Rc<RefCell<WatchState>>,
+    cycle_limit: u64,
+}
+
+impl Debugger {
+    pub fn new(board: BoardImpl) -> Self {
+        let watch = Rc::new(RefCell::new(WatchState::default()));
+        let cpu_bus: Rc<RefCell<dyn BusAdapter>> = Rc::new(RefCell::new(WatchBus {
+            inner: board.cpu_bus.clone(),
+            bus: BusKind::Cpu,
+            state: watch.clone(),
+        }));
+        let ppu_bus: Rc<RefCell<dyn BusAdapter>> = Rc::new(RefCell::new(WatchBus {
+            inner: board.ppu_bus.clone(),
+            bus: BusKind::Ppu,
+            state: watch.clone(),
+        }));
+        board.cpu.borrow_mut().attach_bus(cpu_bus);
+        board.ppu.borrow_mut().attach_bus(ppu_bus);
+
+        Self {
+            board,
+            breakpoints: HashMap::new(),
+            watch,
+            cycle_limit: DEFAULT_CYCLE_LIMIT,
+        }
+    }
+
+    /// 退出调试，恢复 CPU/PPU 原来的总线连接
+    pub fn into_board(self) -> BoardImpl {
+        let board = self.board;
+        board.cpu.borrow_mut().attach_bus(board.cpu_bus.clone());
+        board.ppu.borrow_mut().attach_bus(board.ppu_bus.clone());
+        board
+    }
+
+    pub fn board(&self) -> &BoardImpl {
+        &self.board
+    }
+
+    pub fn board_mut(&mut self) -> &mut BoardImpl {
+        &mut self.board
+    }
+
+    pub fn cpu_state(&self) -> CpuState {
+        self.board.cpu.borrow().dump_state()
+    }
+
+    /// 修改 CPU 寄存器
+    pub fn set_cpu_registers(&mut self, state: &CpuState) {
+        self.board.cpu.borrow_mut().set_registers(state);
+    }
+
+    /// 通过原始总线读取 CPU 地址空间，不触发观察点
+    ///
+    /// 通过 `Reader::peek` 只读取内部 RAM 与卡带的 PRG-ROM/PRG-RAM，不改变设备状态：
+    /// - $2000-$401F 的 I/O 寄存器读取有副作用或只写，返回 0xFF
+    /// - 卡带上没有映射存储器的地址 (Mapper 寄存器、不存在的 PRG-RAM) 返回 0
+    pub fn peek(&self, address: u16) -> u8 {
+        match self.board.cpu_bus.borrow().peek(address) {
+            Some(value) => value,
+            None if (0x2000..=0x401F).contains(&address) => 0xFF,
+            None => 0,
+        }
+    }
+
+    /// poke 是否允许写入该地址
+    ///
+    /// 只允许写入内部 RAM，其他地址的写入会改变 I/O 或 Mapper 的 bank 寄存器
+    pub fn can_poke(address: u16) -> bool {
+        address < 0x2000
+    }
+
+    /// 通过原始总线写入内部 RAM，不触发观察点，不允许写入的地址返回 false
+    pub fn poke(&mut self, address: u16, value: u8) -> bool {
+        if !Self::can_poke(address) {
+            return false;
+        }
+        self.board.cpu_bus.borrow_mut().write(address, value);
+        true
+    }
+
+    pub fn cycle_limit(&self) -> u64 {
+        self.cycle_limit
+    }
+
+    /// 设置单次运行的 CPU 周期上限，超出后返回 StopReason::CycleLimit
+    pub fn set_cycle_limit(&mut self, cycles: u64) {
+        self.cycle_limit = cycles;
+    }
+
+    /// 在 PC 处设置断点，condition 为寄存器条件表达式
+    pub fn add_breakpoint(
+        &mut self,
+        address: u16,
+        condition: Option<&str>,
+    ) -> Result<(), DebuggerError> {
+        let condition = condition.map(Condition::parse).transpose()?;
+        self.breakpoints.insert(address, condition);
+        Ok(())
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (u16, Option<&Condition>)> {
+        self.breakpoints
+            .iter()
+            .map(|(address, condition)| (*address, condition.as_ref()))
+    }
+
+    /// 添加观察点，返回其序号
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        let mut watch = self.watch.borrow_mut();
+        watch.watchpoints.push(watchpoint);
+        watch.watchpoints.len() - 1
+    }
+
+    pub fn remove_watchpoint(&mut self, index: usize) -> Option<Watchpoint> {
+        let mut watch = self.watch.borrow_mut();
+        (index < watch.watchpoints.len()).then(|| watch.watchpoints.remove(index))
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.watch.borrow().watchpoints.clone()
+    }
+
+    /// 执行一条指令
+    pub fn step_into(&mut self) -> StopReason {
+        self.run_until(|_, _| Some(StopReason::Step))
+    }
+
+    /// 执行一条指令，遇到 JSR 时执行完整个子程序
+    pub fn step_over(&mut self) -> StopReason {
+        let state = self.cpu_state();
+        if self.peek(state.reg_pc) != OPCODE_JSR {
+            return self.step_into();
+        }
+        let return_address = state.reg_pc.wrapping_add(3);
+        // 递归调用时同一返回地址会出现多次，用栈指针区分
+        self.run_until(|debugger, _| {
+            let now = debugger.cpu_state();
+            (now.reg_pc == return_address && now.reg_sp >= state.reg_sp).then_some(StopReason::Step)
+        })
+    }
+
+    /// 运行到当前子程序返回
+    pub fn step_out(&mut self) -> StopReason {
+        let start_sp = self.cpu_state().reg_sp;
+        self.run_until(|debugger, executed| {
+            let now = debugger.cpu_state();
+            (matches!(executed.opcode, OPCODE_RTS | OPCODE_RTI) && now.reg_sp > start_sp)
+                .then_some(StopReason::Step)
+        })
+    }
+
+    /// 持续运行，直到命中断点或观察点
+    pub fn run(&mut self) -> StopReason {
+        self.run_until(|_, _| None)
+    }
+
+    /// 运行到 PPU 进入指定扫描线后的第一个指令边界
+    pub fn run_to_scanline(&mut self, scanline: u16) -> StopReason {
+        self.run_until(move |_, executed| {
+            executed
+                .entered_scanline(scanline)
+                .then_some(StopReason::Scanline(scanline))
+        })
+    }
+
+    /// 运行到 CPU 响应 NMI 并进入处理程序
+    ///
+    /// 以 CPU 的中断响应序列为准，不比较 PC，处理程序随 bank 切换移动或被 JMP/JSR 直接调用都不影响判断
+    pub fn run_to_nmi(&mut self) -> StopReason {
+        let serviced = self.cpu_state().nmi_count;
+        self.run_until(move |debugger, _| {
+            (debugger.cpu_state().nmi_count != serviced).then_some(StopReason::Nmi)
+        })
+    }
+
+    /// 逐条执行指令，每条指令结束后依次检查观察点、停止条件和断点
+    fn run_until(
+        &mut self,
+        mut stop: impl FnMut(&Self, &Executed) -> Option<StopReason>,
+    ) -> StopReason {
+        let mut cycles = 0;
+        loop {
+            let opcode = self.peek(self.cpu_state().reg_pc);
+            let before = self.board.ppu.borrow().position().0;
+            cycles += self.clock_instruction();
+            let after = self.board.ppu.borrow().position().0;
+            let executed = Executed {
+                opcode,
+                scanlines: (before, after),
+            };
+
+            if let Some(hit) = self.take_watch_hit() {
+                return StopReason::Watchpoint(hit);
+            }
+            if self.cpu_state().jammed {
+                return StopReason::Jam;
+            }
+            if let Some(reason) = stop(self, &executed) {
+                return reason;
+            }
+            if let Some(address) = self.breakpoint_hit() {
+                return StopReason::Breakpoint(address);
+            }
+            if cycles >= self.cycle_limit {
+                return StopReason::CycleLimit;
+            }
+        }
+    }
+
+    /// 驱动主板直到当前指令 (或中断) 的所有周期结束，返回经过的周期数
+    fn clock_instruction(&mut self) -> u64 {
+        let mut cycles = 0;
+        loop {
+            self.board.clock();
+            cycles += 1;
+            if self.board.cpu.borrow().dump_state().remaining_cycles == 0 {
+                return cycles;
+            }
+        }
+    }
+
+    fn take_watch_hit(&self) -> Option<WatchHit> {
+        let hits = std::mem::take(&mut self.watch.borrow_mut().hits);
+        let state = self.cpu_state();
+        let watch = self.watch.borrow();
+        hits.into_iter().find(|hit| {
+            watch.watchpoints[hit.index]
+                .condition
+                .as_ref()
+                .is_none_or(|condition| condition.eval(&state))
+        })
+    }
+
+    fn breakpoint_hit(&self) -> Option<u16> {
+        let state = self.cpu_state();
+        let condition = self.breakpoints.get(&state.reg_pc)?;
+        condition
+            .as_ref()
+            .is_none_or(|condition| condition.eval(&state))
+            .then_some(state.reg_pc)
+    }
+}
@@ -0,0 +1,128 @@
+use std::{cell::RefCell, ops::RangeInclusive, rc::Rc};
+
+use nes_base::{Bus, BusAdapter, Reader, Writer};
+
+use crate::Condition;
+
+/// 观察点所在的总线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusKind {
+    Cpu,
+    Ppu,
+}
+
+/// 访问类型，观察点可以同时监听读写
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn matches(self, access: Access) -> bool {
+        self == Access::ReadWrite || self == access
+    }
+}
+
+/// 读写观察点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub bus: BusKind,
+    pub range: RangeInclusive<u16>,
+    pub access: Access,
+    /// 在触发访问的指令执行完后求值
+    pub condition: Option<Condition>,
+}
+
+impl Watchpoint {
+    pub fn new(bus: BusKind, range: RangeInclusive<u16>, access: Access) -> Self {
+        Self {
+            bus,
+            range,
+            access,
+            condition: None,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+/// 观察点命中记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// 命中的观察点序号
+    pub index: usize,
+    pub bus: BusKind,
+    pub address: u16,
+    pub value: u8,
+    /// 实际的访问类型，Read 或 Write
+    pub access: Access,
+}
+
+#[derive(Default)]
+pub(crate) struct WatchState {
+    pub watchpoints: Vec<Watchpoint>,
+    /// 当前指令内命中的观察点，按访问顺序排列
+    pub hits: Vec<WatchHit>,
+}
+
+impl WatchState {
+    fn record(&mut self, bus: BusKind, address: u16, value: u8, access: Access) {
+        for (index, watchpoint) in self.watchpoints.iter().enumerate() {
+            if watchpoint.bus == bus
+                && watchpoint.access.matches(access)
+                && watchpoint.range.contains(&address)
+            {
+                self.hits.push(WatchHit {
+                    index,
+                    bus,
+                    address,
+                    value,
+                    access,
+                });
+            }
+        }
+    }
+}
+
+/// 包装 CPU/PPU 看到的总线，记录命中观察点的访问
+///
+/// 只在调试器接管主板时挂到 CPU/PPU 上，正常运行时不经过这一层
+pub(crate) struct WatchBus {
+    pub inner: Rc<RefCell<dyn Bus>>,
+    pub bus: BusKind,
+    pub state: Rc<RefCell<WatchState>>,
+}
+
+impl Reader for WatchBus {
+    fn read(&self, addr: u16) -> u8 {
+        let value = self.inner.borrow().read(addr);
+        self.state
+            .borrow_mut()
+            .record(self.bus, addr, value, Access::Read);
+        value
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.inner.borrow().peek(addr)
+    }
+}
+
+impl Writer for WatchBus {
+    fn write(&mut self, addr: u16, data: u8) {
+        self.inner.borrow_mut().write(addr, data);
+        self.state
+            .borrow_mut()
+            .record(self.bus, addr, data, Access::Write);
+    }
+}
+
+impl BusAdapter for WatchBus {
+    fn address_accept(&self, addr: u16) -> bool {
+        self.inner.borrow().address_accept(addr)
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    cell::RefCell,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use nes_cartridge::{CartridgeImpl, sav_path};
+
+/// 每隔多少帧检查一次存档是否需要写回磁盘，约 5 秒
+const FLUSH_INTERVAL_FRAMES: u32 = 60 * 5;
+
+/// 管理 ROM 同目录下的 .sav 存档文件
+/// 创建时自动加载存档，运行时定期写回，销毁时保存最后的状态
+pub struct BatterySave {
+    path: PathBuf,
+    cartridge: Rc<RefCell<CartridgeImpl>>,
+    frames: u32,
+}
+
+impl BatterySave {
+    pub fn load(rom_path: &Path, cartridge: Rc<RefCell<CartridgeImpl>>) -> io::Result<Self> {
+        let path = sav_path(rom_path);
+        if cartridge.borrow_mut().load_sav_file(&path)? {
+            log::info!("Loaded save file: {}", path.display());
+        }
+        Ok(Self {
+            path,
+            cartridge,
+            frames: 0,
+        })
+    }
+
+    /// 每帧结束时调用，定期把修改过的存档写回磁盘
+    pub fn on_frame(&mut self) {
+        self.frames += 1;
+        if self.frames >= FLUSH_INTERVAL_FRAMES {
+            self.frames = 0;
+            if let Err(e) = self.flush() {
+                log::warn!("Failed to write save file {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    /// 如果存档被修改过，则立即写回磁盘
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut cartridge = self.cartridge.borrow_mut();
+        if cartridge.is_sram_dirty() {
+            cartridge.write_sav_file(&self.path)?;
+            log::debug!("Flushed save file: {}", self.path.display());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BatterySave {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            log::warn!("Failed to write save file {}: {}", self.path.display(), e);
+        }
+    }
+}
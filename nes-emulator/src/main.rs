@@ -6,10 +6,55 @@ use embedded_graphics::{
     text::Text,
 };
 use embedded_graphics_simulator::{
-    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent, Window,
 };
+use nes_cartridge::{CartridgeImpl, NESFile, RomError, soft_patch_path};
+use std::{cell::RefCell, path::Path, rc::Rc, time::Duration};
+
+use crate::battery::BatterySave;
+
+mod battery;
+
+fn load_rom(rom_path: &str) -> Result<BatterySave, Box<dyn std::error::Error>> {
+    // ROM 同目录下有同名的补丁文件时自动打上补丁，不修改原文件
+    let nes = match soft_patch_path(Path::new(rom_path)) {
+        Some(patch_path) => {
+            let bytes = std::fs::read(rom_path).map_err(|source| RomError::Io {
+                path: rom_path.to_string(),
+                source,
+            })?;
+            let patch = std::fs::read(&patch_path).map_err(|source| RomError::Io {
+                path: patch_path.display().to_string(),
+                source,
+            })?;
+            log::info!("Applying patch: {}", patch_path.display());
+            NESFile::with_patches(bytes, &[patch])?
+        }
+        None => NESFile::from_file(rom_path)?,
+    };
+    match nes.game() {
+        Some(game) => log::info!("{} ({:?}), {}", game.title, game.region, nes.hash()),
+        None => log::info!("Unknown ROM, {}", nes.hash()),
+    }
+    let cartridge = Rc::new(RefCell::new(CartridgeImpl::new(nes)?));
+    Ok(BatterySave::load(Path::new(rom_path), cartridge)?)
+}
 
 fn main() -> Result<(), core::convert::Infallible> {
+    env_logger::init();
+
+    // 命令行指定了 ROM 时加载卡带，并自动读写同名的 .sav 存档
+    let mut save = match std::env::args().nth(1) {
+        Some(rom_path) => match load_rom(&rom_path) {
+            Ok(save) => Some(save),
+            Err(e) => {
+                eprintln!("Failed to load ROM {}: {}", rom_path, e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
 
     let line_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
@@ -36,7 +81,17 @@ fn main() -> Result<(), core::convert::Infallible> {
     let output_settings = OutputSettingsBuilder::new()
         .theme(BinaryColorTheme::OledBlue)
         .build();
-    Window::new("Hello World", &output_settings).show_static(&display);
+    let mut window = Window::new("Hello World", &output_settings);
+    'running: loop {
+        window.update(&display);
+        if window.events().any(|e| e == SimulatorEvent::Quit) {
+            break 'running;
+        }
+        if let Some(save) = &mut save {
+            save.on_frame();
+        }
+        std::thread::sleep(Duration::from_millis(16));
+    }
 
     Ok(())
 }
@@ -48,8 +48,14 @@ pub struct PpuImpl {
     reg_oam_data: u8,
 }
 
+impl Default for PpuImpl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PpuImpl {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             ppu_bus: None,
             scanline: 0,
@@ -185,6 +191,10 @@ impl Ppu for PpuImpl {
         self.ppu_bus = Some(bus);
     }
 
+    fn position(&self) -> (u16, u16) {
+        (self.scanline, self.cycle)
+    }
+
     fn check_nmi_interrupt(&self) -> bool {
         self.nmi_interrupt
     }
@@ -62,4 +62,4 @@ impl Into<u8> for PpuMaskRegister {
         }
         value
     }
-}
\ No newline at end of file
+}
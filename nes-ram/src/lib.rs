@@ -2,14 +2,26 @@ use nes_base::{Ram, Reader, Writer};
 
 pub struct RamImpl {
     data: Vec<u8>,
+    /// 上次 clear_dirty 之后是否被写入过
+    dirty: bool,
 }
 
 impl RamImpl {
     pub fn new(size: usize) -> Self {
         RamImpl {
             data: vec![0; size],
+            dirty: false,
         }
     }
+
+    /// 上次 clear_dirty 之后是否被写入过，用于判断电池存档是否需要写回
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
 }
 
 impl Reader for RamImpl {
@@ -26,6 +38,7 @@ impl Writer for RamImpl {
     fn write(&mut self, addr: u16, data: u8) {
         if (addr as usize) < self.data.len() {
             self.data[addr as usize] = data;
+            self.dirty = true;
         } else {
             panic!("Write out of bounds: {}", addr);
         }
@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+/// 构造主板，CPU 从 $E000 依次写入寄存器后原地循环
+/// 这里用到的 Mapper 都把 PRG-ROM 的最后 8KB 固定在 $E000
+fn board_with_writes(
+    mapper_id: u8,
+    prg_banks: u8,
+    chr_banks: u8,
+    writes: &[(u16, u8)],
+) -> BoardImpl {
+    let mut code = vec![];
+    for &(addr, value) in writes {
+        // LDA #value; STA addr
+        code.extend([0xA9, value, 0x8D, addr as u8, (addr >> 8) as u8]);
+    }
+    let end = 0xE000 + code.len() as u16;
+    code.extend([0x4C, end as u8, (end >> 8) as u8]);
+
+    let mut rom = build_ines(mapper_id, prg_banks, chr_banks, 0);
+    let last_bank = 16 + prg_banks as usize * 0x4000 - 0x2000;
+    rom[last_bank..last_bank + code.len()].copy_from_slice(&code);
+    rom[last_bank + 0x1FFC..last_bank + 0x1FFE].copy_from_slice(&[0x00, 0xE0]);
+
+    // 测试并行运行，每个镜像使用不同的临时文件
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let path =
+        std::env::temp_dir().join(format!("nes-test-audio-{}-{}.nes", std::process::id(), id));
+    std::fs::write(&path, rom).unwrap();
+    let board = board_from_file(path.to_str().unwrap(), Rc::new(RefCell::new(MockPPU)));
+    std::fs::remove_file(&path).unwrap();
+    board
+}
+
+/// 运行若干个 CPU 周期，取走这段时间主板输出的采样，丢掉包含程序执行过程的第一个采样
+fn run_samples(board: &mut BoardImpl, cycles: usize) -> Vec<f32> {
+    for _ in 0..cycles {
+        board.clock();
+    }
+    board.audio.borrow_mut().take_samples().split_off(1)
+}
+
+#[test]
+fn test_audio_output_sample_rate() {
+    let mut board = board_with_writes(5, 2, 1, &[]);
+    assert_eq!(board.audio.borrow().sample_rate(), 44_100);
+    // 一帧约 29780 个 CPU 周期
+    let samples = run_samples(&mut board, 29780);
+    assert_eq!(samples.len() + 1, 29780 * 44_100 / 1_789_773);
+    assert!(samples.iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn test_board_mixes_mmc5_audio() {
+    // PCM 声道直接写入输出值
+    let mut board = board_with_writes(5, 2, 1, &[(0x5011, 0x80)]);
+    let samples = run_samples(&mut board, 0x1000);
+    assert!(board.audio_output() > 0.0);
+    assert!(samples.iter().all(|&sample| sample > 0.0));
+
+    // 方波声道 1
+    let mut board = board_with_writes(
+        5,
+        2,
+        1,
+        &[
+            (0x5015, 0x01),
+            (0x5000, 0xBF),
+            (0x5002, 0x10),
+            (0x5003, 0x08),
+        ],
+    );
+    let samples = run_samples(&mut board, 0x1000);
+    assert!(samples.iter().any(|&sample| sample > 0.0));
+    assert!(samples.contains(&0.0));
+}
+
+#[test]
+fn test_board_mixes_vrc6_audio() {
+    // 方波 1 忽略占空比，直接输出音量
+    let mut board = board_with_writes(24, 4, 2, &[(0x9000, 0x8F), (0x9002, 0x80)]);
+    let samples = run_samples(&mut board, 0x1000);
+    assert!(board.audio_output() > 0.0);
+    assert!(samples.iter().all(|&sample| sample > 0.0));
+}
+
+#[test]
+fn test_board_mixes_namco163_audio() {
+    let mut writes = vec![
+        // 波形: 长度为 4 的方波
+        (0xF800, 0x80),
+        (0x4800, 0xFF),
+        (0x4800, 0x00),
+        // 声道 7: 每四次更新前进一个采样，音量 15
+        (0xF800, 0xC0 | 0x38),
+    ];
+    for value in [0x00, 0x00, 0x40, 0x00, 0xFC, 0x00, 0x00, 0x0F] {
+        writes.push((0x4800, value));
+    }
+    let mut board = board_with_writes(19, 4, 2, &writes);
+    let samples = run_samples(&mut board, 0x1000);
+    assert!(samples.iter().any(|&sample| sample > 0.0));
+    assert!(samples.iter().any(|&sample| sample < 0.0));
+}
+
+#[test]
+fn test_board_mixes_sunsoft5b_audio() {
+    // 只启用声道 A 的方波，周期 0x40
+    let mut writes = vec![];
+    for (register, value) in [(0x7, 0x3E), (0x8, 0x0F), (0x0, 0x40)] {
+        writes.push((0xC000, register));
+        writes.push((0xE000, value));
+    }
+    let mut board = board_with_writes(69, 4, 2, &writes);
+    let samples = run_samples(&mut board, 0x2000);
+    assert!(samples.iter().any(|&sample| sample > 0.0));
+    assert!(samples.contains(&0.0));
+}
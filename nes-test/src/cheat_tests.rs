@@ -0,0 +1,114 @@
+use nes_board::{CheatKind, Cheats};
+
+use super::*;
+
+#[test]
+fn test_cheat_decode() {
+    assert_eq!(
+        CheatKind::parse("SXIOPO").unwrap(),
+        CheatKind::Rom {
+            address: 0x91D9,
+            value: 0xAD,
+            compare: None
+        }
+    );
+    assert_eq!(
+        CheatKind::parse("zexpygla").unwrap(),
+        CheatKind::Rom {
+            address: 0x94A7,
+            value: 0x02,
+            compare: Some(0x03)
+        }
+    );
+    assert_eq!(
+        CheatKind::parse("0075FF").unwrap(),
+        CheatKind::Ram {
+            address: 0x0075,
+            value: 0xFF
+        }
+    );
+    assert_eq!(
+        CheatKind::parse("C000?4C:EA").unwrap(),
+        CheatKind::Rom {
+            address: 0xC000,
+            value: 0xEA,
+            compare: Some(0x4C)
+        }
+    );
+    assert!(CheatKind::parse("SXIOP").is_err());
+    assert!(CheatKind::parse("6000:01").is_err());
+    assert!(CheatKind::parse("0075:1FF").is_err());
+}
+
+#[test]
+fn test_cheat_rom_patch() {
+    let board = new_board();
+    let original = board.cpu_bus.borrow().read(0xC000);
+    assert_eq!(original, 0x4C);
+
+    let mut cheats = board.cheats.borrow_mut();
+    cheats.add("C000:EA", "NOP").unwrap();
+    cheats.add("C001?00:12", "不匹配").unwrap();
+    drop(cheats);
+    assert_eq!(board.cpu_bus.borrow().read(0xC000), 0xEA);
+    assert_eq!(
+        board.cpu_bus.borrow().read(0xC001),
+        board.cartridge.borrow().cpu_read(0xC001)
+    );
+
+    board.cheats.borrow_mut().set_enabled(0, false);
+    assert_eq!(board.cpu_bus.borrow().read(0xC000), original);
+    assert!(!board.cheats.borrow_mut().set_enabled(5, true));
+}
+
+#[test]
+fn test_cheat_ram_freeze() {
+    let mut board = new_board();
+    board.cheats.borrow_mut().add("0075:09", "").unwrap();
+    board.cpu_bus.borrow_mut().write(0x0075, 0x01);
+    board.apply_ram_cheats();
+    assert_eq!(board.cpu_bus.borrow().read(0x0075), 0x09);
+
+    board.cheats.borrow_mut().remove(0).unwrap();
+    board.cpu_bus.borrow_mut().write(0x0075, 0x01);
+    board.apply_ram_cheats();
+    assert_eq!(board.cpu_bus.borrow().read(0x0075), 0x01);
+}
+
+#[test]
+fn test_cheat_ram_freeze_each_frame() {
+    let mut board = board_from_file(
+        "testfiles/nestest.nes",
+        Rc::new(RefCell::new(nes_ppu::PpuImpl::new())),
+    );
+    let ppu = board.ppu.clone();
+    board.cheats.borrow_mut().add("0075:09", "").unwrap();
+    // 复位后 PPU 停在 VBlank 之前，先离开 VBlank 所在的扫描线
+    while ppu.borrow().position().0 <= 241 {
+        board.clock();
+    }
+
+    // 帧中途修改的值保留到这一帧结束
+    board.cpu_bus.borrow_mut().write(0x0075, 0x01);
+    board.clock();
+    assert_eq!(board.cpu_bus.borrow().read(0x0075), 0x01);
+
+    while ppu.borrow().position().0 != 241 {
+        board.clock();
+    }
+    assert_eq!(board.cpu_bus.borrow().read(0x0075), 0x09);
+}
+
+#[test]
+fn test_cheat_load_text() {
+    let mut cheats = Cheats::new();
+    let text = "# Super Mario Bros.\n\nSXIOPO 无限生命\n0075FF\n";
+    assert_eq!(cheats.load_str(text).unwrap(), 2);
+    let list = cheats.list();
+    assert_eq!(list[0].code, "SXIOPO");
+    assert_eq!(list[0].description, "无限生命");
+    assert!(list[1].enabled);
+
+    assert!(cheats.load_str("NOTACODE!").is_err());
+    assert!(cheats.load_file("testfiles/not_exists.cht").is_err());
+}
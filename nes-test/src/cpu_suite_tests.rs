@@ -0,0 +1,205 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use nes_cpu::CpuVariant;
+use nes_ppu::PpuImpl;
+
+use crate::{
+    MockPPU, board_from_file, build_ines,
+    cpu_suites::{
+        BlarggOutcome, FunctionalOutcome, OpcodeReport, SingleStepReport, SingleStepTest,
+        run_blargg_rom, run_functional_test,
+    },
+};
+
+/// 比较 A 是否为 1，相等时停在 $0409，否则停在 $0406
+fn trap_program(value: u8) -> Vec<u8> {
+    let mut image = vec![0; 0x0400];
+    image.extend_from_slice(&[
+        0xa9, value, // LDA #value
+        0xc9, 0x01, // CMP #$01
+        0xf0, 0x03, // BEQ $0409
+        0x4c, 0x06, 0x04, // JMP $0406  失败
+        0x4c, 0x09, 0x04, // JMP $0409  成功
+    ]);
+    image
+}
+
+#[test]
+fn test_functional_test_runner_detects_traps() {
+    let outcome = run_functional_test(CpuVariant::Nmos6502, &trap_program(1), 0x0400, 0x0409, 100);
+    assert_eq!(
+        outcome,
+        FunctionalOutcome::Passed {
+            instructions: 4,
+            cycles: 10
+        }
+    );
+
+    let outcome = run_functional_test(CpuVariant::Nmos6502, &trap_program(2), 0x0400, 0x0409, 100);
+    assert!(matches!(
+        outcome,
+        FunctionalOutcome::Trapped { pc: 0x0406, .. }
+    ));
+
+    // 死循环但 PC 不停在同一条指令上
+    let image = [0xea, 0x4c, 0x00, 0x00]; // NOP; JMP $0000
+    let outcome = run_functional_test(CpuVariant::Nmos6502, &image, 0x0000, 0x0409, 100);
+    assert!(matches!(outcome, FunctionalOutcome::Timeout { .. }));
+}
+
+/// LDA #$8E 和 INC $10 两个用例，格式与 SingleStepTests 相同
+const SINGLE_STEP_JSON: &str = r#"[
+    {
+        "name": "a9 8e 00",
+        "initial": { "pc": 4096, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                     "ram": [[4096, 169], [4097, 142]] },
+        "final":   { "pc": 4098, "s": 253, "a": 142, "x": 0, "y": 0, "p": 164,
+                     "ram": [[4096, 169], [4097, 142]] },
+        "cycles": [[4096, 169, "read"], [4097, 142, "read"]]
+    },
+    {
+        "name": "e6 10 00",
+        "initial": { "pc": 512, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                     "ram": [[512, 230], [513, 16], [16, 5]] },
+        "final":   { "pc": 514, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                     "ram": [[512, 230], [513, 16], [16, 6]] },
+        "cycles": [[512, 230, "read"], [513, 16, "read"], [16, 5, "read"],
+                   [16, 5, "write"], [16, 6, "write"]]
+    }
+]"#;
+
+#[test]
+fn test_single_step_vectors() {
+    let tests = SingleStepTest::parse_all(SINGLE_STEP_JSON).unwrap();
+    assert_eq!(tests.len(), 2);
+    for test in &tests {
+        assert_eq!(test.run(CpuVariant::Nmos6502), Ok(()), "{}", test.name);
+    }
+
+    // 65C02 的读改写指令不写回原值，逐周期比较能发现差异
+    let err = tests[1].run(CpuVariant::Cmos65C02).unwrap_err();
+    assert!(err.starts_with("bus cycles"), "{err}");
+
+    let mut wrong = tests[0].clone();
+    wrong.expected.a = 0x8f;
+    let report = OpcodeReport::run(CpuVariant::Nmos6502, 0xa9, &[tests[0].clone(), wrong]);
+    assert_eq!((report.passed, report.failed), (1, 1));
+    let (name, reason) = report.first_failure.unwrap();
+    assert_eq!(name, "a9 8e 00");
+    assert_eq!(reason, "a: expected 0x8f, got 0x8e");
+}
+
+#[test]
+fn test_single_step_report_for_directory() {
+    let dir = std::env::temp_dir().join(format!("nes-test-single-step-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a9.json"), SINGLE_STEP_JSON).unwrap();
+    std::fs::write(dir.join("README.md"), "not a test").unwrap();
+
+    let report = SingleStepReport::run_dir(CpuVariant::Cmos65C02, &dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.opcodes.len(), 1);
+    assert!(!report.all_passed());
+    assert_eq!(report.failed_opcodes(), [0xa9]);
+    let text = report.to_string();
+    assert!(
+        text.starts_with("a9      1/2      FAIL [e6 10 00] bus cycles"),
+        "{text}"
+    );
+    assert!(text.ends_with("1 opcodes, 0 passed, 1 failed"), "{text}");
+}
+
+#[test]
+fn test_blargg_result_protocol() {
+    let mut rom = build_ines(0, 1, 1, 0x02);
+    let code = [
+        0xa2, 0x00, // LDX #$00
+        0xbd, 0x10, 0x80, // LDA $8010,X
+        0x9d, 0x00, 0x60, // STA $6000,X
+        0xe8, // INX
+        0xe0, 0x07, // CPX #$07
+        0xd0, 0xf5, // BNE $8002
+        0x4c, 0x0d, 0x80, // JMP $800D
+    ];
+    let result = [0x00, 0xde, 0xb0, 0x61, b'o', b'k', 0x00];
+    rom[16..16 + code.len()].copy_from_slice(&code);
+    rom[16 + 0x10..16 + 0x10 + result.len()].copy_from_slice(&result);
+    rom[16 + 0x3ffc..16 + 0x3ffe].copy_from_slice(&[0x00, 0x80]);
+
+    let path = std::env::temp_dir().join(format!("nes-test-blargg-{}.nes", std::process::id()));
+    std::fs::write(&path, rom).unwrap();
+    let mut board = board_from_file(path.to_str().unwrap(), Rc::new(RefCell::new(MockPPU)));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        run_blargg_rom(&mut board, 100_000),
+        BlarggOutcome::Passed("ok".to_string())
+    );
+}
+
+// 以下测试需要自行下载测试数据，放到 testfiles 目录后用 `cargo test -- --ignored` 运行
+
+#[test]
+#[ignore = "needs testfiles/6502_functional_test.bin"]
+fn test_klaus_dormann_functional() {
+    let image = std::fs::read("testfiles/6502_functional_test.bin").unwrap();
+    // 默认配置下代码从 $0400 开始，全部通过后停在 $3469
+    let outcome = run_functional_test(CpuVariant::Nmos6502, &image, 0x0400, 0x3469, 100_000_000);
+    assert!(
+        matches!(outcome, FunctionalOutcome::Passed { .. }),
+        "{outcome:?}"
+    );
+}
+
+#[test]
+#[ignore = "needs testfiles/65x02 from SingleStepTests"]
+fn test_tom_harte_single_step() {
+    let suites = [
+        ("testfiles/65x02/nes6502/v1", CpuVariant::Ricoh2A03),
+        ("testfiles/65x02/6502/v1", CpuVariant::Nmos6502),
+        ("testfiles/65x02/rockwell65c02/v1", CpuVariant::Cmos65C02),
+    ];
+    let mut failed = Vec::new();
+    let mut ran = 0;
+    for (dir, variant) in suites {
+        let dir = Path::new(dir);
+        if !dir.exists() {
+            continue;
+        }
+        ran += 1;
+        let report = SingleStepReport::run_dir(variant, dir).unwrap();
+        println!("{}:\n{report}", dir.display());
+        if !report.all_passed() {
+            failed.push(dir.display().to_string());
+        }
+    }
+    assert!(
+        ran > 0,
+        "no SingleStepTests suite found under testfiles/65x02"
+    );
+    assert!(failed.is_empty(), "failed suites: {failed:?}");
+}
+
+#[test]
+#[ignore = "needs testfiles/cpu_interrupts_v2 from blargg's test ROMs"]
+fn test_blargg_cpu_interrupts() {
+    let roms = [
+        "1-cli_latency",
+        "2-nmi_and_brk",
+        "3-nmi_and_irq",
+        "4-irq_and_dma",
+        "5-branch_delays_irq",
+    ];
+    let mut failed = Vec::new();
+    for name in roms {
+        let path = format!("testfiles/cpu_interrupts_v2/rom_singles/{name}.nes");
+        let mut board = board_from_file(&path, Rc::new(RefCell::new(PpuImpl::new())));
+        let outcome = run_blargg_rom(&mut board, 100_000_000);
+        println!("{name}: {outcome:?}");
+        if !matches!(outcome, BlarggOutcome::Passed(_)) {
+            failed.push(name);
+        }
+    }
+    assert!(failed.is_empty(), "failed roms: {failed:?}");
+}
@@ -0,0 +1,405 @@
+//! 第三方 6502 测试集的运行器
+//!
+//! - Klaus Dormann 的 6502_functional_test：整块 64K 镜像装入平坦 RAM，程序出错或完成时都会原地死循环
+//! - Tom Harte 的 SingleStepTests (65x02)：每个操作码一个 JSON 文件，给出初始/最终状态与逐周期的总线访问
+//! - blargg 的 NES 测试 ROM（如 cpu_interrupts_v2）：在整机上运行，结果写在 $6000 开始的 PRG-RAM 中
+//!
+//! see: https://github.com/Klaus2m5/6502_65C02_functional_tests
+//! see: https://github.com/SingleStepTests/65x02
+//! see: https://github.com/christopherpow/nes-test-roms
+
+use std::{cell::RefCell, fmt, fs, io, path::Path, rc::Rc};
+
+use nes_base::{BusAdapter, Cartridge, Cpu, Interrupt, Reader, Writer};
+use nes_board::BoardImpl;
+use nes_cpu::{CpuImpl, CpuVariant};
+use serde::Deserialize;
+
+/// 一次总线访问：(地址, 数据, 读/写)，与 SingleStepTests 的 cycles 字段格式相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BusCycle(pub u16, pub u8, pub BusCycleKind);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusCycleKind {
+    Read,
+    Write,
+}
+
+/// 64K 平坦 RAM，没有任何 I/O 映射，可选记录每次访问
+pub struct FlatRam {
+    memory: Vec<u8>,
+    log: Option<RefCell<Vec<BusCycle>>>,
+}
+
+impl FlatRam {
+    /// 从 $0000 开始装入镜像，不足 64K 的部分填 0
+    pub fn new(image: &[u8]) -> Self {
+        let mut memory = vec![0; 0x10000];
+        let len = image.len().min(memory.len());
+        memory[..len].copy_from_slice(&image[..len]);
+        Self { memory, log: None }
+    }
+
+    /// 开始记录总线访问
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(RefCell::new(Vec::new()));
+        self
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// 取出目前为止的访问记录
+    pub fn take_log(&self) -> Vec<BusCycle> {
+        self.log.as_ref().map(RefCell::take).unwrap_or_default()
+    }
+
+    fn record(&self, cycle: BusCycle) {
+        if let Some(log) = &self.log {
+            log.borrow_mut().push(cycle);
+        }
+    }
+}
+
+impl Reader for FlatRam {
+    fn read(&self, addr: u16) -> u8 {
+        let data = self.memory[addr as usize];
+        self.record(BusCycle(addr, data, BusCycleKind::Read));
+        data
+    }
+}
+
+impl Writer for FlatRam {
+    fn write(&mut self, addr: u16, data: u8) {
+        self.record(BusCycle(addr, data, BusCycleKind::Write));
+        self.memory[addr as usize] = data;
+    }
+}
+
+impl BusAdapter for FlatRam {
+    fn address_accept(&self, _addr: u16) -> bool {
+        true
+    }
+}
+
+/// 执行到下一个指令边界
+fn step(cpu: &mut CpuImpl) {
+    loop {
+        cpu.clock();
+        if cpu.dump_state().remaining_cycles == 0 {
+            break;
+        }
+    }
+}
+
+/// 功能测试的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionalOutcome {
+    /// 停在成功地址
+    Passed { instructions: u64, cycles: u64 },
+    /// 停在其他地址，对照测试源码的列表文件即可找到失败的用例
+    Trapped {
+        pc: u16,
+        instructions: u64,
+        cycles: u64,
+    },
+    /// 超过指令数上限仍未停下
+    Timeout { pc: u16 },
+}
+
+/// 运行 Klaus Dormann 的功能测试
+///
+/// `image` 装入 $0000，从 `start` 开始执行，PC 在一条指令后保持不变即视为停下，
+/// 停在 `success` 为通过。
+pub fn run_functional_test(
+    variant: CpuVariant,
+    image: &[u8],
+    start: u16,
+    success: u16,
+    max_instructions: u64,
+) -> FunctionalOutcome {
+    let bus = Rc::new(RefCell::new(FlatRam::new(image)));
+    let mut cpu = CpuImpl::with_variant(variant);
+    cpu.attach_bus(bus);
+    cpu.set_reg_pc(start);
+
+    // total_cycles 只有 32 位，整个测试要运行约 1 亿周期，这里单独累计
+    let mut cycles = 0u64;
+    for executed in 1..=max_instructions {
+        let before = cpu.dump_state();
+        step(&mut cpu);
+        let after = cpu.dump_state();
+        cycles += after.total_cycles.wrapping_sub(before.total_cycles) as u64;
+        if after.reg_pc == before.reg_pc {
+            let pc = after.reg_pc;
+            return if pc == success {
+                FunctionalOutcome::Passed {
+                    instructions: executed,
+                    cycles,
+                }
+            } else {
+                FunctionalOutcome::Trapped {
+                    pc,
+                    instructions: executed,
+                    cycles,
+                }
+            };
+        }
+    }
+    FunctionalOutcome::Timeout {
+        pc: cpu.dump_state().reg_pc,
+    }
+}
+
+/// SingleStepTests 中的 CPU 状态
+#[derive(Debug, Clone, Deserialize)]
+pub struct CpuSnapshot {
+    pub pc: u16,
+    pub s: u8,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub ram: Vec<(u16, u8)>,
+}
+
+/// SingleStepTests 中的一个用例，执行一条指令
+#[derive(Debug, Clone, Deserialize)]
+pub struct SingleStepTest {
+    pub name: String,
+    pub initial: CpuSnapshot,
+    #[serde(rename = "final")]
+    pub expected: CpuSnapshot,
+    pub cycles: Vec<BusCycle>,
+}
+
+impl SingleStepTest {
+    /// 解析一个操作码的 JSON 文件
+    pub fn parse_all(json: &str) -> serde_json::Result<Vec<SingleStepTest>> {
+        serde_json::from_str(json)
+    }
+
+    /// 执行用例，返回第一处不一致的描述
+    pub fn run(&self, variant: CpuVariant) -> Result<(), String> {
+        let bus = Rc::new(RefCell::new(FlatRam::new(&[]).with_log()));
+        for &(addr, data) in &self.initial.ram {
+            bus.borrow_mut().memory_mut()[addr as usize] = data;
+        }
+
+        let mut cpu = CpuImpl::with_variant(variant);
+        cpu.attach_bus(bus.clone());
+        cpu.set_reg_pc(self.initial.pc);
+        let mut state = cpu.dump_state();
+        state.reg_a = self.initial.a;
+        state.reg_x = self.initial.x;
+        state.reg_y = self.initial.y;
+        state.reg_sp = self.initial.s;
+        state.reg_status = self.initial.p.into();
+        cpu.set_registers(&state);
+
+        step(&mut cpu);
+
+        let state = cpu.dump_state();
+        let expected = &self.expected;
+        // B 与第 5 位在芯片上并不存在，不参与比较
+        let status: u8 = state.reg_status.into();
+        let registers = [
+            ("pc", state.reg_pc, expected.pc),
+            ("s", state.reg_sp as u16, expected.s as u16),
+            ("a", state.reg_a as u16, expected.a as u16),
+            ("x", state.reg_x as u16, expected.x as u16),
+            ("y", state.reg_y as u16, expected.y as u16),
+            ("p", (status | 0x30) as u16, (expected.p | 0x30) as u16),
+        ];
+        for (name, actual, wanted) in registers {
+            if actual != wanted {
+                return Err(format!("{name}: expected {wanted:#x}, got {actual:#x}"));
+            }
+        }
+
+        let bus = bus.borrow();
+        for &(addr, wanted) in &expected.ram {
+            let actual = bus.memory()[addr as usize];
+            if actual != wanted {
+                return Err(format!(
+                    "ram[{addr:#06x}]: expected {wanted:#04x}, got {actual:#04x}"
+                ));
+            }
+        }
+
+        let log = bus.take_log();
+        if log != self.cycles {
+            return Err(format!(
+                "bus cycles: expected {:?}, got {:?}",
+                self.cycles, log
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 一个操作码的通过情况
+#[derive(Debug, Clone)]
+pub struct OpcodeReport {
+    pub opcode: u8,
+    pub passed: usize,
+    pub failed: usize,
+    /// 第一个失败用例的名字和原因
+    pub first_failure: Option<(String, String)>,
+}
+
+impl OpcodeReport {
+    pub fn run(variant: CpuVariant, opcode: u8, tests: &[SingleStepTest]) -> Self {
+        let mut report = Self {
+            opcode,
+            passed: 0,
+            failed: 0,
+            first_failure: None,
+        };
+        for test in tests {
+            match test.run(variant) {
+                Ok(()) => report.passed += 1,
+                Err(reason) => {
+                    report.failed += 1;
+                    report
+                        .first_failure
+                        .get_or_insert_with(|| (test.name.clone(), reason));
+                }
+            }
+        }
+        report
+    }
+}
+
+/// 整个测试目录的逐操作码报告
+#[derive(Debug, Clone, Default)]
+pub struct SingleStepReport {
+    pub opcodes: Vec<OpcodeReport>,
+}
+
+impl SingleStepReport {
+    /// 运行目录下所有 `xx.json`，文件名为十六进制操作码
+    pub fn run_dir(variant: CpuVariant, dir: &Path) -> io::Result<Self> {
+        let mut files: Vec<(u8, std::path::PathBuf)> = fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let opcode = u8::from_str_radix(path.file_stem()?.to_str()?, 16).ok()?;
+                (path.extension()? == "json").then_some((opcode, path))
+            })
+            .collect();
+        files.sort();
+
+        let mut report = Self::default();
+        for (opcode, path) in files {
+            let json = fs::read_to_string(&path)?;
+            let tests = SingleStepTest::parse_all(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            report
+                .opcodes
+                .push(OpcodeReport::run(variant, opcode, &tests));
+        }
+        Ok(report)
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.opcodes.iter().all(|op| op.failed == 0)
+    }
+
+    pub fn failed_opcodes(&self) -> Vec<u8> {
+        self.opcodes
+            .iter()
+            .filter(|op| op.failed > 0)
+            .map(|op| op.opcode)
+            .collect()
+    }
+}
+
+impl fmt::Display for SingleStepReport {
+    /// 每个操作码一行，失败的操作码附带第一个失败用例
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for op in &self.opcodes {
+            let total = op.passed + op.failed;
+            write!(f, "{:02x}  {:>5}/{:<5}", op.opcode, op.passed, total)?;
+            match &op.first_failure {
+                None => writeln!(f, "  ok")?,
+                Some((name, reason)) => writeln!(f, "  FAIL [{name}] {reason}")?,
+            }
+        }
+        let failed = self.failed_opcodes().len();
+        write!(
+            f,
+            "{} opcodes, {} passed, {} failed",
+            self.opcodes.len(),
+            self.opcodes.len() - failed,
+            failed
+        )
+    }
+}
+
+/// blargg 测试 ROM 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlarggOutcome {
+    /// 结果码为 0
+    Passed(String),
+    /// 其他结果码，附带 ROM 输出的文本
+    Failed { code: u8, text: String },
+    /// 超过周期上限仍在运行
+    Timeout,
+}
+
+/// 复位请求后等待约 100ms 再复位
+const BLARGG_RESET_DELAY: u64 = 180_000;
+
+/// 运行 blargg 的测试 ROM
+///
+/// $6001-$6003 为 DE B0 61 时 $6000 有效：$80 表示仍在运行，$81 表示需要按下复位，
+/// 其他值为结果码；$6004 起是以 0 结尾的结果文本。
+pub fn run_blargg_rom(board: &mut BoardImpl, max_cycles: u64) -> BlarggOutcome {
+    let mut reset_at = None;
+    for cycle in 0..max_cycles {
+        board.clock();
+        if reset_at == Some(cycle) {
+            reset_at = None;
+            board.cpu.borrow_mut().trigger_interrupt(Interrupt::Reset);
+        }
+        // 大约每帧检查一次
+        if cycle % 30_000 != 0 {
+            continue;
+        }
+
+        let cartridge = board.cartridge.borrow();
+        let signature = [0x6001, 0x6002, 0x6003].map(|addr| cartridge.cpu_read(addr));
+        if signature != [0xDE, 0xB0, 0x61] {
+            continue;
+        }
+        match cartridge.cpu_read(0x6000) {
+            0x80 => {}
+            0x81 => {
+                reset_at.get_or_insert(cycle + BLARGG_RESET_DELAY);
+            }
+            code => {
+                let text = blargg_text(&*cartridge);
+                return if code == 0 {
+                    BlarggOutcome::Passed(text)
+                } else {
+                    BlarggOutcome::Failed { code, text }
+                };
+            }
+        }
+    }
+    BlarggOutcome::Timeout
+}
+
+fn blargg_text(cartridge: &dyn Cartridge) -> String {
+    let bytes: Vec<u8> = (0x6004..0x8000)
+        .map(|addr| cartridge.cpu_read(addr))
+        .take_while(|&byte| byte != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).trim_end().to_string()
+}
@@ -0,0 +1,439 @@
+use nes_base::{BusAdapter, Cpu, Interrupt, Reader, Writer};
+use nes_cpu::CpuVariant;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BusAccess {
+    Read(u16),
+    Write(u16, u8),
+}
+
+/// 64K 平坦内存，记录 CPU 每一次总线访问
+struct RecordingBus {
+    memory: Vec<u8>,
+    log: RefCell<Vec<BusAccess>>,
+}
+
+impl RecordingBus {
+    fn new(program: &[u8], origin: u16) -> Self {
+        let mut memory = vec![0; 0x10000];
+        memory[origin as usize..origin as usize + program.len()].copy_from_slice(program);
+        Self {
+            memory,
+            log: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Reader for RecordingBus {
+    fn read(&self, addr: u16) -> u8 {
+        self.log.borrow_mut().push(BusAccess::Read(addr));
+        self.memory[addr as usize]
+    }
+}
+
+impl Writer for RecordingBus {
+    fn write(&mut self, addr: u16, data: u8) {
+        self.log.borrow_mut().push(BusAccess::Write(addr, data));
+        self.memory[addr as usize] = data;
+    }
+}
+
+impl BusAdapter for RecordingBus {
+    fn address_accept(&self, _addr: u16) -> bool {
+        true
+    }
+}
+
+/// IRQ/BRK 处理程序地址
+const IRQ_HANDLER: u16 = 0x0300;
+/// NMI 处理程序地址
+const NMI_HANDLER: u16 = 0x0400;
+
+/// 程序从 $0200 开始，两个中断处理程序都是 NOP
+fn new_cpu(program: &[u8]) -> (CpuImpl, Rc<RefCell<RecordingBus>>) {
+    new_variant_cpu(CpuVariant::Ricoh2A03, program)
+}
+
+fn new_variant_cpu(variant: CpuVariant, program: &[u8]) -> (CpuImpl, Rc<RefCell<RecordingBus>>) {
+    let bus = Rc::new(RefCell::new(RecordingBus::new(program, 0x0200)));
+    {
+        let mut bus = bus.borrow_mut();
+        bus.memory[0xfffa..=0xfffb].copy_from_slice(&NMI_HANDLER.to_le_bytes());
+        bus.memory[0xfffe..=0xffff].copy_from_slice(&IRQ_HANDLER.to_le_bytes());
+        bus.memory[IRQ_HANDLER as usize] = 0xea;
+        bus.memory[NMI_HANDLER as usize] = 0xea;
+    }
+    let mut cpu = CpuImpl::with_variant(variant);
+    cpu.attach_bus(bus.clone());
+    cpu.set_reg_pc(0x0200);
+    (cpu, bus)
+}
+
+/// 执行一条指令或一次中断响应序列，检查每个周期恰好有一次总线访问
+fn step(cpu: &mut CpuImpl, bus: &Rc<RefCell<RecordingBus>>) {
+    loop {
+        let before = bus.borrow().log.borrow().len();
+        cpu.clock();
+        assert_eq!(bus.borrow().log.borrow().len(), before + 1);
+        if cpu.dump_state().remaining_cycles == 0 {
+            break;
+        }
+    }
+}
+
+/// 执行 count 条指令，返回全部访问记录
+fn run_instructions(program: &[u8], count: usize) -> Vec<BusAccess> {
+    let (mut cpu, bus) = new_cpu(program);
+    for _ in 0..count {
+        step(&mut cpu, &bus);
+    }
+    bus.borrow().log.take()
+}
+
+/// 中断压栈的返回地址与状态寄存器
+fn pushed_frame(cpu: &CpuImpl, bus: &Rc<RefCell<RecordingBus>>) -> (u16, u8) {
+    let sp = cpu.dump_state().reg_sp as usize;
+    let memory = &bus.borrow().memory;
+    let pc = u16::from_le_bytes([memory[0x0102 + sp], memory[0x0103 + sp]]);
+    (pc, memory[0x0101 + sp])
+}
+
+#[test]
+fn test_cycle_accesses_for_rmw_and_indexed() {
+    use BusAccess::*;
+    let program: &[u8] = &[
+        0xa2, 0x20, // LDX #$20
+        0xe6, 0x10, // INC $10
+        0xbd, 0xf0, 0x20, // LDA $20F0,X  -> 跨页
+        0x9d, 0x00, 0x03, // STA $0300,X
+    ];
+    let log = run_instructions(program, 4);
+    assert_eq!(
+        log[2..],
+        [
+            // INC $10: 读出原值后先写回原值，再写入新值
+            Read(0x0202),
+            Read(0x0203),
+            Read(0x0010),
+            Write(0x0010, 0x00),
+            Write(0x0010, 0x01),
+            // LDA $20F0,X: 跨页时先读未修正高字节的地址
+            Read(0x0204),
+            Read(0x0205),
+            Read(0x0206),
+            Read(0x2010),
+            Read(0x2110),
+            // STA $0300,X: 写指令总是先空读一次
+            Read(0x0207),
+            Read(0x0208),
+            Read(0x0209),
+            Read(0x0320),
+            Write(0x0320, 0x00),
+        ]
+    );
+}
+
+#[test]
+fn test_cycle_accesses_for_branch_and_jsr() {
+    use BusAccess::*;
+    let program: &[u8] = &[
+        0x18, // CLC
+        0x90, 0xf0, // BCC -16   -> 跨页到 $01F3
+    ];
+    let log = run_instructions(program, 2);
+    assert_eq!(
+        log[2..],
+        [
+            Read(0x0201),
+            Read(0x0202),
+            Read(0x0203),
+            // 先只修正 PC 低字节，跨页时再空读一次
+            Read(0x02f3),
+        ]
+    );
+
+    let program: &[u8] = &[
+        0x20, 0x10, 0x02, // JSR $0210
+    ];
+    let log = run_instructions(program, 1);
+    assert_eq!(
+        log,
+        [
+            Read(0x0200),
+            Read(0x0201),
+            Read(0x01fd),
+            Write(0x01fd, 0x02),
+            Write(0x01fc, 0x02),
+            Read(0x0202),
+        ]
+    );
+}
+
+#[test]
+fn test_irq_cli_and_sei_latency() {
+    let program: &[u8] = &[
+        0x58, // CLI
+        0xea, // NOP
+        0x78, // SEI
+        0xea, // NOP
+    ];
+    let (mut cpu, bus) = new_cpu(program);
+    cpu.set_irq_line(true);
+
+    // CLI 之后还要再执行一条指令才响应 IRQ
+    step(&mut cpu, &bus);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, 0x0202);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, IRQ_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0202, 0x20));
+    assert_eq!(cpu.dump_state().total_cycles, 4 + 7);
+
+    // SEI 之前到来的 IRQ 在 SEI 之后响应，压栈的 I 位已经置位
+    let (mut cpu, bus) = new_cpu(program);
+    step(&mut cpu, &bus);
+    step(&mut cpu, &bus);
+    cpu.set_irq_line(true);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, 0x0203);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, IRQ_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0203, 0x24));
+}
+
+#[test]
+fn test_irq_is_level_triggered() {
+    let program: &[u8] = &[
+        0xea, // NOP
+        0x58, // CLI
+        0xea, // NOP
+        0xea, // NOP
+    ];
+    // 被屏蔽期间一直有效的 IRQ 在 CLI 之后响应
+    let (mut cpu, bus) = new_cpu(program);
+    cpu.trigger_interrupt(Interrupt::Irq);
+    for _ in 0..3 {
+        step(&mut cpu, &bus);
+    }
+    assert_eq!(cpu.dump_state().reg_pc, 0x0203);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, IRQ_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0203, 0x20));
+
+    // 外设在 CLI 之前应答，IRQ 不会被锁存
+    let (mut cpu, bus) = new_cpu(program);
+    cpu.set_irq_line(true);
+    step(&mut cpu, &bus);
+    cpu.set_irq_line(false);
+    for _ in 0..3 {
+        step(&mut cpu, &bus);
+    }
+    assert_eq!(cpu.dump_state().reg_pc, 0x0204);
+}
+
+#[test]
+fn test_nmi_hijacks_brk_and_irq() {
+    // BRK 压栈状态寄存器前到来的 NMI 劫持向量，压栈的 B 位保持为 1
+    let (mut cpu, bus) = new_cpu(&[0x00]);
+    cpu.clock();
+    cpu.clock();
+    cpu.trigger_interrupt(Interrupt::Nmi);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, NMI_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0202, 0x34));
+    assert_eq!(cpu.dump_state().nmi_count, 1);
+    // NMI 已经被 BRK 消耗
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, NMI_HANDLER + 1);
+    assert_eq!(cpu.dump_state().nmi_count, 1);
+
+    // IRQ 响应序列中到来的 NMI 同样劫持向量，压栈的 B 位为 0
+    let (mut cpu, bus) = new_cpu(&[0x58, 0xea]);
+    cpu.set_irq_line(true);
+    step(&mut cpu, &bus);
+    step(&mut cpu, &bus);
+    for _ in 0..3 {
+        cpu.clock();
+    }
+    cpu.trigger_interrupt(Interrupt::Nmi);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, NMI_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0202, 0x20));
+}
+
+#[test]
+fn test_taken_branch_delays_interrupt() {
+    let program: &[u8] = &[
+        0x58, // CLI
+        0xd0, 0x00, // BNE +0   -> 跳转但不跨页
+        0xea, // NOP
+        0xea, // NOP
+    ];
+    let (mut cpu, bus) = new_cpu(program);
+    step(&mut cpu, &bus);
+    cpu.clock();
+    cpu.clock();
+    // 分支最后一个周期到来的 IRQ 推迟到下一条指令之后
+    cpu.set_irq_line(true);
+    step(&mut cpu, &bus);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, 0x0204);
+    step(&mut cpu, &bus);
+    assert_eq!(cpu.dump_state().reg_pc, IRQ_HANDLER);
+    assert_eq!(pushed_frame(&cpu, &bus), (0x0204, 0x20));
+}
+
+#[test]
+fn test_unstable_unofficial_opcodes() {
+    let program: &[u8] = &[
+        0xa2, 0x0f, // LDX #$0F
+        0xa9, 0x31, // LDA #$31
+        0x8b, 0xff, // XAA #$FF   -> A = (A | $EE) & X & $FF = $0F
+        0xab, 0x53, // LXA #$53   -> A = X = ($0F | $EE) & $53 = $43
+        0xa0, 0x20, // LDY #$20
+        0x9c, 0xf0, 0x12, // SHY $12F0,X -> 跨页，写入 Y & $13 = $00 到 $0033
+        0x9e, 0x00, 0x05, // SHX $0500,Y -> 写入 X & $06 = $02 到 $0520
+        0xbb, 0x00, 0x05, // LAS $0500,Y -> A = X = SP = $02 & $FD
+    ];
+    let (mut cpu, bus) = new_cpu(program);
+    bus.borrow_mut().memory[0x0033] = 0xaa;
+    for _ in 0..4 {
+        step(&mut cpu, &bus);
+    }
+    let state = cpu.dump_state();
+    assert_eq!((state.reg_a, state.reg_x), (0x43, 0x43));
+
+    step(&mut cpu, &bus);
+    step(&mut cpu, &bus);
+    assert_eq!(bus.borrow().memory[0x0033], 0x00);
+    assert_eq!(bus.borrow().memory[0x1333], 0x00);
+
+    step(&mut cpu, &bus);
+    assert_eq!(bus.borrow().memory[0x0520], 0x02);
+
+    step(&mut cpu, &bus);
+    let state = cpu.dump_state();
+    assert_eq!((state.reg_a, state.reg_x, state.reg_sp), (0x00, 0x00, 0x00));
+    assert!(state.reg_status.zero);
+}
+
+#[test]
+fn test_jam_halts_until_reset() {
+    let (mut cpu, bus) = new_cpu(&[0x02, 0xea]);
+    step(&mut cpu, &bus);
+    assert!(cpu.dump_state().jammed);
+
+    // 停机后不再取指，也不响应中断
+    cpu.trigger_interrupt(Interrupt::Nmi);
+    for _ in 0..10 {
+        step(&mut cpu, &bus);
+    }
+    let state = cpu.dump_state();
+    assert!(state.jammed);
+    assert_eq!(state.reg_pc, 0x0201);
+    assert_eq!(
+        bus.borrow().log.borrow().last(),
+        Some(&BusAccess::Read(0xffff))
+    );
+
+    cpu.reset();
+    assert!(!cpu.dump_state().jammed);
+}
+
+#[test]
+fn test_decimal_mode_by_variant() {
+    let program: &[u8] = &[
+        0xf8, // SED
+        0x18, // CLC
+        0xa9, 0x58, // LDA #$58
+        0x69, 0x46, // ADC #$46   -> BCD: $04, C = 1
+        0x18, // CLC
+        0xa9, 0x99, // LDA #$99
+        0x69, 0x01, // ADC #$01   -> BCD: $00, C = 1
+        0x38, // SEC
+        0xa9, 0x10, // LDA #$10
+        0xe9, 0x01, // SBC #$01   -> BCD: $09
+    ];
+    let run = |variant: CpuVariant, count: usize| {
+        let (mut cpu, bus) = new_variant_cpu(variant, program);
+        for _ in 0..count {
+            step(&mut cpu, &bus);
+        }
+        cpu.dump_state()
+    };
+
+    // 2A03 忽略 D 标志
+    let state = run(CpuVariant::Ricoh2A03, 4);
+    assert_eq!((state.reg_a, state.reg_status.carry), (0x9e, false));
+
+    for variant in [CpuVariant::Nmos6502, CpuVariant::Cmos65C02] {
+        let state = run(variant, 4);
+        assert_eq!((state.reg_a, state.reg_status.carry), (0x04, true));
+        let state = run(variant, 10);
+        assert_eq!(state.reg_a, 0x09);
+    }
+
+    // $99 + $01：NMOS 的 Z/N 取自二进制结果和中间结果，65C02 取自十进制结果
+    let state = run(CpuVariant::Nmos6502, 7);
+    assert_eq!(state.reg_a, 0x00);
+    assert!(state.reg_status.carry && !state.reg_status.zero && state.reg_status.negative);
+    let state = run(CpuVariant::Cmos65C02, 7);
+    assert_eq!(state.reg_a, 0x00);
+    assert!(state.reg_status.carry && state.reg_status.zero && !state.reg_status.negative);
+}
+
+#[test]
+fn test_65c02_instructions() {
+    let program: &[u8] = &[
+        0xa9, 0xf0, // LDA #$F0
+        0x85, 0x10, // STA $10
+        0x64, 0x11, // STZ $11
+        0xa9, 0x0f, // LDA #$0F
+        0x04, 0x10, // TSB $10     -> $FF, Z = 1
+        0x14, 0x10, // TRB $10     -> $F0, Z = 0
+        0x77, 0x10, // RMB7 $10    -> $70
+        0x87, 0x10, // SMB0 $10    -> $71
+        0x0f, 0x10, 0x02, // BBR0 $10,+2 -> 不跳转
+        0x8f, 0x10, 0x02, // BBS0 $10,+2 -> 跳转到 $0218
+        0xa9, 0xee, // LDA #$EE (跳过)
+        0xa2, 0x42, // LDX #$42
+        0xda, // PHX
+        0x7a, // PLY
+        0xb2, 0x20, // LDA ($20)
+        0x80, 0x00, // BRA +0
+        0x03, // 单字节 NOP
+        0x7c, 0x00, 0x04, // JMP ($0400,X)
+    ];
+    let (mut cpu, bus) = new_variant_cpu(CpuVariant::Cmos65C02, program);
+    {
+        let mut bus = bus.borrow_mut();
+        bus.memory[0x0011] = 0xaa;
+        bus.memory[0x0020..=0x0021].copy_from_slice(&[0x00, 0x03]);
+        bus.memory[0x0300] = 0x5a;
+        bus.memory[0x0442..=0x0443].copy_from_slice(&[0x50, 0x02]);
+    }
+
+    let mut cycles = Vec::new();
+    let mut zero = Vec::new();
+    for _ in 0..16 {
+        let before = cpu.dump_state().total_cycles;
+        step(&mut cpu, &bus);
+        let state = cpu.dump_state();
+        cycles.push(state.total_cycles - before);
+        zero.push(state.reg_status.zero);
+    }
+    assert_eq!(cycles, [2, 3, 3, 2, 5, 5, 5, 5, 5, 6, 2, 3, 4, 5, 3, 1]);
+    assert_eq!((zero[4], zero[5]), (true, false));
+    assert_eq!(bus.borrow().memory[0x0010], 0x71);
+    assert_eq!(bus.borrow().memory[0x0011], 0x00);
+
+    step(&mut cpu, &bus);
+    let state = cpu.dump_state();
+    assert_eq!(state.total_cycles, cycles.iter().sum::<u32>() + 6);
+    assert_eq!(
+        (state.reg_pc, state.reg_a, state.reg_x, state.reg_y),
+        (0x0250, 0x5a, 0x42, 0x42)
+    );
+}
@@ -0,0 +1,209 @@
+use nes_base::Ppu;
+use nes_debugger::{Access, BusKind, Condition, Debugger, StopReason, WatchHit, Watchpoint};
+
+use super::*;
+
+fn nestest_debugger() -> Debugger {
+    let mut board = new_board();
+    board.reset();
+    board.cpu.borrow_mut().set_reg_pc(0xc000);
+    Debugger::new(board)
+}
+
+#[test]
+fn test_debugger_step_into_over_out() {
+    let mut debugger = nestest_debugger();
+
+    assert_eq!(debugger.step_into(), StopReason::Step);
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc5f5);
+
+    // C5F5 LDX / STX x3 之后是 C5FD JSR $C72D
+    for _ in 0..4 {
+        debugger.step_over();
+    }
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc5fd);
+    assert_eq!(debugger.step_over(), StopReason::Step);
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc600);
+    assert_eq!(debugger.cpu_state().reg_sp, 0xfd);
+
+    // 进入 C600 JSR $C7DB 后跳出
+    debugger.step_into();
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc7db);
+    assert_eq!(debugger.step_out(), StopReason::Step);
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc603);
+    assert_eq!(debugger.cpu_state().total_cycles, 379);
+}
+
+#[test]
+fn test_debugger_conditional_breakpoint() {
+    let mut debugger = nestest_debugger();
+    debugger
+        .add_breakpoint(0xc728, Some("X == 3 && Y >= $50"))
+        .unwrap();
+
+    assert_eq!(debugger.run(), StopReason::Breakpoint(0xc728));
+    let state = debugger.cpu_state();
+    assert_eq!((state.reg_x, state.reg_y, state.reg_sp), (0x03, 0x50, 0xf5));
+
+    assert!(debugger.remove_breakpoint(0xc728));
+    debugger.set_cycle_limit(1000);
+    assert_eq!(debugger.run(), StopReason::CycleLimit);
+
+    assert!(debugger.add_breakpoint(0xc728, Some("X = 3")).is_err());
+    assert!(Condition::parse("Q > 1").is_err());
+}
+
+#[test]
+fn test_debugger_watchpoints() {
+    let mut debugger = nestest_debugger();
+    debugger.add_watchpoint(Watchpoint::new(
+        BusKind::Cpu,
+        0x0010..=0x0011,
+        Access::Write,
+    ));
+
+    // C5F9 STX $10
+    assert_eq!(
+        debugger.run(),
+        StopReason::Watchpoint(WatchHit {
+            index: 0,
+            bus: BusKind::Cpu,
+            address: 0x0010,
+            value: 0x00,
+            access: Access::Write,
+        })
+    );
+    assert_eq!(debugger.cpu_state().reg_pc, 0xc5fb);
+
+    // 条件不成立时忽略命中
+    debugger.remove_watchpoint(0);
+    debugger.add_watchpoint(
+        Watchpoint::new(BusKind::Cpu, 0x0011..=0x0011, Access::ReadWrite)
+            .with_condition(Condition::parse("A != 0").unwrap()),
+    );
+    debugger.set_cycle_limit(100);
+    assert_eq!(debugger.run(), StopReason::CycleLimit);
+    assert_eq!(debugger.watchpoints().len(), 1);
+
+    // 退出调试后 CPU 重新连接到原来的总线
+    let board = debugger.into_board();
+    board.cpu.borrow_mut().clock();
+}
+
+/// 按时钟计数推算扫描线，并在扫描线 241 产生 NMI 的 PPU
+#[derive(Default)]
+struct CountingPPU {
+    dots: u32,
+    nmi: bool,
+}
+
+impl Ppu for CountingPPU {
+    fn write_reg_control(&mut self, _value: u8) {}
+
+    fn write_reg_mask(&mut self, _value: u8) {}
+
+    fn read_reg_status(&self) -> u8 {
+        0
+    }
+
+    fn write_reg_oam_addr(&mut self, _value: u8) {}
+
+    fn read_reg_oam_data(&self) -> u8 {
+        0
+    }
+
+    fn write_reg_oam_data(&mut self, _value: u8) {}
+
+    fn write_reg_scroll(&mut self, _value: u8) {}
+
+    fn write_reg_address(&mut self, _value: u8) {}
+
+    fn read_reg_data(&self) -> u8 {
+        0
+    }
+
+    fn write_reg_data(&mut self, _value: u8) {}
+
+    fn reset(&mut self) {
+        self.dots = 0;
+    }
+
+    fn clock(&mut self) {
+        self.dots = (self.dots + 1) % (341 * 262);
+        if self.dots == 341 * 241 + 1 {
+            self.nmi = true;
+        }
+    }
+
+    fn attach_bus(&mut self, _bus: Rc<RefCell<dyn nes_base::BusAdapter>>) {}
+
+    fn position(&self) -> (u16, u16) {
+        ((self.dots / 341) as u16, (self.dots % 341) as u16)
+    }
+
+    fn check_nmi_interrupt(&self) -> bool {
+        self.nmi
+    }
+
+    fn clear_nmi_interrupt(&mut self) {
+        self.nmi = false;
+    }
+}
+
+#[test]
+fn test_debugger_run_to_scanline_and_nmi() {
+    let nes = nes_cartridge::NESFile::from_file("testfiles/nestest.nes").unwrap();
+    let cartridge = nes_cartridge::CartridgeImpl::new(nes).unwrap();
+    let ppu = Rc::new(RefCell::new(CountingPPU::default()));
+    let board = BoardImpl {
+        joypad1: None,
+        joypad2: None,
+        cheats: Default::default(),
+        audio: Default::default(),
+        cpu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        ppu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        cpu: Rc::new(RefCell::new(CpuImpl::new())),
+        ppu: ppu.clone(),
+        apu: Rc::new(RefCell::new(MockAPU)),
+        ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x800))),
+        ppu_name_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x1000))),
+        ppu_palettes_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x20))),
+        cartridge: Rc::new(RefCell::new(cartridge)),
+    }
+    .init();
+    // 在 RAM 中死循环等待 NMI: JMP $0200
+    for (i, &byte) in [0x4c, 0x00, 0x02].iter().enumerate() {
+        board.cpu_bus.borrow_mut().write(0x0200 + i as u16, byte);
+    }
+    board.cpu.borrow_mut().set_reg_pc(0x0200);
+    let mut debugger = Debugger::new(board);
+
+    assert_eq!(debugger.run_to_scanline(100), StopReason::Scanline(100));
+    assert_eq!(ppu.borrow().position().0, 100);
+
+    let handler = debugger.board().cpu_bus.borrow().read_u16(0xfffa);
+    assert_eq!(debugger.run_to_nmi(), StopReason::Nmi);
+    assert_eq!(debugger.cpu_state().reg_pc, handler);
+    assert_eq!(ppu.borrow().position().0, 241);
+
+    // 处理程序在 $0300，主循环不断经过 $0300，但只有真正响应 NMI 时才停下
+    let board = debugger.into_board();
+    for (addr, byte) in [
+        (0x0200, 0x4c), // JMP $0300
+        (0x0201, 0x00),
+        (0x0202, 0x03),
+        (0x0300, 0x4c), // JMP $0200
+        (0x0301, 0x00),
+        (0x0302, 0x02),
+        (0xfffa, 0x00), // NROM 的 PRG 写入会修改镜像，借此把 NMI 向量改到 $0300
+        (0xfffb, 0x03),
+    ] {
+        board.cpu_bus.borrow_mut().write(addr, byte);
+    }
+    board.cpu.borrow_mut().set_reg_pc(0x0200);
+    let mut debugger = Debugger::new(board);
+    assert_eq!(debugger.run_to_scanline(10), StopReason::Scanline(10));
+    assert_eq!(debugger.run_to_nmi(), StopReason::Nmi);
+    assert_eq!(debugger.cpu_state().reg_pc, 0x0300);
+    assert_eq!(ppu.borrow().position().0, 241);
+}
@@ -0,0 +1,138 @@
+use std::io::BufRead;
+
+use nes_base::{Cartridge, Reader};
+use nes_cpu::disassemble;
+
+use crate::neslog::NESLog;
+
+use super::*;
+
+/// 64KB 平坦内存，用于不依赖总线的反汇编测试
+struct FlatMemory(Vec<u8>);
+
+impl FlatMemory {
+    fn with_program(addr: u16, program: &[u8]) -> Self {
+        let mut mem = vec![0; 0x10000];
+        mem[addr as usize..addr as usize + program.len()].copy_from_slice(program);
+        Self(mem)
+    }
+}
+
+impl Reader for FlatMemory {
+    fn read(&self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.read(addr))
+    }
+}
+
+#[test]
+fn test_disassemble_addressing_modes() {
+    let mut mem = FlatMemory::with_program(0x8000, &[0xbd, 0x00, 0x02]);
+    mem.0[0x0205] = 0x3f;
+    let dis = disassemble(&mem, 0x8000, 0x05, 0x00);
+    assert_eq!(dis.to_string(), "LDA $0200,X @ 0205 = 3F");
+    assert_eq!(dis.bytes_hex(), "BD 00 02");
+    assert_eq!(dis.effective_address, Some(0x0205));
+    assert_eq!(dis.next_address(), 0x8003);
+
+    // (zp),Y 指针在零页内回绕
+    let mut mem = FlatMemory::with_program(0x8000, &[0xb1, 0xff]);
+    mem.0[0x00ff] = 0x00;
+    mem.0[0x0000] = 0x03;
+    mem.0[0x0310] = 0x89;
+    let dis = disassemble(&mem, 0x8000, 0x00, 0x10);
+    assert_eq!(dis.to_string(), "LDA ($FF),Y = 0300 @ 0310 = 89");
+
+    // JMP 间接寻址的页内回绕
+    let mut mem = FlatMemory::with_program(0x8000, &[0x6c, 0xff, 0x02]);
+    mem.0[0x02ff] = 0x7e;
+    mem.0[0x0200] = 0xdb;
+    let dis = disassemble(&mem, 0x8000, 0, 0);
+    assert_eq!(dis.to_string(), "JMP ($02FF) = DB7E");
+    assert_eq!(dis.effective_address, Some(0xdb7e));
+
+    // 向后分支
+    let mem = FlatMemory::with_program(0x8010, &[0xd0, 0xfc]);
+    let dis = disassemble(&mem, 0x8010, 0, 0);
+    assert_eq!(dis.to_string(), "BNE $800E");
+
+    let mem = FlatMemory::with_program(0x8000, &[0x4a, 0x18]);
+    assert_eq!(disassemble(&mem, 0x8000, 0, 0).to_string(), "LSR A");
+    assert_eq!(disassemble(&mem, 0x8001, 0, 0).to_string(), "CLC");
+}
+
+#[test]
+fn test_disassemble_unofficial_and_jam() {
+    let mem = FlatMemory::with_program(0x8000, &[0xe3, 0x10, 0xeb, 0x01, 0x02]);
+    let dis = disassemble(&mem, 0x8000, 0x00, 0x00);
+    assert!(dis.unofficial);
+    assert_eq!(dis.to_string(), "*ISB ($10,X) @ 10 = 0000 = 00");
+    assert_eq!(disassemble(&mem, 0x8002, 0, 0).to_string(), "*SBC #$01");
+
+    let dis = disassemble(&mem, 0x8004, 0, 0);
+    assert_eq!(dis.to_string(), "*JAM");
+    assert_eq!(dis.len(), 1);
+}
+
+#[test]
+fn test_disassemble_matches_nestest_log() {
+    let mut board = new_board();
+    let testlogs = std::fs::read("testfiles/nestest.txt").unwrap();
+
+    board.reset();
+    board.cpu.borrow_mut().set_reg_pc(0xc000);
+
+    for (line_no, line) in testlogs.lines().enumerate() {
+        let line = line.unwrap();
+        let log = NESLog::parse_line(&line);
+        let state = board.cpu.borrow().dump_state();
+        let dis = disassemble(
+            &*board.cpu_bus.borrow(),
+            state.reg_pc,
+            state.reg_x,
+            state.reg_y,
+        );
+
+        assert_eq!(
+            &line[6..14].trim_end(),
+            &dis.bytes_hex(),
+            "line {}",
+            line_no + 1
+        );
+        assert_eq!(dis.mnemonic, log.instruction_abbr, "line {}", line_no + 1);
+        assert_eq!(dis.unofficial, &line[15..16] == "*", "line {}", line_no + 1);
+        assert_eq!(dis.operand, log.addressing_display, "line {}", line_no + 1);
+
+        loop {
+            board.cpu.borrow_mut().clock();
+            if board.cpu.borrow().dump_state().remaining_cycles == 0 {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_disassemble_does_not_touch_mapper_registers() {
+    // $E000: LDA $4800，N163 读取 $4800 会让内部 RAM 地址自增
+    let mut rom = build_ines(19, 4, 2, 0);
+    let last_bank = 16 + 4 * 0x4000 - 0x2000;
+    rom[last_bank..last_bank + 3].copy_from_slice(&[0xad, 0x00, 0x48]);
+    let nes = nes_cartridge::NESFile::new(rom).unwrap();
+    let cartridge = Rc::new(RefCell::new(
+        nes_cartridge::CartridgeImpl::new(nes).unwrap(),
+    ));
+    cartridge.borrow_mut().cpu_write(0xF800, 0x80);
+    cartridge.borrow_mut().cpu_write(0x4800, 0x11);
+    cartridge.borrow_mut().cpu_write(0x4800, 0x22);
+    cartridge.borrow_mut().cpu_write(0xF800, 0x80);
+
+    let reader = nes_base::CartridgeAdapterForCPUBus(cartridge.clone());
+    let dis = disassemble(&reader, 0xE000, 0, 0);
+    assert_eq!(dis.bytes, [0xad, 0x00, 0x48]);
+    assert_eq!(dis.to_string(), "LDA $4800 = FF");
+    assert_eq!(cartridge.borrow().cpu_read(0x4800), 0x11);
+}
@@ -0,0 +1,263 @@
+use nes_base::{Cartridge, Mirroring};
+use nes_cartridge::{FdsCartridge, FdsDisk, RomError};
+
+use super::*;
+
+/// 构造一面磁盘：磁盘信息块、文件数量块，以及一个文件的文件头与数据块
+fn build_side(file_data: &[u8]) -> Vec<u8> {
+    let mut side = vec![0x01];
+    side.extend(b"*NINTENDO-HVC*");
+    side.resize(56, 0);
+    side.extend([0x02, 0x01]);
+    let mut header = vec![0x03, 0x00, 0x00];
+    header.extend(b"FILE0001");
+    header.extend(0x6000u16.to_le_bytes());
+    header.extend((file_data.len() as u16).to_le_bytes());
+    header.push(0x00);
+    side.extend(header);
+    side.push(0x04);
+    side.extend_from_slice(file_data);
+    side.resize(65500, 0);
+    side
+}
+
+/// 构造带 fwNES 文件头的 .fds 镜像
+fn build_fds(sides: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = b"FDS\x1A".to_vec();
+    bytes.push(sides.len() as u8);
+    bytes.resize(16, 0);
+    for side in sides {
+        bytes.extend_from_slice(side);
+    }
+    bytes
+}
+
+/// BIOS 的复位向量指向 $E000，程序写入 PRG-RAM 后原地循环
+fn build_bios() -> Vec<u8> {
+    let mut bios = vec![0; 0x2000];
+    bios[..7].copy_from_slice(&[
+        0xA9, 0x42, // LDA #$42
+        0x8D, 0x00, 0x60, // STA $6000
+        0xD0, 0xFE, // BNE *
+    ]);
+    bios[0x1FFC] = 0x00;
+    bios[0x1FFD] = 0xE0;
+    bios
+}
+
+fn new_fds(sides: &[Vec<u8>]) -> FdsCartridge {
+    let disk = FdsDisk::new(&build_fds(sides)).unwrap();
+    FdsCartridge::new(build_bios(), disk).unwrap()
+}
+
+#[test]
+fn test_fds_image() {
+    let disk = FdsDisk::new(&build_fds(&[build_side(b"A"), build_side(b"B")])).unwrap();
+    assert_eq!(disk.side_count(), 2);
+    // 没有文件头
+    let disk = FdsDisk::new(&build_side(b"A")).unwrap();
+    assert_eq!(disk.side_count(), 1);
+
+    // QD 镜像每面 64KB
+    let mut qd = build_side(b"A");
+    qd.resize(0x20000, 0);
+    assert_eq!(FdsDisk::new(&qd).unwrap().side_count(), 2);
+
+    assert!(matches!(
+        FdsDisk::new(&[0; 100]),
+        Err(RomError::InvalidDiskImage(100))
+    ));
+    assert!(matches!(
+        FdsCartridge::new(vec![0; 0x1000], disk),
+        Err(RomError::InvalidBiosSize(0x1000))
+    ));
+}
+
+#[test]
+fn test_fds_memory() {
+    let mut cartridge = new_fds(&[build_side(b"A")]);
+    assert_eq!(cartridge.cpu_read(0xE000), 0xA9);
+    assert_eq!(cartridge.cpu_read(0xFFFD), 0xE0);
+    // BIOS 只读
+    cartridge.cpu_write(0xE000, 0x00);
+    assert_eq!(cartridge.cpu_read(0xE000), 0xA9);
+
+    cartridge.cpu_write(0x6000, 0x12);
+    cartridge.cpu_write(0xDFFF, 0x34);
+    assert_eq!(cartridge.cpu_read(0x6000), 0x12);
+    assert_eq!(cartridge.cpu_read(0xDFFF), 0x34);
+
+    cartridge.ppu_write(0x1FFF, 0x56);
+    assert_eq!(cartridge.ppu_read(0x1FFF), 0x56);
+
+    cartridge.cpu_write(0x4025, 0x2E);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Horizontal));
+    cartridge.cpu_write(0x4025, 0x26);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Vertical));
+}
+
+#[test]
+fn test_fds_timer_irq() {
+    let mut cartridge = new_fds(&[build_side(b"A")]);
+    cartridge.cpu_write(0x4020, 0x03);
+    cartridge.cpu_write(0x4021, 0x00);
+    cartridge.cpu_write(0x4022, 0x03);
+
+    for _ in 0..3 {
+        cartridge.clock();
+    }
+    assert!(!cartridge.check_irq_interrupt());
+    cartridge.clock();
+    assert!(cartridge.check_irq_interrupt());
+
+    // 读取 $4030 应答计时器 IRQ
+    assert_eq!(cartridge.cpu_read(0x4030) & 0x01, 0x01);
+    assert!(!cartridge.check_irq_interrupt());
+    assert_eq!(cartridge.cpu_read(0x4030) & 0x01, 0x00);
+
+    // 重复模式下再次触发
+    for _ in 0..4 {
+        cartridge.clock();
+    }
+    assert!(cartridge.check_irq_interrupt());
+
+    // 关闭磁盘寄存器后计时器停止，IRQ 同时被应答
+    cartridge.cpu_write(0x4023, 0x00);
+    assert!(!cartridge.check_irq_interrupt());
+    for _ in 0..8 {
+        cartridge.clock();
+    }
+    assert!(!cartridge.check_irq_interrupt());
+}
+
+/// 运行磁盘驱动器直到传输一个字节，返回读到的数据
+fn read_disk_byte(cartridge: &mut FdsCartridge) -> u8 {
+    for _ in 0..1_000_000 {
+        cartridge.clock();
+        if cartridge.check_irq_interrupt() {
+            // 读取 $4031 同时应答传输 IRQ
+            return cartridge.cpu_read(0x4031);
+        }
+    }
+    panic!("no byte transferred");
+}
+
+#[test]
+fn test_fds_disk_read() {
+    let mut cartridge = new_fds(&[build_side(b"A"), build_side(b"B")]);
+    assert_eq!(cartridge.current_side(), Some(0));
+    // 电机未启动时未就绪
+    assert_eq!(cartridge.cpu_read(0x4032) & 0x07, 0x02);
+
+    // 启动电机，读取模式，开始传输并在每个字节后产生 IRQ
+    cartridge.cpu_write(0x4025, 0xE5);
+    let block: Vec<u8> = (0..15).map(|_| read_disk_byte(&mut cartridge)).collect();
+    assert_eq!(block[0], 0x01);
+    assert_eq!(&block[1..], b"*NINTENDO-HVC*");
+    assert_eq!(cartridge.cpu_read(0x4032) & 0x07, 0x00);
+
+    // 换面
+    cartridge.eject_disk();
+    assert_eq!(cartridge.current_side(), None);
+    assert_eq!(cartridge.cpu_read(0x4032) & 0x07, 0x07);
+    cartridge.insert_disk(1);
+    assert_eq!(cartridge.current_side(), Some(1));
+    cartridge.insert_disk(2);
+    assert_eq!(cartridge.current_side(), Some(1));
+}
+
+#[test]
+fn test_fds_audio() {
+    let mut cartridge = new_fds(&[build_side(b"A")]);
+    assert_eq!(cartridge.audio_output(), 0.0);
+
+    // 写入方波波表
+    cartridge.cpu_write(0x4089, 0x80);
+    for i in 0..64 {
+        cartridge.cpu_write(0x4040 + i, if i < 32 { 0x3F } else { 0x00 });
+    }
+    assert_eq!(cartridge.cpu_read(0x4040) & 0x3F, 0x3F);
+    cartridge.cpu_write(0x4089, 0x00);
+
+    // 关闭包络，音量 32
+    cartridge.cpu_write(0x4080, 0xA0);
+    assert_eq!(cartridge.cpu_read(0x4090) & 0x3F, 0x20);
+    cartridge.cpu_write(0x4082, 0x00);
+    cartridge.cpu_write(0x4083, 0x08);
+
+    let mut outputs = Vec::new();
+    for _ in 0..0x1000 {
+        cartridge.clock();
+        outputs.push(cartridge.audio_output());
+    }
+    let max = outputs.iter().cloned().fold(0.0, f32::max);
+    assert!(max > 0.2 && max <= 0.25);
+    assert!(outputs.contains(&0.0));
+
+    // 停止波形后输出保持在第一个采样
+    cartridge.cpu_write(0x4083, 0x80);
+    cartridge.clock();
+    assert!(cartridge.audio_output() > 0.2);
+}
+
+/// 用 FDS 卡带构造主板，同时返回具体类型的卡带
+fn new_fds_board(cartridge: FdsCartridge) -> (BoardImpl, Rc<RefCell<FdsCartridge>>) {
+    let cartridge = Rc::new(RefCell::new(cartridge));
+    let board = BoardImpl {
+        joypad1: None,
+        joypad2: None,
+        cheats: Default::default(),
+        audio: Default::default(),
+        cpu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        ppu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        cpu: Rc::new(RefCell::new(CpuImpl::new())),
+        ppu: Rc::new(RefCell::new(MockPPU)),
+        apu: Rc::new(RefCell::new(MockAPU)),
+        ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x800))),
+        ppu_name_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x1000))),
+        ppu_palettes_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x20))),
+        cartridge: cartridge.clone(),
+    }
+    .init();
+    (board, cartridge)
+}
+
+#[test]
+fn test_fds_board() {
+    let (mut board, cartridge) = new_fds_board(new_fds(&[build_side(b"A")]));
+    for _ in 0..20 {
+        board.clock();
+    }
+    assert_eq!(cartridge.borrow().cpu_read(0x6000), 0x42);
+}
+
+#[test]
+fn test_fds_board_audio() {
+    // BIOS 写入只有一个采样的波表，停止波形后输出保持在这个采样
+    let mut bios = vec![0; 0x2000];
+    let mut code = vec![];
+    for (addr, value) in [
+        (0x4089u16, 0x80u8),
+        (0x4040, 0x3F),
+        (0x4089, 0x00),
+        (0x4080, 0xA0),
+        (0x4083, 0x80),
+    ] {
+        // LDA #value; STA addr
+        code.extend([0xA9, value, 0x8D, addr as u8, (addr >> 8) as u8]);
+    }
+    let end = 0xE000 + code.len() as u16;
+    code.extend([0x4C, end as u8, (end >> 8) as u8]);
+    bios[..code.len()].copy_from_slice(&code);
+    bios[0x1FFC] = 0x00;
+    bios[0x1FFD] = 0xE0;
+    let disk = FdsDisk::new(&build_fds(&[build_side(b"A")])).unwrap();
+    let (mut board, _) = new_fds_board(FdsCartridge::new(bios, disk).unwrap());
+
+    for _ in 0..0x1000 {
+        board.clock();
+    }
+    let samples = board.audio.borrow_mut().take_samples();
+    assert!(board.audio_output() > 0.2);
+    assert!(samples[1..].iter().all(|&sample| sample > 0.2));
+}
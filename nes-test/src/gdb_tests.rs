@@ -0,0 +1,156 @@
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use nes_debugger::{Debugger, GdbServer};
+
+use super::*;
+
+/// 按脚本发送数据包并收集应答的 GDB 客户端
+struct ScriptedClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl ScriptedClient {
+    fn connect(port: u16) -> Self {
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let mut byte = [0];
+        self.reader.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    fn send(&mut self, data: &str) {
+        let sum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.writer, "${data}#{sum:02x}").unwrap();
+        assert_eq!(self.read_byte(), b'+');
+    }
+
+    fn receive(&mut self) -> String {
+        assert_eq!(self.read_byte(), b'$');
+        let mut data = Vec::new();
+        loop {
+            match self.read_byte() {
+                b'#' => break,
+                byte => data.push(byte),
+            }
+        }
+        let checksum = [self.read_byte(), self.read_byte()];
+        let sum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        assert_eq!(
+            std::str::from_utf8(&checksum).unwrap(),
+            format!("{sum:02x}")
+        );
+        self.writer.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.receive()
+    }
+}
+
+#[test]
+fn test_gdb_server_scripted_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let client = std::thread::spawn(move || {
+        let mut gdb = ScriptedClient::connect(port);
+        assert!(
+            gdb.request("qSupported:multiprocess+")
+                .contains("qXfer:features:read+")
+        );
+        assert!(
+            gdb.request("qXfer:features:read:target.xml:0,fff")
+                .starts_with("l<?xml")
+        );
+        assert_eq!(gdb.request("?"), "S05");
+        // A X Y P SP PCL PCH
+        assert_eq!(gdb.request("g"), "00000024fd00c0");
+
+        assert_eq!(gdb.request("M0000,2:abcd"), "OK");
+        assert_eq!(gdb.request("m0000,4"), "abcd0000");
+        assert_eq!(gdb.request("mc000,3"), "4cf5c5");
+        // 没有 PRG-RAM 的卡带不访问总线，I/O 与 Mapper 寄存器不允许写入
+        assert_eq!(gdb.request("m6000,2"), "0000");
+        assert_eq!(gdb.request("M1fff,2:0000"), "E01");
+        assert_eq!(gdb.request("M8000,1:00"), "E01");
+        assert_eq!(gdb.request("mc000,1"), "4c");
+
+        // 断点：C5FD JSR $C72D
+        assert_eq!(gdb.request("Z0,c5fd,1"), "OK");
+        assert_eq!(gdb.request("c"), "S05");
+        assert_eq!(gdb.request("p5"), "fdc5");
+        assert_eq!(gdb.request("z0,c5fd,1"), "OK");
+
+        // 观察点：JSR 先把返回地址高字节压入 $01FD
+        assert_eq!(gdb.request("Z2,1fc,2"), "OK");
+        assert_eq!(gdb.request("c"), "T05watch:01fd;");
+        assert_eq!(gdb.request("z2,1fc,2"), "OK");
+
+        assert_eq!(gdb.request("s"), "S05");
+        assert_eq!(gdb.request("p5"), "2ec7");
+        assert_eq!(gdb.request("P0=7f"), "OK");
+        assert_eq!(gdb.request("p0"), "7f");
+
+        // 在 RAM 中写入死循环 JMP $0300，继续运行后用 Ctrl-C 中断
+        assert_eq!(gdb.request("M0300,3:4c0003"), "OK");
+        assert_eq!(gdb.request("P5=0003"), "OK");
+        gdb.send("c");
+        gdb.writer.write_all(&[0x03]).unwrap();
+        assert_eq!(gdb.receive(), "S02");
+        assert_eq!(gdb.request("p5"), "0003");
+
+        assert_eq!(gdb.request("vMustReplyEmpty"), "");
+        assert_eq!(gdb.request("D"), "OK");
+    });
+
+    let mut board = new_board();
+    board.reset();
+    board.cpu.borrow_mut().set_reg_pc(0xc000);
+    let mut server = GdbServer::new(Debugger::new(board));
+    let (stream, _) = listener.accept().unwrap();
+    server.serve(stream).unwrap();
+    client.join().unwrap();
+
+    assert_eq!(server.debugger().cpu_state().reg_a, 0x7f);
+}
+
+#[test]
+fn test_gdb_server_stops_running_when_client_disconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (result_tx, result_rx) = std::sync::mpsc::channel();
+
+    // Debugger 不能跨线程，在服务线程中构造
+    std::thread::spawn(move || {
+        let mut board = new_board();
+        board.reset();
+        board.cpu.borrow_mut().set_reg_pc(0x0300);
+        let mut debugger = Debugger::new(board);
+        // JMP $0300
+        for (i, byte) in [0x4c, 0x00, 0x03].into_iter().enumerate() {
+            debugger.poke(0x0300 + i as u16, byte);
+        }
+        let mut server = GdbServer::new(debugger);
+        let (stream, _) = listener.accept().unwrap();
+        let _ = result_tx.send(server.serve(stream).map_err(|e| e.kind()));
+    });
+
+    let mut gdb = ScriptedClient::connect(port);
+    gdb.send("c");
+    drop(gdb);
+
+    let result = result_rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("server kept running after disconnect");
+    assert_eq!(result, Err(std::io::ErrorKind::UnexpectedEof));
+}
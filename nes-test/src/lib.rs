@@ -5,14 +5,60 @@ use nes_board::BoardImpl;
 use nes_bus::BusImpl;
 use nes_cpu::CpuImpl;
 
+pub mod cpu_suites;
 mod neslog;
 
+#[cfg(test)]
+mod audio_tests;
+
+#[cfg(test)]
+mod cheat_tests;
+
+#[cfg(test)]
+mod cpu_suite_tests;
+
 #[cfg(test)]
 mod cpu_tests;
 
+#[cfg(test)]
+mod cycle_tests;
+
+#[cfg(test)]
+mod debugger_tests;
+
+#[cfg(test)]
+mod disasm_tests;
+
+#[cfg(test)]
+mod fds_tests;
+
+#[cfg(test)]
+mod gdb_tests;
+
+#[cfg(test)]
+mod mapper_tests;
+
+#[cfg(test)]
+mod patch_tests;
+
+#[cfg(test)]
+mod registry_tests;
+
+#[cfg(test)]
+mod rom_tests;
+
+#[cfg(test)]
+mod save_tests;
+
+#[cfg(test)]
+mod state_tests;
+
 #[cfg(test)]
 mod tile_tests;
 
+#[cfg(test)]
+mod trace_tests;
+
 struct MockPPU;
 
 impl Ppu for MockPPU {
@@ -48,6 +94,10 @@ impl Ppu for MockPPU {
 
     fn attach_bus(&mut self, bus: std::rc::Rc<std::cell::RefCell<dyn nes_base::BusAdapter>>) {}
 
+    fn position(&self) -> (u16, u16) {
+        (0, 0)
+    }
+
     fn check_nmi_interrupt(&self) -> bool {
         false
     }
@@ -104,24 +154,33 @@ impl Apu for MockAPU {
 
     fn clock(&mut self) {}
 
+    fn output(&self) -> f32 {
+        0.0
+    }
+
     fn check_irq_interrupt(&self) -> bool {
         false
     }
-
-    fn clear_irq_interrupt(&mut self) {}
 }
 
 fn new_board() -> BoardImpl {
-    let nes = nes_cartridge::NESFile::from_file("testfiles/nestest.nes");
-    let cartridge = nes_cartridge::CartridgeImpl::new(nes);
+    board_from_file("testfiles/nestest.nes", Rc::new(RefCell::new(MockPPU)))
+}
+
+/// 从 ROM 文件构造主板，APU 使用空实现
+fn board_from_file(path: &str, ppu: Rc<RefCell<dyn Ppu>>) -> BoardImpl {
+    let nes = nes_cartridge::NESFile::from_file(path).unwrap();
+    let cartridge = nes_cartridge::CartridgeImpl::new(nes).unwrap();
 
     BoardImpl {
         joypad1: None,
         joypad2: None,
+        cheats: Default::default(),
+        audio: Default::default(),
         cpu_bus: Rc::new(RefCell::new(BusImpl::new())),
         ppu_bus: Rc::new(RefCell::new(BusImpl::new())),
         cpu: Rc::new(RefCell::new(CpuImpl::new())),
-        ppu: Rc::new(RefCell::new(MockPPU)),
+        ppu,
         apu: Rc::new(RefCell::new(MockAPU)),
         ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x800))),
         ppu_name_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x1000))),
@@ -130,3 +189,26 @@ fn new_board() -> BoardImpl {
     }
     .init()
 }
+
+/// 构造一个合成的 iNES 镜像，PRG/CHR 的每个字节填充为其所在 bank 的编号
+#[cfg(test)]
+fn build_ines(mapper_id: u8, prg_banks: u8, chr_banks: u8, flags6: u8) -> Vec<u8> {
+    let mut bytes = vec![
+        0x4E,
+        0x45,
+        0x53,
+        0x1A,
+        prg_banks,
+        chr_banks,
+        ((mapper_id & 0x0F) << 4) | (flags6 & 0x0F),
+        mapper_id & 0xF0,
+    ];
+    bytes.resize(16, 0);
+    for bank in 0..prg_banks {
+        bytes.extend(std::iter::repeat_n(bank, 0x4000));
+    }
+    for bank in 0..chr_banks {
+        bytes.extend(std::iter::repeat_n(bank, 0x2000));
+    }
+    bytes
+}
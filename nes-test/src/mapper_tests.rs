@@ -0,0 +1,553 @@
+use nes_base::{Cartridge, Mirroring};
+use nes_cartridge::{CartridgeImpl, NESFile};
+
+use super::*;
+
+fn new_cartridge(mapper_id: u8, prg_banks: u8, chr_banks: u8) -> CartridgeImpl {
+    let nes = NESFile::new(build_ines(mapper_id, prg_banks, chr_banks, 0)).unwrap();
+    CartridgeImpl::new(nes).unwrap()
+}
+
+#[test]
+fn test_mapper2_uxrom() {
+    let mut cartridge = new_cartridge(2, 4, 0);
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.cpu_read(0xC000), 3);
+    assert_eq!(cartridge.cpu_read(0xFFFF), 3);
+
+    cartridge.cpu_write(0x8000, 2);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert_eq!(cartridge.cpu_read(0xBFFF), 2);
+    assert_eq!(cartridge.cpu_read(0xC000), 3);
+
+    // CHR-RAM
+    cartridge.ppu_write(0x1234, 0x56);
+    assert_eq!(cartridge.ppu_read(0x1234), 0x56);
+}
+
+#[test]
+fn test_mapper3_cnrom() {
+    let mut cartridge = new_cartridge(3, 1, 4);
+    // 16KB PRG-ROM 镜像
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.cpu_read(0xC000), 0);
+    assert_eq!(cartridge.ppu_read(0x0000), 0);
+
+    cartridge.cpu_write(0x8000, 2);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    assert_eq!(cartridge.ppu_read(0x1FFF), 2);
+
+    cartridge.cpu_write(0xFFFF, 3);
+    assert_eq!(cartridge.ppu_read(0x1000), 3);
+
+    // 没有 CHR-ROM 时使用 8KB CHR-RAM，bank 选择不起作用
+    let mut cartridge = new_cartridge(3, 1, 0);
+    cartridge.cpu_write(0x8000, 1);
+    cartridge.ppu_write(0x1FFF, 0x56);
+    assert_eq!(cartridge.ppu_read(0x1FFF), 0x56);
+}
+
+#[test]
+fn test_mapper7_axrom() {
+    let mut cartridge = new_cartridge(7, 8, 0);
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.cpu_read(0xC000), 1);
+    assert!(matches!(
+        cartridge.mirroring(),
+        Mirroring::SingleScreenLower
+    ));
+
+    cartridge.cpu_write(0x8000, 0x13);
+    assert_eq!(cartridge.cpu_read(0x8000), 6);
+    assert_eq!(cartridge.cpu_read(0xFFFF), 7);
+    assert!(matches!(
+        cartridge.mirroring(),
+        Mirroring::SingleScreenUpper
+    ));
+
+    cartridge.cpu_write(0x8000, 0x01);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert!(matches!(
+        cartridge.mirroring(),
+        Mirroring::SingleScreenLower
+    ));
+
+    // CHR-RAM
+    cartridge.ppu_write(0x0010, 0xAA);
+    assert_eq!(cartridge.ppu_read(0x0010), 0xAA);
+}
+
+#[test]
+fn test_mapper11_color_dreams() {
+    let mut cartridge = new_cartridge(11, 8, 16);
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.ppu_read(0x0000), 0);
+
+    cartridge.cpu_write(0x8000, 0xF3);
+    assert_eq!(cartridge.cpu_read(0x8000), 6);
+    assert_eq!(cartridge.cpu_read(0xC000), 7);
+    assert_eq!(cartridge.ppu_read(0x0000), 15);
+
+    // 没有 CHR-ROM 时使用 8KB CHR-RAM
+    let mut cartridge = new_cartridge(11, 2, 0);
+    cartridge.cpu_write(0x8000, 0x30);
+    cartridge.ppu_write(0x0123, 0x45);
+    assert_eq!(cartridge.ppu_read(0x0123), 0x45);
+}
+
+#[test]
+fn test_mapper66_gxrom() {
+    let mut cartridge = new_cartridge(66, 8, 4);
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.ppu_read(0x0000), 0);
+
+    cartridge.cpu_write(0x8000, 0x32);
+    assert_eq!(cartridge.cpu_read(0x8000), 6);
+    assert_eq!(cartridge.cpu_read(0xC000), 7);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    assert_eq!(cartridge.ppu_read(0x1FFF), 2);
+}
+
+/// 设置 MMC2/MMC4 的 CHR 寄存器，每个 4KB bank 的内容为 bank 编号 / 2
+fn setup_chr_latch(cartridge: &mut CartridgeImpl) {
+    cartridge.cpu_write(0xB000, 2); // 低窗口 $FD -> 1
+    cartridge.cpu_write(0xC000, 4); // 低窗口 $FE -> 2
+    cartridge.cpu_write(0xD000, 6); // 高窗口 $FD -> 3
+    cartridge.cpu_write(0xE000, 1); // 高窗口 $FE -> 0
+}
+
+#[test]
+fn test_mapper9_mmc2() {
+    let mut cartridge = new_cartridge(9, 8, 4);
+    cartridge.cpu_write(0xA000, 3);
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0xA000), 6);
+    assert_eq!(cartridge.cpu_read(0xC000), 7);
+    assert_eq!(cartridge.cpu_read(0xFFFF), 7);
+
+    cartridge.cpu_write(0xF000, 1);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Horizontal));
+    cartridge.cpu_write(0xF000, 0);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Vertical));
+
+    setup_chr_latch(&mut cartridge);
+    // 锁存器初始为 $FE
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    assert_eq!(cartridge.ppu_read(0x1000), 0);
+
+    // 触发切换的那次读取仍然使用旧的 bank
+    assert_eq!(cartridge.ppu_read(0x0FD8), 2);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+    // MMC2 的低窗口只响应 $0FE8
+    cartridge.ppu_read(0x0FE9);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+    cartridge.ppu_read(0x0FE8);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+
+    // 高窗口响应整个范围
+    cartridge.ppu_read(0x1FDA);
+    assert_eq!(cartridge.ppu_read(0x1000), 3);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    cartridge.ppu_read(0x1FEF);
+    assert_eq!(cartridge.ppu_read(0x1000), 0);
+}
+
+#[test]
+fn test_mapper9_small_prg_rom() {
+    // PRG-ROM 只有 16KB 时，固定的 24KB 按 bank 数回绕
+    let mut cartridge = new_cartridge(9, 1, 4);
+    cartridge.cpu_write(0xA000, 3);
+    assert_eq!(cartridge.cpu_read(0x8000), 0);
+    assert_eq!(cartridge.cpu_read(0xA000), 0);
+    assert_eq!(cartridge.cpu_read(0xC000), 0);
+    assert_eq!(cartridge.cpu_read(0xFFFF), 0);
+}
+
+#[test]
+fn test_mapper10_mmc4() {
+    let mut cartridge = new_cartridge(10, 8, 4);
+    cartridge.cpu_write(0xA000, 2);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert_eq!(cartridge.cpu_read(0xBFFF), 2);
+    assert_eq!(cartridge.cpu_read(0xC000), 7);
+
+    setup_chr_latch(&mut cartridge);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    cartridge.ppu_read(0x0FDF);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+    cartridge.ppu_read(0x0FE9);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+}
+
+#[test]
+fn test_mapper21_vrc4() {
+    let mut cartridge = new_cartridge(21, 8, 4);
+    assert_eq!(cartridge.cpu_read(0xC000), 7);
+    assert_eq!(cartridge.cpu_read(0xE000), 7);
+
+    cartridge.cpu_write(0x8000, 4);
+    cartridge.cpu_write(0xA000, 3);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert_eq!(cartridge.cpu_read(0xA000), 1);
+
+    // VRC4a 的 A1 接在 CPU A2 上，$9004 即 $9002
+    cartridge.cpu_write(0x9004, 0x02);
+    assert_eq!(cartridge.cpu_read(0x8000), 7);
+    assert_eq!(cartridge.cpu_read(0xC000), 2);
+
+    cartridge.cpu_write(0x9000, 1);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Horizontal));
+
+    // CHR bank 0 = 0x10，高半字节通过 VRC4c 的 $B040 写入
+    cartridge.cpu_write(0xB000, 0x00);
+    cartridge.cpu_write(0xB040, 0x01);
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    assert_eq!(cartridge.ppu_read(0x0400), 0);
+
+    // 没有 CHR-ROM 时使用 8KB CHR-RAM，仍然按 1KB bank 切换
+    let mut cartridge = new_cartridge(21, 8, 0);
+    cartridge.cpu_write(0xB000, 0x01);
+    cartridge.ppu_write(0x0000, 0x56);
+    cartridge.cpu_write(0xB004, 0x01);
+    assert_eq!(cartridge.ppu_read(0x0400), 0x56);
+}
+
+#[test]
+fn test_mapper21_vrc4_irq() {
+    let mut cartridge = new_cartridge(21, 8, 4);
+    // 锁存值 0xFE，周期模式
+    cartridge.cpu_write(0xF000, 0x0E);
+    cartridge.cpu_write(0xF002, 0x0F);
+    cartridge.cpu_write(0xF004, 0x06);
+
+    cartridge.clock();
+    assert!(!cartridge.check_irq_interrupt());
+    cartridge.clock();
+    assert!(cartridge.check_irq_interrupt());
+
+    // 应答后 IRQ 被禁用
+    cartridge.cpu_write(0xF006, 0);
+    assert!(!cartridge.check_irq_interrupt());
+    for _ in 0..0x200 {
+        cartridge.clock();
+    }
+    assert!(!cartridge.check_irq_interrupt());
+}
+
+#[test]
+fn test_mapper24_vrc6() {
+    let mut cartridge = new_cartridge(24, 4, 2);
+    cartridge.cpu_write(0x8000, 1);
+    cartridge.cpu_write(0xC000, 5);
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0xC000), 2);
+    assert_eq!(cartridge.cpu_read(0xE000), 3);
+
+    cartridge.cpu_write(0xD000, 9);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+
+    cartridge.cpu_write(0xB003, 0x04);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Horizontal));
+
+    // 方波 1 忽略占空比，直接输出音量
+    assert_eq!(cartridge.audio_output(), 0.0);
+    cartridge.cpu_write(0x9000, 0x8F);
+    cartridge.cpu_write(0x9002, 0x80);
+    assert!(cartridge.audio_output() > 0.0);
+    cartridge.cpu_write(0x9002, 0x00);
+    assert_eq!(cartridge.audio_output(), 0.0);
+
+    // 没有 CHR-ROM 时使用 8KB CHR-RAM
+    let mut cartridge = new_cartridge(24, 4, 0);
+    cartridge.cpu_write(0xD000, 1);
+    cartridge.ppu_write(0x0000, 0x56);
+    cartridge.cpu_write(0xD001, 1);
+    assert_eq!(cartridge.ppu_read(0x0400), 0x56);
+}
+
+#[test]
+fn test_mapper5_mmc5_prg() {
+    let mut cartridge = new_cartridge(5, 8, 1);
+    // 上电时 $5117 指向最后一个 bank
+    assert_eq!(cartridge.cpu_read(0xFFFC), 7);
+
+    cartridge.cpu_write(0x5114, 0x82);
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+
+    cartridge.cpu_write(0x5100, 0);
+    cartridge.cpu_write(0x5117, 0x05);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert_eq!(cartridge.cpu_read(0xE000), 3);
+
+    cartridge.cpu_write(0x5100, 1);
+    cartridge.cpu_write(0x5115, 0x84);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+    assert_eq!(cartridge.cpu_read(0xA000), 2);
+
+    // PRG-RAM 写保护
+    cartridge.cpu_write(0x5113, 1);
+    cartridge.cpu_write(0x6000, 0x42);
+    assert_eq!(cartridge.cpu_read(0x6000), 0);
+    cartridge.cpu_write(0x5102, 0x02);
+    cartridge.cpu_write(0x5103, 0x01);
+    cartridge.cpu_write(0x6000, 0x42);
+    assert_eq!(cartridge.cpu_read(0x6000), 0x42);
+    cartridge.cpu_write(0x5113, 0);
+    assert_eq!(cartridge.cpu_read(0x6000), 0);
+
+    // PRG-RAM 映射到 $8000
+    cartridge.cpu_write(0x5100, 3);
+    cartridge.cpu_write(0x5114, 0x01);
+    assert_eq!(cartridge.cpu_read(0x8000), 0x42);
+}
+
+#[test]
+fn test_mapper5_mmc5_multiplier() {
+    let mut cartridge = new_cartridge(5, 2, 1);
+    cartridge.cpu_write(0x5205, 200);
+    cartridge.cpu_write(0x5206, 100);
+    assert_eq!(cartridge.cpu_read(0x5205), (20000u16 & 0xFF) as u8);
+    assert_eq!(cartridge.cpu_read(0x5206), (20000u16 >> 8) as u8);
+}
+
+/// 模拟 PPU 渲染一条扫描线的取数过程，返回背景与精灵取到的 CHR 数据
+fn fetch_scanline(cartridge: &mut CartridgeImpl) -> (Vec<u8>, Vec<u8>) {
+    let mut bg = vec![];
+    let mut sprite = vec![];
+    for tile in 0..32 {
+        cartridge.nametable_read(0x2002 + tile);
+        cartridge.nametable_read(0x23C0);
+        bg.push(cartridge.ppu_read(0x0000));
+        bg.push(cartridge.ppu_read(0x0008));
+    }
+    for _ in 0..8 {
+        cartridge.nametable_read(0x2000);
+        cartridge.nametable_read(0x23C0);
+        sprite.push(cartridge.ppu_read(0x0000));
+        sprite.push(cartridge.ppu_read(0x0008));
+    }
+    for _ in 0..2 {
+        cartridge.nametable_read(0x2000);
+        cartridge.nametable_read(0x23C0);
+        cartridge.ppu_read(0x0000);
+        cartridge.ppu_read(0x0008);
+    }
+    // 两次无用的名称表读取，与下一条扫描线的第一次读取地址相同
+    cartridge.nametable_read(0x2002);
+    cartridge.nametable_read(0x2002);
+    (bg, sprite)
+}
+
+#[test]
+fn test_mapper5_mmc5_chr_and_irq() {
+    let mut cartridge = new_cartridge(5, 2, 8);
+    cartridge.cpu_write(0x5101, 3);
+    cartridge.cpu_write(0x5120, 8);
+    cartridge.cpu_write(0x5128, 16);
+    // 非 8x16 模式时使用最后写入的一组 bank
+    assert_eq!(cartridge.ppu_read(0x0000), 2);
+    assert_eq!(cartridge.ppu_read(0x1000), 2);
+
+    cartridge.ppu_register_write(0x2000, 0x20);
+    cartridge.ppu_register_write(0x2001, 0x18);
+    cartridge.cpu_write(0x5203, 2);
+    cartridge.cpu_write(0x5204, 0x80);
+
+    cartridge.nametable_read(0x2002);
+    cartridge.nametable_read(0x2002);
+    let (bg, sprite) = fetch_scanline(&mut cartridge);
+    assert!(bg.iter().all(|&value| value == 2));
+    assert!(sprite.iter().all(|&value| value == 1));
+    assert_eq!(cartridge.cpu_read(0x5204), 0x40);
+
+    fetch_scanline(&mut cartridge);
+    assert!(!cartridge.check_irq_interrupt());
+    fetch_scanline(&mut cartridge);
+    assert!(cartridge.check_irq_interrupt());
+    assert_eq!(cartridge.cpu_read(0x5204), 0xC0);
+    assert_eq!(cartridge.cpu_read(0x5204), 0x40);
+
+    // PPU 停止取数后离开帧
+    for _ in 0..3 {
+        cartridge.clock();
+    }
+    assert_eq!(cartridge.cpu_read(0x5204), 0x00);
+}
+
+#[test]
+fn test_mapper5_mmc5_nametables() {
+    let mut cartridge = new_cartridge(5, 2, 8);
+    cartridge.cpu_write(0x5104, 2);
+    cartridge.cpu_write(0x5C00, 0x55);
+    assert_eq!(cartridge.cpu_read(0x5C00), 0x55);
+
+    // NT0/NT1 使用 VRAM，NT2 使用 ExRAM，NT3 使用填充模式
+    cartridge.cpu_write(0x5105, 0xE4);
+    assert!(matches!(
+        cartridge.mirroring(),
+        Mirroring::Mapped([0, 1, _, _])
+    ));
+    assert_eq!(cartridge.nametable_read(0x2000), None);
+    assert!(!cartridge.nametable_write(0x2400, 0));
+
+    cartridge.cpu_write(0x5104, 0);
+    assert!(cartridge.nametable_write(0x2805, 0x77));
+    assert_eq!(cartridge.nametable_read(0x2805), Some(0x77));
+
+    cartridge.cpu_write(0x5106, 0x33);
+    cartridge.cpu_write(0x5107, 0x02);
+    assert_eq!(cartridge.nametable_read(0x2C00), Some(0x33));
+    assert_eq!(cartridge.nametable_read(0x2FC0), Some(0xAA));
+
+    // 扩展属性模式: 调色板 3，4KB CHR bank 2
+    cartridge.cpu_write(0x5104, 1);
+    assert!(cartridge.nametable_write(0x2800, 0xC2));
+    cartridge.ppu_register_write(0x2001, 0x18);
+    for _ in 0..3 {
+        cartridge.nametable_read(0x2000);
+    }
+    assert_eq!(cartridge.nametable_read(0x23C0), Some(0xFF));
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+}
+
+#[test]
+fn test_mapper5_mmc5_audio() {
+    let mut cartridge = new_cartridge(5, 2, 1);
+    assert_eq!(cartridge.audio_output(), 0.0);
+
+    cartridge.cpu_write(0x5011, 0x80);
+    assert!(cartridge.audio_output() > 0.0);
+    cartridge.cpu_write(0x5011, 0x00);
+    assert!(cartridge.audio_output() > 0.0);
+
+    let mut cartridge = new_cartridge(5, 2, 1);
+    cartridge.cpu_write(0x5015, 0x01);
+    cartridge.cpu_write(0x5000, 0xBF);
+    cartridge.cpu_write(0x5002, 0x10);
+    cartridge.cpu_write(0x5003, 0x08);
+    let mut outputs = vec![];
+    for _ in 0..0x100 {
+        cartridge.clock();
+        outputs.push(cartridge.audio_output());
+    }
+    assert!(outputs.iter().any(|&output| output > 0.0));
+    assert!(outputs.contains(&0.0));
+}
+
+#[test]
+fn test_mapper19_namco163() {
+    let mut cartridge = new_cartridge(19, 4, 2);
+    cartridge.cpu_write(0xE000, 3);
+    cartridge.cpu_write(0xE800, 4);
+    cartridge.cpu_write(0xF000, 5);
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0xA000), 2);
+    assert_eq!(cartridge.cpu_read(0xC000), 2);
+    assert_eq!(cartridge.cpu_read(0xE000), 3);
+
+    cartridge.cpu_write(0x8000, 9);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+
+    // 名称表使用 CHR-ROM
+    cartridge.cpu_write(0xC000, 8);
+    cartridge.cpu_write(0xC800, 0xE0);
+    assert_eq!(cartridge.nametable_read(0x2000), Some(1));
+    assert_eq!(cartridge.nametable_read(0x2400), None);
+    assert!(matches!(
+        cartridge.mirroring(),
+        Mirroring::Mapped([_, 0, 0, 1])
+    ));
+
+    // 内部 RAM 自动递增
+    cartridge.cpu_write(0xF800, 0x90);
+    cartridge.cpu_write(0x4800, 0xAB);
+    cartridge.cpu_write(0x4800, 0xCD);
+    cartridge.cpu_write(0xF800, 0x90);
+    assert_eq!(cartridge.cpu_read(0x4800), 0xAB);
+    assert_eq!(cartridge.cpu_read(0x4800), 0xCD);
+}
+
+#[test]
+fn test_mapper19_namco163_irq_and_audio() {
+    let mut cartridge = new_cartridge(19, 4, 2);
+    cartridge.cpu_write(0x5000, 0xFD);
+    cartridge.cpu_write(0x5800, 0xFF);
+    cartridge.clock();
+    assert!(!cartridge.check_irq_interrupt());
+    cartridge.clock();
+    assert!(cartridge.check_irq_interrupt());
+    cartridge.cpu_write(0x5800, 0x00);
+    assert!(!cartridge.check_irq_interrupt());
+
+    // 波形: 长度为 4 的方波
+    cartridge.cpu_write(0xF800, 0x80);
+    cartridge.cpu_write(0x4800, 0xFF);
+    cartridge.cpu_write(0x4800, 0x00);
+    // 声道 7: 每次更新前进一个采样，音量 15
+    cartridge.cpu_write(0xF800, 0xC0 | 0x38);
+    for value in [0x00, 0x00, 0x00, 0x00, 0xFD, 0x00, 0x00, 0x0F] {
+        cartridge.cpu_write(0x4800, value);
+    }
+    let mut outputs = vec![];
+    for _ in 0..15 * 8 {
+        cartridge.clock();
+        outputs.push(cartridge.audio_output());
+    }
+    assert!(outputs.iter().any(|&output| output > 0.0));
+    assert!(outputs.iter().any(|&output| output < 0.0));
+}
+
+#[test]
+fn test_mapper69_fme7() {
+    let mut cartridge = new_cartridge(69, 4, 2);
+    cartridge.cpu_write(0x8000, 0x9);
+    cartridge.cpu_write(0xA000, 3);
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0xE000), 3);
+
+    // [0x6000, 0x7FFF] 映射 PRG-ROM
+    cartridge.cpu_write(0x8000, 0x8);
+    cartridge.cpu_write(0xA000, 2);
+    assert_eq!(cartridge.cpu_read(0x6000), 1);
+
+    cartridge.cpu_write(0x8000, 0x0);
+    cartridge.cpu_write(0xA000, 9);
+    assert_eq!(cartridge.ppu_read(0x0000), 1);
+
+    cartridge.cpu_write(0x8000, 0xC);
+    cartridge.cpu_write(0xA000, 1);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Horizontal));
+
+    // IRQ 计数器从 2 开始递减，下溢时触发
+    cartridge.cpu_write(0x8000, 0xE);
+    cartridge.cpu_write(0xA000, 2);
+    cartridge.cpu_write(0x8000, 0xF);
+    cartridge.cpu_write(0xA000, 0);
+    cartridge.cpu_write(0x8000, 0xD);
+    cartridge.cpu_write(0xA000, 0x81);
+    cartridge.clock();
+    cartridge.clock();
+    assert!(!cartridge.check_irq_interrupt());
+    cartridge.clock();
+    assert!(cartridge.check_irq_interrupt());
+    cartridge.cpu_write(0xA000, 0x00);
+    assert!(!cartridge.check_irq_interrupt());
+}
+
+#[test]
+fn test_mapper69_sunsoft5b_audio() {
+    let mut cartridge = new_cartridge(69, 4, 2);
+    assert_eq!(cartridge.audio_output(), 0.0);
+    // 只启用声道 A 的方波
+    for (register, value) in [(0x7, 0x3E), (0x8, 0x0F), (0x0, 0x01)] {
+        cartridge.cpu_write(0xC000, register);
+        cartridge.cpu_write(0xE000, value);
+    }
+    let mut outputs = vec![];
+    for _ in 0..16 * 4 {
+        cartridge.clock();
+        outputs.push(cartridge.audio_output());
+    }
+    assert!(outputs.iter().any(|&output| output > 0.0));
+    assert!(outputs.contains(&0.0));
+}
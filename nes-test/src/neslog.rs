@@ -75,6 +75,8 @@ impl From<NESLog> for CpuState {
             reg_sp: val.reg_sp,
             reg_pc: val.reg_pc,
             reg_status: val.reg_status.into(),
+            jammed: false,
+            nmi_count: 0,
         }
     }
 }
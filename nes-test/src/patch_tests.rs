@@ -0,0 +1,187 @@
+use nes_cartridge::{NESFile, PatchError, PatchFormat, RomError, RomHash, apply_patch};
+
+use super::*;
+
+fn varint(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte | 0x80);
+            return bytes;
+        }
+        bytes.push(byte);
+        value -= 1;
+    }
+}
+
+/// 追加源文件、目标文件与补丁本身的 CRC32
+fn push_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+    patch.extend(RomHash::new(source).crc32.to_le_bytes());
+    patch.extend(RomHash::new(target).crc32.to_le_bytes());
+    let crc = RomHash::new(patch).crc32;
+    patch.extend(crc.to_le_bytes());
+}
+
+#[test]
+fn test_patch_ips() {
+    let rom = build_ines(0, 1, 1, 0);
+    let mut patch = b"PATCH".to_vec();
+    // 将 Mapper 改为 2
+    patch.extend([0x00, 0x00, 0x06, 0x00, 0x01, 0x20]);
+    // RLE：PRG-ROM 开头 4 字节填充为 0xEA
+    patch.extend([0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x04, 0xEA]);
+    patch.extend(b"EOF");
+    assert_eq!(PatchFormat::detect(&patch), Some(PatchFormat::Ips));
+
+    let patched = apply_patch(&rom, &patch).unwrap();
+    assert_eq!(patched.len(), rom.len());
+    assert_eq!(patched[6], 0x20);
+    assert_eq!(&patched[0x10..0x15], &[0xEA, 0xEA, 0xEA, 0xEA, 0x00]);
+
+    let nes = NESFile::with_patches(rom.clone(), &[patch.clone()]).unwrap();
+    assert_eq!(nes.header().mapper_id, 2);
+
+    // 截断扩展
+    patch.extend([0x00, 0x40, 0x10]);
+    assert_eq!(apply_patch(&rom, &patch).unwrap().len(), 0x4010);
+}
+
+#[test]
+fn test_patch_bps() {
+    let rom = build_ines(0, 1, 1, 0);
+    let mut target = rom.clone();
+    target[6] = 0x20;
+    target[0x10..0x18].copy_from_slice(b"ROMHACK!");
+    // 用 PRG-ROM 的前 8 字节重复填充
+    for i in 0x18..0x20 {
+        target[i] = target[i - 8];
+    }
+
+    let mut patch = b"BPS1".to_vec();
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(target.len()));
+    patch.extend(varint(0));
+    // SourceRead 6 字节
+    patch.extend(varint((6 - 1) << 2));
+    // TargetRead 1 字节
+    patch.extend(varint(1));
+    patch.push(0x20);
+    // SourceCopy 9 字节，源偏移 +7
+    patch.extend(varint(((9 - 1) << 2) | 2));
+    patch.extend(varint(7 << 1));
+    // TargetRead 8 字节
+    patch.extend(varint(((8 - 1) << 2) | 1));
+    patch.extend(b"ROMHACK!");
+    // TargetCopy 8 字节，从目标偏移 0x10 复制
+    patch.extend(varint(((8 - 1) << 2) | 3));
+    patch.extend(varint(0x10 << 1));
+    // 剩余部分 SourceRead
+    patch.extend(varint((rom.len() - 0x20 - 1) << 2));
+    push_footer(&mut patch, &rom, &target);
+
+    assert_eq!(apply_patch(&rom, &patch).unwrap(), target);
+
+    // 源文件不匹配时校验失败
+    let mut other = rom.clone();
+    other[0x100] = 1;
+    assert!(matches!(
+        apply_patch(&other, &patch),
+        Err(PatchError::ChecksumMismatch {
+            which: "source",
+            ..
+        })
+    ));
+
+    // 补丁损坏
+    let mut broken = patch.clone();
+    broken[8] ^= 0xFF;
+    assert!(matches!(
+        NESFile::with_patches(rom, &[broken]),
+        Err(RomError::Patch(PatchError::ChecksumMismatch {
+            which: "patch",
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_patch_ups() {
+    let rom = build_ines(0, 1, 1, 0);
+    let mut target = rom.clone();
+    target[6] = 0x20;
+    target[0x2000] = 0x55;
+    target.extend([0x01, 0x02]);
+
+    let mut patch = b"UPS1".to_vec();
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(target.len()));
+    patch.extend(varint(6));
+    patch.extend([0x20, 0x00]);
+    patch.extend(varint(0x2000 - 8));
+    patch.extend([0x55 ^ rom[0x2000], 0x00]);
+    patch.extend(varint(rom.len() - 0x2002));
+    patch.extend([0x01, 0x02, 0x00]);
+    push_footer(&mut patch, &rom, &target);
+
+    assert_eq!(apply_patch(&rom, &patch).unwrap(), target);
+    assert!(matches!(
+        apply_patch(&rom[..0x100], &patch),
+        Err(PatchError::SourceSizeMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_patch_unknown_format() {
+    let rom = build_ines(0, 1, 1, 0);
+    assert_eq!(
+        apply_patch(&rom, b"NOT A PATCH"),
+        Err(PatchError::UnknownFormat)
+    );
+    assert_eq!(
+        apply_patch(&rom, b"PATCH\x00\x00"),
+        Err(PatchError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn test_patch_oversized_target() {
+    let rom = build_ines(0, 1, 1, 0);
+    for magic in [b"BPS1", b"UPS1"] {
+        let mut patch = magic.to_vec();
+        patch.extend(varint(rom.len()));
+        patch.extend(varint(1 << 40));
+        patch.extend(varint(0));
+        push_footer(&mut patch, &rom, &[]);
+        assert_eq!(
+            apply_patch(&rom, &patch),
+            Err(PatchError::Malformed("target size too large"))
+        );
+    }
+}
+
+#[test]
+fn test_patch_offset_overflow() {
+    let rom = build_ines(0, 1, 1, 0);
+
+    // BPS 元数据长度接近 usize::MAX
+    let mut patch = b"BPS1".to_vec();
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(usize::MAX - 1));
+    push_footer(&mut patch, &rom, &rom);
+    assert_eq!(apply_patch(&rom, &patch), Err(PatchError::UnexpectedEnd));
+
+    // UPS 跳过的字节数使偏移量溢出
+    let mut patch = b"UPS1".to_vec();
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(rom.len()));
+    patch.extend(varint(usize::MAX - 1));
+    patch.extend([0x01, 0x00]);
+    push_footer(&mut patch, &rom, &rom);
+    assert_eq!(
+        apply_patch(&rom, &patch),
+        Err(PatchError::Malformed("offset overflow"))
+    );
+}
@@ -0,0 +1,99 @@
+use nes_base::Cartridge;
+use nes_cartridge::{
+    BankMap, CartridgeImpl, Mapper, MapperContext, MapperEntry, MapperRegistry, NESFile, RomError,
+    StateError, StateReader, StateWriter,
+};
+
+use super::*;
+
+/// 外部实现的 Mapper：所有 PRG 读取都返回子 Mapper 编号
+struct ConstantMapper {
+    value: u8,
+}
+
+impl Mapper for ConstantMapper {
+    fn cpu_read(&self, _addr: u16) -> u8 {
+        self.value
+    }
+
+    fn cpu_write(&mut self, _addr: u16, _value: u8) {}
+
+    fn ppu_read(&mut self, _addr: u16) -> u8 {
+        0
+    }
+
+    fn ppu_write(&mut self, _addr: u16, _value: u8) {}
+
+    fn bank_map(&self) -> BankMap {
+        BankMap::new()
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.value);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.value = state.read_u8()?;
+        Ok(())
+    }
+}
+
+fn constant_mapper(context: MapperContext) -> Box<dyn Mapper> {
+    Box::new(ConstantMapper {
+        value: 0x80 | context.submapper,
+    })
+}
+
+/// 构造 NES 2.0 文件头的镜像
+fn build_nes2(mapper_id: u8, submapper: u8) -> Vec<u8> {
+    let mut bytes = build_ines(mapper_id, 1, 1, 0);
+    bytes[7] |= 0x08;
+    bytes[8] = submapper << 4;
+    bytes
+}
+
+#[test]
+fn test_registry_external_mapper() {
+    let mut registry = MapperRegistry::builtin();
+    assert!(registry.find(0xF0, 0).is_none());
+    registry.register(MapperEntry::new(0xF0, "Test", constant_mapper));
+
+    let nes = NESFile::new(build_nes2(0xF0, 3)).unwrap();
+    assert_eq!(nes.header().submapper, 3);
+    let cartridge = CartridgeImpl::with_registry(nes, &registry).unwrap();
+    assert_eq!(cartridge.cpu_read(0x8000), 0x83);
+
+    // 内置注册表中不存在
+    let nes = NESFile::new(build_nes2(0xF0, 3)).unwrap();
+    assert!(matches!(
+        CartridgeImpl::new(nes),
+        Err(RomError::UnsupportedMapper { submapper: 3, .. })
+    ));
+}
+
+#[test]
+fn test_registry_submapper_and_override() {
+    let mut registry = MapperRegistry::new();
+    registry.register(
+        MapperEntry::new(0xF1, "Sub 2", |_| Box::new(ConstantMapper { value: 2 })).submapper(2),
+    );
+    assert!(registry.find(0xF1, 1).is_none());
+    assert_eq!(registry.find(0xF1, 2).unwrap().name, "Sub 2");
+
+    registry.register(MapperEntry::new(0xF1, "Generic", constant_mapper));
+    assert_eq!(registry.find(0xF1, 1).unwrap().name, "Generic");
+    // 子 Mapper 完全匹配的项优先
+    assert_eq!(registry.find(0xF1, 2).unwrap().name, "Sub 2");
+
+    // 后注册的项覆盖内置实现
+    let mut registry = MapperRegistry::builtin();
+    registry.register(MapperEntry::new(0, "Custom NROM", constant_mapper));
+    assert_eq!(registry.find(0, 0).unwrap().name, "Custom NROM");
+    let names: Vec<&str> = registry.entries().iter().map(|e| e.name).collect();
+    assert!(names.contains(&"Custom NROM"));
+    assert!(!names.contains(&"NROM"));
+
+    let nes = NESFile::new(build_ines(0, 1, 1, 0)).unwrap();
+    let cartridge = CartridgeImpl::with_registry(nes, &registry).unwrap();
+    assert_eq!(cartridge.cpu_read(0x8000), 0x80);
+}
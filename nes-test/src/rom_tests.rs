@@ -0,0 +1,240 @@
+use nes_base::{Cartridge, Mirroring};
+use nes_cartridge::{CartridgeImpl, NESFile, Region, RomError, RomHash, board_mapper_id};
+
+use super::*;
+
+#[test]
+fn test_rom_missing_file() {
+    let result = NESFile::from_file("testfiles/not_exists.nes");
+    assert!(matches!(result, Err(RomError::Io { .. })));
+}
+
+#[test]
+fn test_rom_invalid_magic() {
+    let mut bytes = build_ines(0, 1, 1, 0);
+    bytes[0] = b'X';
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::InvalidMagic(_))
+    ));
+}
+
+#[test]
+fn test_rom_truncated() {
+    assert!(matches!(
+        NESFile::new(vec![0x4E, 0x45, 0x53]),
+        Err(RomError::Truncated { expected: 16, .. })
+    ));
+
+    let mut bytes = build_ines(0, 2, 1, 0);
+    bytes.truncate(bytes.len() - 1);
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::Truncated { expected, .. }) if expected == 0x10 + 0x8000 + 0x2000
+    ));
+
+    // 声明了 Trainer 但数据不足
+    let bytes = build_ines(0, 1, 1, 0b0000_0100);
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn test_rom_zero_prg_banks() {
+    let bytes = build_ines(0, 0, 1, 0);
+    assert!(matches!(NESFile::new(bytes), Err(RomError::NoPrgRom)));
+}
+
+#[test]
+fn test_rom_unsupported_mapper() {
+    let nes = NESFile::new(build_ines(0xF0, 1, 1, 0)).unwrap();
+    assert!(matches!(
+        CartridgeImpl::new(nes),
+        Err(RomError::UnsupportedMapper {
+            mapper_id: 0xF0,
+            board: None,
+            ..
+        })
+    ));
+
+    // 诊断信息包含板名与支持的 Mapper
+    let nes = NESFile::new(build_ines(4, 1, 1, 0)).unwrap();
+    let Err(e) = CartridgeImpl::new(nes) else {
+        panic!("mapper 4 should be unsupported");
+    };
+    let message = e.to_string();
+    assert!(message.starts_with("unsupported mapper 4.0 (MMC3 (TxROM))"));
+    assert!(message.contains("0 (NROM)"));
+    assert!(message.contains("69 (Sunsoft FME-7)"));
+}
+
+#[test]
+fn test_rom_trainer_mapped_to_prg_ram() {
+    let mut bytes = build_ines(0, 1, 1, 0b0000_0100);
+    let trainer: Vec<u8> = (0..0x200).map(|i| (i % 251) as u8).collect();
+    bytes.splice(16..16, trainer.iter().copied());
+
+    let nes = NESFile::new(bytes).unwrap();
+    assert_eq!(nes.trainer_rom().as_deref(), Some(trainer.as_slice()));
+    // PRG-ROM 紧跟在 Trainer 之后
+    assert_eq!(nes.prg_rom()[0], 0);
+
+    let cartridge = CartridgeImpl::new(nes).unwrap();
+    assert!(!cartridge.has_battery_backed());
+    for (i, &value) in trainer.iter().enumerate() {
+        assert_eq!(cartridge.cpu_read(0x7000 + i as u16), value);
+    }
+    assert_eq!(cartridge.cpu_read(0x6FFF), 0);
+    assert_eq!(cartridge.cpu_read(0x7200), 0);
+}
+
+#[test]
+fn test_rom_hash() {
+    assert_eq!(RomHash::new(b"123456789").crc32, 0xCBF4_3926);
+    assert_eq!(
+        RomHash::new(b"abc").sha1_hex(),
+        "A9993E364706816ABA3E25717850C26C9CD0D89D"
+    );
+    // 跨越两个分块的消息
+    assert_eq!(
+        RomHash::new(&[b'a'; 100]).sha1_hex(),
+        "7F9000257A4918D7072655EA468540CDCBD42E0C"
+    );
+}
+
+#[test]
+fn test_rom_database_lookup() {
+    let nes = NESFile::from_file("testfiles/nestest.nes").unwrap();
+    assert_eq!(nes.hash().crc32, 0x158B_0388);
+    let game = nes.game().unwrap();
+    assert_eq!(game.title, "nestest");
+    assert_eq!(game.region, Region::Ntsc);
+
+    let nes = NESFile::from_file("testfiles/Super_mario_brothers.nes").unwrap();
+    let game = nes.game().unwrap();
+    assert_eq!(game.title, "Super Mario Bros.");
+    assert_eq!(game.crc32, nes.hash().crc32);
+    assert_eq!(game.sha1, "FACEE9C577A5262DBE33AC4930BB0B58C8C037F7");
+    assert_eq!(game.mapper_id, 0);
+    assert!(matches!(game.mirroring, Mirroring::Vertical));
+    assert!(!game.has_battery_backed);
+
+    // 未收录的 ROM 保留原始文件头
+    let nes = NESFile::new(build_ines(2, 2, 0, 0b0000_0011)).unwrap();
+    assert!(nes.game().is_none());
+    assert_eq!(nes.header().mapper_id, 2);
+    assert!(nes.header().has_battery_backed);
+}
+
+#[test]
+fn test_rom_database_header_correction() {
+    // 文件头错误地声明了水平镜像、电池与 Mapper 1
+    let mut bytes = std::fs::read("testfiles/Super_mario_brothers.nes").unwrap();
+    bytes[6] = 0x12;
+    bytes[7] = 0x00;
+    let nes = NESFile::new(bytes).unwrap();
+    assert_eq!(nes.game().unwrap().title, "Super Mario Bros.");
+    assert_eq!(nes.header().mapper_id, 0);
+    assert!(matches!(nes.header().mirroring, Mirroring::Vertical));
+    assert!(!nes.header().has_battery_backed);
+}
+
+#[test]
+fn test_rom_database_corrects_cartridge() {
+    // 文件头错误地声明为带电池的 AxROM、水平镜像，卡带应按数据库以 NROM 运行
+    let mut bytes = std::fs::read("testfiles/Super_mario_brothers.nes").unwrap();
+    bytes[6] = 0x72;
+    bytes[7] = 0x00;
+    let mut cartridge = CartridgeImpl::new(NESFile::new(bytes).unwrap()).unwrap();
+    assert!(!cartridge.has_battery_backed());
+    assert!(matches!(cartridge.mirroring(), Mirroring::Vertical));
+
+    // AxROM 会把这次写入当作单屏镜像选择，NROM 忽略
+    cartridge.cpu_write(0x8000, 0x10);
+    assert!(matches!(cartridge.mirroring(), Mirroring::Vertical));
+}
+
+/// 构造一个 UNIF 文件，chunks 为 (ID, 数据)
+fn build_unif(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut bytes = b"UNIF".to_vec();
+    bytes.extend(7u32.to_le_bytes());
+    bytes.resize(32, 0);
+    for (id, data) in chunks {
+        bytes.extend_from_slice(*id);
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
+
+#[test]
+fn test_rom_unif() {
+    let ines = build_ines(2, 4, 0, 0);
+    let bytes = build_unif(&[
+        (b"MAPR", b"NES-UNROM\0"),
+        (b"NAME", b"Homebrew\0"),
+        (b"PRG1", &ines[0x10 + 0x8000..]),
+        (b"PRG0", &ines[0x10..0x10 + 0x8000]),
+        (b"MIRR", &[1]),
+        (b"BATR", &[1]),
+    ]);
+    let nes = NESFile::new(bytes).unwrap();
+    assert_eq!(nes.board(), Some("NES-UNROM"));
+    assert_eq!(nes.name(), Some("Homebrew"));
+    assert_eq!(nes.header().mapper_id, 2);
+    assert_eq!(nes.header().prg_banks, 4);
+    assert_eq!(nes.header().chr_banks, 0);
+    assert!(matches!(nes.header().mirroring, Mirroring::Vertical));
+    assert!(nes.header().has_battery_backed);
+    // 与 iNES 文件得到相同的数据
+    assert_eq!(nes.prg_rom(), NESFile::new(ines).unwrap().prg_rom());
+
+    let mut cartridge = CartridgeImpl::new(nes).unwrap();
+    assert_eq!(cartridge.cpu_read(0xC000), 3);
+    cartridge.cpu_write(0x8000, 2);
+    assert_eq!(cartridge.cpu_read(0x8000), 2);
+}
+
+#[test]
+fn test_rom_unif_single_screen() {
+    // 8KB PRG-ROM 被重复为 16KB
+    let bytes = build_unif(&[
+        (b"MAPR", b"NES-AOROM\0"),
+        (b"PRG0", &[0xEA; 0x2000]),
+        (b"MIRR", &[2]),
+    ]);
+    let nes = NESFile::new(bytes).unwrap();
+    assert_eq!(nes.header().mapper_id, 7);
+    assert_eq!(nes.prg_rom().len(), 0x4000);
+    assert!(matches!(
+        nes.header().mirroring,
+        Mirroring::SingleScreenLower
+    ));
+}
+
+#[test]
+fn test_rom_unif_errors() {
+    assert_eq!(board_mapper_id("HVC-CNROM"), Some(3));
+    assert_eq!(board_mapper_id("UNL-UNKNOWN"), None);
+
+    let bytes = build_unif(&[(b"MAPR", b"UNL-UNKNOWN\0"), (b"PRG0", &[0; 0x4000])]);
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::UnsupportedBoard(board)) if board == "UNL-UNKNOWN"
+    ));
+
+    let bytes = build_unif(&[(b"PRG0", &[0; 0x4000])]);
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::MissingChunk("MAPR"))
+    ));
+
+    let mut bytes = build_unif(&[(b"MAPR", b"NES-NROM\0"), (b"PRG0", &[0; 0x4000])]);
+    bytes.truncate(bytes.len() - 1);
+    assert!(matches!(
+        NESFile::new(bytes),
+        Err(RomError::Truncated { .. })
+    ));
+}
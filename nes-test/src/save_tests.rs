@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use nes_base::Cartridge;
+use nes_cartridge::{CartridgeImpl, NESFile, sav_path};
+
+use super::*;
+
+fn new_battery_cartridge() -> CartridgeImpl {
+    let nes = NESFile::new(build_ines(0, 1, 1, 0b0000_0010)).unwrap();
+    CartridgeImpl::new(nes).unwrap()
+}
+
+#[test]
+fn test_sav_path() {
+    assert_eq!(
+        sav_path(Path::new("roms/Zelda.nes")),
+        Path::new("roms/Zelda.sav")
+    );
+}
+
+#[test]
+fn test_sram_export_import() {
+    let mut cartridge = new_battery_cartridge();
+    assert!(cartridge.has_battery_backed());
+    assert!(!cartridge.is_sram_dirty());
+
+    cartridge.cpu_write(0x6000, 0x12);
+    cartridge.cpu_write(0x7FFF, 0x34);
+    assert!(cartridge.is_sram_dirty());
+
+    let data = cartridge.export_sram().unwrap();
+    assert_eq!(data.len(), 0x2000);
+    assert_eq!(data[0], 0x12);
+    assert_eq!(data[0x1FFF], 0x34);
+
+    let mut other = new_battery_cartridge();
+    other.import_sram(&data);
+    assert!(!other.is_sram_dirty());
+    assert_eq!(other.cpu_read(0x6000), 0x12);
+    assert_eq!(other.cpu_read(0x7FFF), 0x34);
+}
+
+#[test]
+fn test_sram_without_battery() {
+    let nes = NESFile::new(build_ines(0, 1, 1, 0)).unwrap();
+    let cartridge = CartridgeImpl::new(nes).unwrap();
+    assert!(!cartridge.has_battery_backed());
+    assert!(cartridge.export_sram().is_none());
+}
+
+#[test]
+fn test_sav_file_roundtrip() {
+    let path = std::env::temp_dir().join(format!("nes-test-{}.sav", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut cartridge = new_battery_cartridge();
+    // 存档文件不存在时不报错
+    assert!(!cartridge.load_sav_file(&path).unwrap());
+
+    cartridge.cpu_write(0x6123, 0xAB);
+    cartridge.write_sav_file(&path).unwrap();
+    assert!(!cartridge.is_sram_dirty());
+
+    let mut other = new_battery_cartridge();
+    assert!(other.load_sav_file(&path).unwrap());
+    assert_eq!(other.cpu_read(0x6123), 0xAB);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_sram_dirty_tracks_mapper_writes() {
+    let nes = NESFile::new(build_ines(5, 2, 1, 0b0000_0010)).unwrap();
+    let mut cartridge = CartridgeImpl::new(nes).unwrap();
+
+    // 写保护时 Mapper 拦下写入，存档不需要写回
+    cartridge.cpu_write(0x6000, 0x12);
+    assert!(!cartridge.is_sram_dirty());
+
+    // 解除写保护，$8000 映射 PRG-RAM 的第 1 个 bank
+    cartridge.cpu_write(0x5102, 0x02);
+    cartridge.cpu_write(0x5103, 0x01);
+    cartridge.cpu_write(0x5100, 0x03);
+    cartridge.cpu_write(0x5114, 0x01);
+    cartridge.cpu_write(0x8000, 0x34);
+    assert!(cartridge.is_sram_dirty());
+    assert_eq!(cartridge.export_sram().unwrap()[0x2000], 0x34);
+}
+
+#[test]
+fn test_sav_file_replaces_existing_save() {
+    let path = std::env::temp_dir().join(format!("nes-test-replace-{}.sav", std::process::id()));
+    std::fs::write(&path, [0xFF; 4]).unwrap();
+
+    let mut cartridge = new_battery_cartridge();
+    cartridge.cpu_write(0x6000, 0x56);
+    cartridge.write_sav_file(&path).unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data.len(), 0x2000);
+    assert_eq!(data[0], 0x56);
+    // 临时文件已经被重命名为存档
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    assert!(!Path::new(&tmp_path).exists());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,135 @@
+use nes_base::Cartridge;
+use nes_cartridge::{BankBus, BankMemory, CartridgeImpl, NESFile, StateError};
+
+use super::*;
+
+fn new_cartridge(mapper_id: u8, prg_banks: u8, chr_banks: u8, flags6: u8) -> CartridgeImpl {
+    let nes = NESFile::new(build_ines(mapper_id, prg_banks, chr_banks, flags6)).unwrap();
+    CartridgeImpl::new(nes).unwrap()
+}
+
+#[test]
+fn test_bank_map() {
+    let mut cartridge = new_cartridge(2, 4, 0, 0b0000_0001);
+    cartridge.cpu_write(0x8000, 2);
+
+    let map = cartridge.bank_map();
+    let mapping = map.find(BankBus::Cpu, 0x9234).unwrap();
+    assert_eq!(mapping.start, 0x8000);
+    assert_eq!(mapping.size, 0x4000);
+    assert_eq!(mapping.memory, BankMemory::PrgRom);
+    assert_eq!(mapping.bank, 2);
+    assert_eq!(map.find(BankBus::Cpu, 0xC000).unwrap().bank, 3);
+    assert_eq!(
+        map.find(BankBus::Ppu, 0x0000).unwrap().memory,
+        BankMemory::ChrRam
+    );
+    // 垂直镜像
+    assert_eq!(map.find(BankBus::Ppu, 0x2C00).unwrap().bank, 1);
+    assert!(map.find(BankBus::Cpu, 0x6000).is_none());
+
+    let text = map.to_string();
+    assert!(text.contains("CPU $8000-$BFFF: PRG-ROM #2 (16KB)"));
+    assert!(text.contains("PPU $2400-$27FF: VRAM #1 (1KB)"));
+}
+
+#[test]
+fn test_bank_map_registers() {
+    let mut cartridge = new_cartridge(21, 8, 4, 0);
+    cartridge.cpu_write(0xF000, 0x0E);
+    cartridge.cpu_write(0xF002, 0x0F);
+
+    let map = cartridge.bank_map();
+    assert!(map.registers.contains(&("IRQ latch", 0xFE)));
+    assert_eq!(map.find(BankBus::Cpu, 0xE000).unwrap().bank, 15);
+}
+
+#[test]
+fn test_save_load_state() {
+    let mut cartridge = new_cartridge(2, 4, 0, 0b0000_0010);
+    cartridge.cpu_write(0x8000, 1);
+    cartridge.cpu_write(0x6000, 0x12);
+    cartridge.ppu_write(0x0123, 0x45);
+    let state = cartridge.save_state();
+
+    cartridge.cpu_write(0x8000, 3);
+    cartridge.cpu_write(0x6000, 0x00);
+    cartridge.ppu_write(0x0123, 0x00);
+    cartridge.load_state(&state).unwrap();
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0x6000), 0x12);
+    assert_eq!(cartridge.ppu_read(0x0123), 0x45);
+
+    // 同一个游戏的另一个实例也能载入
+    let mut other = new_cartridge(2, 4, 0, 0b0000_0010);
+    other.load_state(&state).unwrap();
+    assert_eq!(other.cpu_read(0x8000), 1);
+    assert_eq!(other.save_state(), state);
+}
+
+#[test]
+fn test_save_load_state_mmc5() {
+    let mut cartridge = new_cartridge(5, 8, 8, 0);
+    cartridge.cpu_write(0x5100, 3);
+    cartridge.cpu_write(0x5114, 0x85);
+    cartridge.cpu_write(0x5205, 7);
+    cartridge.cpu_write(0x5206, 9);
+    cartridge.cpu_write(0x5C00, 0x77);
+    let state = cartridge.save_state();
+
+    let mut other = new_cartridge(5, 8, 8, 0);
+    other.load_state(&state).unwrap();
+    assert_eq!(other.cpu_read(0x8000), cartridge.cpu_read(0x8000));
+    assert_eq!(other.cpu_read(0x5205), 63);
+    assert_eq!(other.save_state(), state);
+}
+
+#[test]
+fn test_load_state_errors() {
+    let cartridge = new_cartridge(2, 4, 0, 0);
+    let state = cartridge.save_state();
+
+    let mut other = new_cartridge(3, 1, 4, 0);
+    assert_eq!(
+        other.load_state(&state),
+        Err(StateError::MapperMismatch {
+            expected: 3,
+            actual: 2
+        })
+    );
+
+    let mut same = new_cartridge(2, 4, 0, 0);
+    assert_eq!(
+        same.load_state(&state[..state.len() - 1]),
+        Err(StateError::UnexpectedEnd)
+    );
+    let mut extended = state.clone();
+    extended.push(0);
+    assert_eq!(same.load_state(&extended), Err(StateError::TrailingData(1)));
+
+    // CHR bank 超出 CHR-ROM 大小
+    let mut cnrom = new_cartridge(3, 1, 4, 0);
+    let mut state = cnrom.save_state();
+    state[1] = 4;
+    assert_eq!(
+        cnrom.load_state(&state),
+        Err(StateError::InvalidValue("CHR bank"))
+    );
+    assert_eq!(cnrom.ppu_read(0x0000), 0);
+}
+
+#[test]
+fn test_load_state_error_keeps_state() {
+    let mut cartridge = new_cartridge(2, 4, 0, 0b0000_0010);
+    cartridge.cpu_write(0x8000, 2);
+    cartridge.cpu_write(0x6000, 0x12);
+    let mut state = cartridge.save_state();
+
+    // Mapper 寄存器有效，但 PRG-RAM 的数据被截断
+    cartridge.cpu_write(0x8000, 1);
+    cartridge.cpu_write(0x6000, 0x34);
+    state.truncate(state.len() - 1);
+    assert!(cartridge.load_state(&state).is_err());
+    assert_eq!(cartridge.cpu_read(0x8000), 1);
+    assert_eq!(cartridge.cpu_read(0x6000), 0x34);
+}
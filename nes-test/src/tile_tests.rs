@@ -91,8 +91,10 @@ fn render_pattern_table(tile_reader: &TileReader, filename: &str) {
 
 #[test]
 fn test_tile_get_pixel() {
-    let nes = nes_cartridge::NESFile::from_file("testfiles/Super_mario_brothers.nes");
-    let cartridge = Rc::new(RefCell::new(nes_cartridge::CartridgeImpl::new(nes)));
+    let nes = nes_cartridge::NESFile::from_file("testfiles/Super_mario_brothers.nes").unwrap();
+    let cartridge = Rc::new(RefCell::new(
+        nes_cartridge::CartridgeImpl::new(nes).unwrap(),
+    ));
     let pattern_tables_reader = Rc::new(RefCell::new(PatternTablesAdapterForPpuBus(cartridge)));
     let tile_reader_1 = TileReader::new(pattern_tables_reader.clone(), 0x0000);
     let tile_reader_2 = TileReader::new(pattern_tables_reader.clone(), 0x1000);
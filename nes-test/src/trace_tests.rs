@@ -0,0 +1,162 @@
+use std::io::Write;
+
+use nes_base::CpuState;
+use nes_cpu::{Tracer, disassemble, format_trace_line};
+
+use super::*;
+
+/// 共享的日志缓冲区，测试结束后读取 CPU 写入的内容
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 构造加载 nestest 的主板，同时返回具体类型的 CPU 以便设置跟踪器
+fn new_traced_board() -> (BoardImpl, Rc<RefCell<CpuImpl>>) {
+    traced_board_from_file("testfiles/nestest.nes")
+}
+
+fn traced_board_from_file(path: &str) -> (BoardImpl, Rc<RefCell<CpuImpl>>) {
+    let nes = nes_cartridge::NESFile::from_file(path).unwrap();
+    let cartridge = nes_cartridge::CartridgeImpl::new(nes).unwrap();
+    let cpu = Rc::new(RefCell::new(CpuImpl::new()));
+    let board = BoardImpl {
+        joypad1: None,
+        joypad2: None,
+        cheats: Default::default(),
+        audio: Default::default(),
+        cpu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        ppu_bus: Rc::new(RefCell::new(BusImpl::new())),
+        cpu: cpu.clone(),
+        ppu: Rc::new(RefCell::new(MockPPU)),
+        apu: Rc::new(RefCell::new(MockAPU)),
+        ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x800))),
+        ppu_name_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x1000))),
+        ppu_palettes_tables_ram: Rc::new(RefCell::new(nes_ram::RamImpl::new(0x20))),
+        cartridge: Rc::new(RefCell::new(cartridge)),
+    }
+    .init();
+    (board, cpu)
+}
+
+fn run_instruction(board: &BoardImpl) {
+    loop {
+        board.cpu.borrow_mut().clock();
+        if board.cpu.borrow().dump_state().remaining_cycles == 0 {
+            break;
+        }
+    }
+}
+
+#[test]
+fn test_trace_matches_nestest_log() {
+    let (mut board, cpu) = new_traced_board();
+    let expected = std::fs::read_to_string("testfiles/nestest.txt").unwrap();
+
+    let buffer = SharedBuffer::default();
+    cpu.borrow_mut()
+        .set_tracer(Some(Tracer::new(buffer.clone())));
+    board.reset();
+    board.cpu.borrow_mut().set_reg_pc(0xc000);
+
+    for _ in expected.lines() {
+        run_instruction(&board);
+    }
+
+    let actual = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    for (line_no, (actual, expected)) in actual.lines().zip(expected.lines()).enumerate() {
+        assert_eq!(actual, expected, "line {}", line_no + 1);
+    }
+    assert_eq!(actual.lines().count(), expected.lines().count());
+}
+
+#[test]
+fn test_trace_ppu_position_source_and_disable() {
+    let (mut board, cpu) = new_traced_board();
+    let buffer = SharedBuffer::default();
+    cpu.borrow_mut().set_tracer(Some(
+        Tracer::new(buffer.clone()).with_ppu_position(|| (241, 5)),
+    ));
+    board.reset();
+    board.cpu.borrow_mut().set_reg_pc(0xc000);
+
+    run_instruction(&board);
+    cpu.borrow_mut().set_tracer(None);
+    run_instruction(&board);
+
+    let actual = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    assert_eq!(actual.lines().count(), 1);
+    assert!(actual.contains("PPU:241,  5 CYC:7"), "{actual}");
+}
+
+#[test]
+fn test_format_trace_line_unofficial() {
+    let board = new_board();
+    board.cpu_bus.borrow_mut().write(0x0300, 0x04);
+    board.cpu_bus.borrow_mut().write(0x0301, 0xa9);
+    let dis = disassemble(&*board.cpu_bus.borrow(), 0x0300, 0, 0);
+    let state = CpuState {
+        total_cycles: 14579,
+        remaining_cycles: 0,
+        reg_a: 0xaa,
+        reg_x: 0x97,
+        reg_y: 0x4e,
+        reg_sp: 0xf9,
+        reg_pc: 0x0300,
+        reg_status: 0xef.into(),
+        jammed: false,
+        nmi_count: 0,
+    };
+    assert_eq!(
+        format_trace_line(&dis, &state, 128, 89),
+        "0300  04 A9    *NOP $A9 = 00                    A:AA X:97 Y:4E P:EF SP:F9 PPU:128, 89 CYC:14579"
+    );
+}
+
+#[test]
+fn test_trace_does_not_change_emulation() {
+    // N163: 写入两个字节到内部 RAM 后重新从地址 0 读出，读取 $4800 会让地址自增
+    let mut code = vec![];
+    for (addr, value) in [
+        (0xF800u16, 0x80u8),
+        (0x4800, 0x11),
+        (0x4800, 0x22),
+        (0xF800, 0x80),
+    ] {
+        code.extend([0xa9, value, 0x8d, addr as u8, (addr >> 8) as u8]); // LDA #value; STA addr
+    }
+    code.extend([0xad, 0x00, 0x48, 0x85, 0x00]); // LDA $4800; STA $00
+    code.extend([0xad, 0x00, 0x48, 0x85, 0x01]); // LDA $4800; STA $01
+    let end = 0xe000 + code.len() as u16;
+    code.extend([0x4c, end as u8, (end >> 8) as u8]); // JMP *
+
+    let mut rom = build_ines(19, 4, 2, 0);
+    let last_bank = 16 + 4 * 0x4000 - 0x2000;
+    rom[last_bank..last_bank + code.len()].copy_from_slice(&code);
+    rom[last_bank + 0x1ffc..last_bank + 0x1ffe].copy_from_slice(&[0x00, 0xe0]);
+    let path = std::env::temp_dir().join(format!("nes-test-trace-{}.nes", std::process::id()));
+    std::fs::write(&path, rom).unwrap();
+    let (mut board, cpu) = traced_board_from_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    let buffer = SharedBuffer::default();
+    cpu.borrow_mut()
+        .set_tracer(Some(Tracer::new(buffer.clone())));
+    for _ in 0..200 {
+        board.clock();
+    }
+
+    assert_eq!(board.ram.borrow().read(0x00), 0x11);
+    assert_eq!(board.ram.borrow().read(0x01), 0x22);
+    let actual = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    assert!(actual.contains("LDA $4800 = FF"), "{actual}");
+}